@@ -1,96 +1,1146 @@
-use crate::models::ContentProcessingResult;
-use crate::providers::registry;
+use crate::config::Config;
+use crate::models::{
+    ChunkStrategy, ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, EmbeddedChunk,
+    EmbeddedContentResult, JobPhase, JobProgress, JobState, JobStatus, ProcessingOptions, ProcessingStatus, SupportedType,
+};
+use crate::providers::fixed_width::FixedWidthProvider;
+use crate::providers::gzip;
+use crate::providers::limits::{LimitExceeded, ResourceLimits};
+use crate::providers::registry::{self, ProviderFactory};
+use crate::providers::traced::TracedProvider;
+use crate::providers::{fill_default_metadata, ids, Encrypted};
+use crate::services::model_registry::ModelError;
+use crate::services::{postprocess, JobStore};
 use axum::{
-    extract::{Multipart, Path as AxumPath},
+    extract::{
+        multipart::{Field, MultipartError},
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Multipart, Path as AxumPath, Query,
+    },
     http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::post,
-    Json, Router,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Extension, Json, Router,
 };
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+#[cfg(test)]
+#[path = "content_tests.rs"]
+mod tests;
 
 pub fn routes() -> Router {
     Router::new()
         .route("/process", post(process_file))
         .route("/process/:content_type", post(process_file_with_type))
+        .route("/embed", post(embed_file))
+        .route("/embed/:content_type", post(embed_file_with_type))
+        .route("/metadata", post(extract_metadata))
+        .route("/types", get(supported_types))
+        .route("/jobs", post(submit_job))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/events", get(job_events))
+        .route("/stream", get(stream_content))
 }
 
-async fn process_file(mut multipart: Multipart) -> Result<Json<ContentProcessingResult>, AppError> {
-    while let Some(field) = multipart.next_field().await? {
-        if field.name() == Some("file") {
-            let file_name = field.file_name()
-                .ok_or_else(|| anyhow::anyhow!("No filename provided"))?
-                .to_string();
-            
-            let extension = Path::new(&file_name)
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .ok_or_else(|| anyhow::anyhow!("No file extension"))?;
-            
-            let (_content_type, provider) = registry::get_provider_by_extension(extension)
-                .ok_or_else(|| anyhow::anyhow!("Unsupported file type: {}", extension))?;
-            
-            let temp_path = format!("/tmp/{}", file_name);
-            let mut file = fs::File::create(&temp_path).await?;
-            
-            let bytes = field.bytes().await?.to_vec();
-            file.write_all(&bytes).await?;
-            file.flush().await?;
-            
-            let result = provider.process_content(Path::new(&temp_path)).await?;
-            
-            fs::remove_file(&temp_path).await.ok();
-            
-            return Ok(Json(result));
-        }
-    }
-    
-    Err(anyhow::anyhow!("No file provided").into())
+async fn process_file(
+    Extension(config): Extension<Arc<Config>>,
+    Query(options): Query<ProcessingOptions>,
+    mut multipart: Multipart,
+) -> Result<Json<ProcessResponse>, AppError> {
+    let uploads = read_uploads(&config, &mut multipart, None, options, usize::MAX).await?;
+
+    Ok(Json(process_batch(&config, uploads).await?))
 }
 
 async fn process_file_with_type(
     AxumPath(content_type): AxumPath<String>,
+    Extension(config): Extension<Arc<Config>>,
+    Query(options): Query<ProcessingOptions>,
+    mut multipart: Multipart,
+) -> Result<Json<ProcessResponse>, AppError> {
+    let forced = forced_provider(&content_type)?;
+    let uploads = read_uploads(&config, &mut multipart, Some(forced), options, usize::MAX).await?;
+
+    Ok(Json(process_batch(&config, uploads).await?))
+}
+
+/// A single result for an upload with one `file` field, and one result per
+/// file, in upload order, when there are several.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ProcessResponse {
+    Single(Box<ContentProcessingResult>),
+    Batch(Vec<ContentProcessingResult>),
+}
+
+/// Processes every file of the upload, up to `config.batch_concurrency` at a
+/// time and each under one of the server-wide `config.dispatch_permits`. A
+/// lone file fails the request as before; in a batch, a file that is rejected
+/// or fails comes back as a `Failed` result and the others carry on.
+async fn process_batch(config: &Config, uploads: Vec<FileUpload>) -> Result<ProcessResponse, AppError> {
+    if uploads.len() <= 1 {
+        let upload = match uploads.into_iter().next() {
+            Some(upload) => upload.map_err(|(_, err)| err)?,
+            None => return Err(AppError::MissingFile),
+        };
+        return Ok(ProcessResponse::Single(Box::new(process_and_embed(config, &upload).await?)));
+    }
+
+    let mut results: Vec<(usize, ContentProcessingResult)> = stream::iter(uploads.into_iter().enumerate())
+        .map(|(index, upload)| async move {
+            let result = match upload {
+                Ok(upload) => process_and_embed(config, &upload)
+                    .await
+                    .unwrap_or_else(|err| failed_result(upload.file_name.clone(), &AppError::from(err))),
+                Err((file_name, err)) => failed_result(file_name, &err),
+            };
+            (index, result)
+        })
+        .buffer_unordered(config.batch_concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by_key(|(index, _)| *index);
+
+    Ok(ProcessResponse::Batch(results.into_iter().map(|(_, result)| result).collect()))
+}
+
+/// The result reported for one file of a batch that could not be processed,
+/// with the error code a single upload would have failed with.
+fn failed_result(file_name: String, err: &AppError) -> ContentProcessingResult {
+    let mut additional = HashMap::new();
+    additional.insert("error_code".to_string(), json!(err.status_and_code().1));
+
+    ContentProcessingResult {
+        success: false,
+        status: ProcessingStatus::Failed,
+        chunks: Vec::new(),
+        metadata: ContentMetadata {
+            content_type: ContentType::Unknown,
+            file_name: Some(file_name),
+            file_size: None,
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional,
+        },
+        error: Some(err.message()),
+    }
+}
+
+/// Processes the upload like `/content/process` and embeds every chunk,
+/// returning the vectors alongside the chunks in one round trip.
+async fn embed_file(
+    Extension(config): Extension<Arc<Config>>,
+    Query(options): Query<ProcessingOptions>,
+    mut multipart: Multipart,
+) -> Result<Json<EmbeddedContentResult>, AppError> {
+    let upload = read_upload(&config, &mut multipart, None, options).await?;
+
+    Ok(Json(embed_upload(&config, &upload).await?))
+}
+
+async fn embed_file_with_type(
+    AxumPath(content_type): AxumPath<String>,
+    Extension(config): Extension<Arc<Config>>,
+    Query(options): Query<ProcessingOptions>,
+    mut multipart: Multipart,
+) -> Result<Json<EmbeddedContentResult>, AppError> {
+    let forced = forced_provider(&content_type)?;
+    let upload = read_upload(&config, &mut multipart, Some(forced), options).await?;
+
+    Ok(Json(embed_upload(&config, &upload).await?))
+}
+
+/// The content types this node can process and the extensions mapping to each.
+async fn supported_types() -> Json<Vec<SupportedType>> {
+    Json(registry::supported_types())
+}
+
+/// Reads only the upload's `ContentMetadata`, without chunking it. Providers
+/// take their metadata from headers and document properties where the format
+/// has them, so this stays cheap for large files.
+async fn extract_metadata(
+    Extension(config): Extension<Arc<Config>>,
+    Query(options): Query<ProcessingOptions>,
+    mut multipart: Multipart,
+) -> Result<Json<ContentMetadata>, AppError> {
+    let upload = read_upload(&config, &mut multipart, None, options).await?;
+
+    let temp_file = stage_upload(&config, &upload).await?;
+    let mut metadata = within_timeout(&config, upload.provider.to_metadata(temp_file.path())).await?;
+    fill_default_metadata(&mut metadata, &upload.file_name);
+
+    if let Some(user_metadata) = &upload.metadata {
+        metadata.additional.insert("user_metadata".to_string(), Value::Object(user_metadata.clone()));
+    }
+
+    Ok(Json(metadata))
+}
+
+/// Accepts an upload for background processing and returns its job immediately.
+async fn submit_job(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(jobs): Extension<Arc<JobStore>>,
+    Query(options): Query<ProcessingOptions>,
     mut multipart: Multipart,
-) -> Result<Json<ContentProcessingResult>, AppError> {
-    let content_type = serde_json::from_str(&format!("\"{}\"", content_type.to_uppercase()))?;
-    let provider = registry::get_provider(&content_type)
-        .ok_or_else(|| anyhow::anyhow!("Unsupported content type: {:?}", content_type))?;
-    
-    while let Some(field) = multipart.next_field().await? {
-        if field.name() == Some("file") {
-            let file_name = field.file_name()
-                .unwrap_or("upload")
-                .to_string();
-            
-            let temp_path = format!("/tmp/{}", file_name);
-            let mut file = fs::File::create(&temp_path).await?;
-            
-            let bytes = field.bytes().await?.to_vec();
-            file.write_all(&bytes).await?;
-            file.flush().await?;
-            
-            let result = provider.process_content(Path::new(&temp_path)).await?;
-            
-            fs::remove_file(&temp_path).await.ok();
-            
-            return Ok(Json(result));
-        }
-    }
-    
-    Err(anyhow::anyhow!("No file provided").into())
-}
-
-pub struct AppError(anyhow::Error);
+) -> Result<(StatusCode, Json<JobState>), AppError> {
+    let upload = read_upload(&config, &mut multipart, None, options).await?;
+    let job = jobs.create();
+    let job_id = job.job_id;
+
+    tokio::spawn(async move {
+        jobs.set_status(&job_id, JobStatus::Running);
+
+        let outcome = process_reporting(&config, &upload, |progress| jobs.set_progress(&job_id, progress)).await;
+        jobs.finish(&job_id, outcome);
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn get_job(
+    AxumPath(job_id): AxumPath<Uuid>,
+    Extension(jobs): Extension<Arc<JobStore>>,
+) -> Result<Json<JobState>, AppError> {
+    jobs.get(&job_id)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown job: {}", job_id)))
+}
+
+/// Where `job_events` is in a job's event stream.
+enum JobEvents {
+    /// Waiting for the job to change; the first state is sent straight away.
+    Watching { updates: tokio::sync::watch::Receiver<JobState>, first: bool },
+    /// The job has finished and its last progress was sent; holds the `done` payload.
+    Finished(Value),
+    Closed,
+}
+
+/// Follows a job as server-sent events: a `progress` event with
+/// `{processed_chunks, total_estimate, phase}` whenever the job changes, and a
+/// final `done` event with the job's `status` and `error`. A job that already
+/// finished sends its last progress and `done` straight away.
+async fn job_events(
+    AxumPath(job_id): AxumPath<Uuid>,
+    Extension(jobs): Extension<Arc<JobStore>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    let updates = jobs
+        .subscribe(&job_id)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown job: {}", job_id)))?;
+
+    let events = stream::unfold(JobEvents::Watching { updates, first: true }, |state| async move {
+        match state {
+            JobEvents::Watching { mut updates, first } => {
+                if !first && updates.changed().await.is_err() {
+                    return None;
+                }
+                let job = updates.borrow_and_update().clone();
+                let event = Event::default().event("progress").json_data(&job.progress);
+                let next = match job.status {
+                    JobStatus::Done | JobStatus::Failed => {
+                        JobEvents::Finished(json!({ "job_id": job.job_id, "status": job.status, "error": job.error }))
+                    }
+                    JobStatus::Pending | JobStatus::Running => JobEvents::Watching { updates, first: false },
+                };
+                Some((event, next))
+            }
+            JobEvents::Finished(done) => Some((Event::default().event("done").json_data(done), JobEvents::Closed)),
+            JobEvents::Closed => None,
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamQuery {
+    /// Name of the file sent as the first message; its extension picks the
+    /// provider, falling back to the file's magic bytes.
+    file_name: Option<String>,
+    /// Replays a background job's chunks instead of reading an upload.
+    job_id: Option<Uuid>,
+}
+
+/// One JSON text message on `/content/stream`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamMessage<'a> {
+    Chunk { chunk: &'a ContentChunk },
+    /// Sent once, after the last chunk.
+    Metadata { metadata: &'a ContentMetadata },
+    /// Ends the stream early, shaped like an HTTP error body's `error`.
+    Error { error: Value },
+}
+
+/// How often a streamed job is checked while it is still running.
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sends an upload's chunks over a WebSocket, one message each, then its
+/// metadata, then closes. The client sends the file as the first binary
+/// message, or passes `job_id` to replay a job's result once it is done.
+///
+/// The upload goes through the same pipeline as `/content/process`, with the
+/// processing options taken from the query string. Chunks are sent as the
+/// provider extracts them unless an option needs the whole document first.
+async fn stream_content(
+    ws: WebSocketUpgrade,
+    Extension(config): Extension<Arc<Config>>,
+    Extension(jobs): Extension<Arc<JobStore>>,
+    Query(query): Query<StreamQuery>,
+    Query(options): Query<ProcessingOptions>,
+) -> Response {
+    let max_message_size = usize::try_from(config.max_upload_bytes).unwrap_or(usize::MAX);
+
+    ws.max_message_size(max_message_size).on_upgrade(move |mut socket| async move {
+        let outcome = match query.job_id {
+            Some(job_id) => stream_job(&mut socket, &config, &jobs, job_id).await,
+            None => stream_upload(&mut socket, &config, query.file_name.as_deref(), options).await,
+        };
+        if let Err(err) = outcome {
+            let _ = send(&mut socket, &StreamMessage::Error { error: err.body() }).await;
+        }
+        let _ = socket.send(Message::Close(None)).await;
+    })
+}
+
+async fn stream_upload(
+    socket: &mut WebSocket,
+    config: &Config,
+    file_name: Option<&str>,
+    options: ProcessingOptions,
+) -> Result<(), AppError> {
+    let bytes = match socket.recv().await {
+        Some(Ok(Message::Binary(bytes))) => Bytes::from(bytes),
+        Some(Ok(_)) => return Err(AppError::BadRequest("Expected the file as a binary message".to_string())),
+        Some(Err(err)) => return Err(err.into()),
+        None => return Ok(()),
+    };
+    let file_name = file_name.map(sanitize_file_name);
+    let upload = accept_file(config, file_name, None, bytes, None, &options, &None).await?;
+
+    if needs_whole_document(&upload.options) || upload.size() < config.min_file_bytes {
+        let result = process_and_embed(config, &upload).await?;
+        return send_result(socket, &result).await;
+    }
+
+    let temp_file = stage_upload(config, &upload).await?;
+    let _permit = config.dispatch_permits.acquire().await?;
+    let metadata = within_timeout(config, send_streamed(socket, &upload, temp_file.path())).await?;
+    send(socket, &StreamMessage::Metadata { metadata: &metadata }).await
+}
+
+/// Options that need every chunk at once, so a streamed upload using any of
+/// them is processed in full before its chunks are sent.
+fn needs_whole_document(options: &ProcessingOptions) -> bool {
+    options.dedupe || options.summary_chunk || options.prior_chunks.is_some()
+}
+
+/// Sends each chunk as soon as the provider extracts it, and returns the
+/// upload's metadata for the closing message.
+async fn send_streamed(socket: &mut WebSocket, upload: &Upload, file_path: &Path) -> Result<ContentMetadata, AppError> {
+    let mut metadata = upload.provider.to_metadata(file_path).await?;
+    fill_default_metadata(&mut metadata, &upload.file_name);
+
+    let mut results = std::pin::pin!(process_streamed(upload, file_path, metadata.clone()));
+    while let Some(result) = results.next().await {
+        let result = result?;
+        if let Some(warning) = result.metadata.additional.get("embedding_warning") {
+            metadata.additional.insert("embedding_warning".to_string(), warning.clone());
+        }
+        for chunk in &result.chunks {
+            send(socket, &StreamMessage::Chunk { chunk }).await?;
+        }
+    }
+
+    if upload.options.include_raw_metadata {
+        if let Some(raw) = upload.provider.raw_metadata(file_path).await? {
+            metadata.additional.insert("raw_metadata".to_string(), raw);
+        }
+    }
+    if let Some(user_metadata) = &upload.metadata {
+        metadata.additional.insert("user_metadata".to_string(), Value::Object(user_metadata.clone()));
+    }
+
+    Ok(metadata)
+}
+
+/// The provider's chunks as it extracts them, each run through the per-chunk
+/// steps of `process_and_embed`: content hash, embedding and rendering. Each
+/// item is a one-chunk result over `metadata`, so an embedding warning comes
+/// back with the chunk it is about.
+fn process_streamed<'a>(
+    upload: &'a Upload,
+    file_path: &'a Path,
+    metadata: ContentMetadata,
+) -> impl Stream<Item = anyhow::Result<ContentProcessingResult>> + 'a {
+    upload.provider.stream_chunks(file_path).then(move |chunk| {
+        let metadata = metadata.clone();
+        async move {
+            let mut result = ContentProcessingResult {
+                success: true,
+                status: ProcessingStatus::Processed,
+                chunks: vec![chunk?],
+                metadata,
+                error: None,
+            };
+            postprocess::attach_content_hashes(&mut result.chunks);
+            embed_chunks(upload, &mut result).await;
+            postprocess::apply_options(&mut result, &upload.options);
+            Ok(result)
+        }
+    })
+}
+
+async fn send_result(socket: &mut WebSocket, result: &ContentProcessingResult) -> Result<(), AppError> {
+    for chunk in &result.chunks {
+        send(socket, &StreamMessage::Chunk { chunk }).await?;
+    }
+    send(socket, &StreamMessage::Metadata { metadata: &result.metadata }).await
+}
+
+async fn stream_job(socket: &mut WebSocket, config: &Config, jobs: &JobStore, job_id: Uuid) -> Result<(), AppError> {
+    let result = await_job(config, jobs, job_id).await?;
+    send_result(socket, &result).await
+}
+
+/// Waits for a job to finish and returns its result. A job is processed
+/// under `config.process_timeout_secs`, so waiting gives up once that much
+/// time has passed.
+async fn await_job(config: &Config, jobs: &JobStore, job_id: Uuid) -> Result<ContentProcessingResult, AppError> {
+    let deadline = Instant::now() + Duration::from_secs(config.process_timeout_secs);
+
+    loop {
+        let job = jobs.get(&job_id).ok_or_else(|| AppError::NotFound(format!("Unknown job: {}", job_id)))?;
+
+        match (job.status, job.result) {
+            (JobStatus::Done, Some(result)) => return Ok(result),
+            (JobStatus::Done, None) => {
+                return Err(AppError::Internal(anyhow::anyhow!("Job {} finished without a result", job_id)));
+            }
+            (JobStatus::Failed, _) => {
+                let error = job.error.unwrap_or_default();
+                return Err(AppError::Internal(anyhow::anyhow!("Job {} failed: {}", job_id, error)));
+            }
+            (JobStatus::Pending | JobStatus::Running, _) if Instant::now() >= deadline => {
+                return Err(AppError::TimedOut(ProcessTimeout(config.process_timeout_secs)));
+            }
+            (JobStatus::Pending | JobStatus::Running, _) => tokio::time::sleep(JOB_POLL_INTERVAL).await,
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &StreamMessage<'_>) -> Result<(), AppError> {
+    socket.send(Message::Text(serde_json::to_string(message)?)).await?;
+    Ok(())
+}
+
+/// The provider for a content type named in the path, e.g. `/process/pdf`.
+fn forced_provider(content_type: &str) -> Result<(ContentType, ProviderFactory), AppError> {
+    let parsed: ContentType = content_type
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("Unknown content type: {}", content_type)))?;
+    let provider = registry::get_provider(&parsed).ok_or_else(|| AppError::Rejected(
+        RejectReason::UnsupportedExtension,
+        format!("Unsupported content type: {:?}", parsed),
+    ))?;
+
+    Ok((parsed, provider))
+}
+
+struct Upload {
+    file_name: String,
+    body: UploadBody,
+    content_type: ContentType,
+    provider: ProviderFactory,
+    /// The query options with any `chunk_options` field applied over them.
+    options: ProcessingOptions,
+    /// The `metadata` field, attached to the result as `user_metadata`.
+    metadata: Option<Map<String, Value>>,
+}
+
+/// Where an accepted upload's content is held.
+enum UploadBody {
+    /// The file's bytes as they were received.
+    Memory(Bytes),
+    /// A gzipped upload, decompressed into the temp file providers read.
+    Staged { file: Arc<NamedTempFile>, size: u64 },
+}
+
+impl Upload {
+    fn size(&self) -> u64 {
+        match &self.body {
+            UploadBody::Memory(bytes) => bytes.len() as u64,
+            UploadBody::Staged { size, .. } => *size,
+        }
+    }
+
+    /// The whole content, read back from disk for a staged upload.
+    async fn bytes(&self) -> std::io::Result<Bytes> {
+        match &self.body {
+            UploadBody::Memory(bytes) => Ok(bytes.clone()),
+            UploadBody::Staged { file, .. } => fs::read(file.path()).await.map(Bytes::from),
+        }
+    }
+}
+
+/// Largest auxiliary (non-file) field that is read.
+const MAX_FIELD_BYTES: usize = 64 * 1024;
+
+/// Largest `prior_chunks` field, which lists every chunk of a document.
+const MAX_PRIOR_CHUNKS_BYTES: usize = 8 * 1024 * 1024;
+
+/// The names `ProcessingOptions` fields go by, which a text field may set.
+static OPTION_FIELDS: Lazy<Vec<String>> = Lazy::new(|| match serde_json::to_value(ProcessingOptions::default()) {
+    Ok(Value::Object(fields)) => fields.into_iter().map(|(name, _)| name).collect(),
+    _ => Vec::new(),
+});
+
+/// A file field ready to process, or the name it was sent under and why it
+/// was turned away.
+type FileUpload = Result<Upload, (String, AppError)>;
+
+/// Reads the first file field, ignoring the rest of the body.
+async fn read_upload(
+    config: &Config,
+    multipart: &mut Multipart,
+    forced: Option<(ContentType, ProviderFactory)>,
+    options: ProcessingOptions,
+) -> Result<Upload, AppError> {
+    match read_uploads(config, multipart, forced, options, 1).await?.into_iter().next() {
+        Some(upload) => upload.map_err(|(_, err)| err),
+        None => Err(AppError::MissingFile),
+    }
+}
+
+/// Reads up to `max_files` file fields. Unless the caller forces a type,
+/// each file's provider is resolved from the extension, then the declared
+/// part type, then the file's magic bytes. Empty, oversized and disabled-type
+/// files are rejected here, each on its own; a malformed body or auxiliary
+/// field fails the whole upload.
+///
+/// A field is read as, in order of precedence:
+/// - `content_type`: forces the type, unless the path already names one
+/// - `chunk_options`: a JSON object of `ProcessingOptions` overriding `options`
+/// - `metadata`: a JSON object attached to the result as `user_metadata`
+/// - `prior_chunks`: a JSON array of `{id, content_hash}`, setting
+///   `options.prior_chunks`; it may be up to `MAX_PRIOR_CHUNKS_BYTES`
+/// - any other `ProcessingOptions` field, e.g. `summary_chunk=true`, setting
+///   that option; the value is read as JSON, or as a string if it is not JSON
+/// - a file: one named `file`, or any other field with a filename, such as
+///   `document` or `upload`
+///
+/// Option fields are honoured for the files that come after them, a later
+/// one overriding an earlier one and both overriding the query string. Other
+/// fields are skipped without being buffered.
+///
+/// `FIXEDWIDTH` uploads get a provider built from `options.columns`.
+async fn read_uploads(
+    config: &Config,
+    multipart: &mut Multipart,
+    mut forced: Option<(ContentType, ProviderFactory)>,
+    mut options: ProcessingOptions,
+    max_files: usize,
+) -> Result<Vec<FileUpload>, AppError> {
+    let mut metadata = None;
+    let mut uploads = Vec::new();
+
+    while uploads.len() < max_files {
+        let Some(mut field) = multipart.next_field().await.map_err(bad_multipart)? else { break };
+
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {}
+            "content_type" => {
+                let value = read_field(&mut field).await?;
+                if forced.is_none() {
+                    forced = Some(forced_provider(value.trim())?);
+                }
+                continue;
+            }
+            "chunk_options" => {
+                let value = read_field(&mut field).await?;
+                let overrides = serde_json::from_str(&value)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid chunk_options field: {}", e)))?;
+                options = merge_options(&options, overrides, &name)?;
+                continue;
+            }
+            "prior_chunks" => {
+                let value = read_field_with_limit(&mut field, MAX_PRIOR_CHUNKS_BYTES).await?;
+                options.prior_chunks = Some(
+                    serde_json::from_str(&value)
+                        .map_err(|e| AppError::BadRequest(format!("Invalid prior_chunks field: {}", e)))?,
+                );
+                continue;
+            }
+            "metadata" => {
+                let value = read_field(&mut field).await?;
+                metadata = Some(
+                    serde_json::from_str(&value)
+                        .map_err(|e| AppError::BadRequest(format!("Invalid metadata field: {}", e)))?,
+                );
+                continue;
+            }
+            option if OPTION_FIELDS.iter().any(|field| field == option) => {
+                let value = read_field(&mut field).await?;
+                // `true` and `500` are JSON; `markdown` is taken as the string it is
+                let value = serde_json::from_str(value.trim()).unwrap_or(Value::String(value));
+                options = merge_options(&options, Map::from_iter([(name.clone(), value)]), &name)?;
+                continue;
+            }
+            _ if field.file_name().is_some() => {}
+            _ => continue,
+        }
+
+        let file_name = field.file_name().map(sanitize_file_name);
+        let mime = field.content_type().map(str::to_string);
+        let bytes = field.bytes().await.map_err(bad_multipart)?;
+
+        uploads.push(
+            accept_file(config, file_name.clone(), mime, bytes, forced.clone(), &options, &metadata)
+                .await
+                .map_err(|err| (file_name.unwrap_or_else(|| "upload".to_string()), err)),
+        );
+    }
+
+    if uploads.is_empty() {
+        return Err(AppError::MissingFile);
+    }
+    Ok(uploads)
+}
+
+/// Picks the provider for one file and checks it may be processed.
+async fn accept_file(
+    config: &Config,
+    file_name: Option<String>,
+    mime: Option<String>,
+    bytes: Bytes,
+    forced: Option<(ContentType, ProviderFactory)>,
+    options: &ProcessingOptions,
+    metadata: &Option<Map<String, Value>>,
+) -> Result<Upload, AppError> {
+    let file_name = match (file_name, &forced) {
+        (Some(name), _) => name,
+        (None, Some(_)) => "upload".to_string(),
+        (None, None) => return Err(AppError::BadRequest("No filename provided".to_string())),
+    };
+
+    if bytes.is_empty() {
+        return Err(AppError::Rejected(RejectReason::Empty, "Uploaded file is empty".to_string()));
+    }
+    if let Some(max_file_bytes) = config.max_file_bytes.filter(|max| bytes.len() as u64 > *max) {
+        return Err(AppError::Rejected(
+            RejectReason::TooLarge,
+            format!("File is {} bytes, over the limit of {} bytes", bytes.len(), max_file_bytes),
+        ));
+    }
+
+    // A gzipped upload is processed as the file inside it; the part's MIME
+    // type describes the compression, not the content
+    let (file_name, mime, body) = match gzip::inner_name(&file_name) {
+        Some(inner) => {
+            let body = decompress_upload(config, &file_name, inner, bytes).await?;
+            (inner.to_string(), None, body)
+        }
+        None => (file_name, mime, UploadBody::Memory(bytes)),
+    };
+
+    let extension = Path::new(&file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_string);
+
+    let mut found = forced
+        .or_else(|| extension.as_deref().and_then(registry::get_provider_by_extension))
+        .or_else(|| mime.as_deref().and_then(registry::get_provider_by_mime));
+    if found.is_none() {
+        found = match &body {
+            UploadBody::Memory(bytes) => registry::detect_provider(bytes),
+            UploadBody::Staged { file, .. } => registry::detect_provider(&fs::read(file.path()).await?),
+        };
+    }
+    let (content_type, mut provider) = found
+        .ok_or_else(|| AppError::Rejected(RejectReason::UnsupportedExtension, match &extension {
+            Some(extension) => format!("Unsupported file type: {}", extension),
+            None => format!("Could not detect file type: {}", file_name),
+        }))?;
+    if config.disabled_types.contains(&content_type) {
+        return Err(AppError::Rejected(
+            RejectReason::DisabledType,
+            format!("Processing of {:?} files is disabled", content_type),
+        ));
+    }
+
+    if content_type == ContentType::FixedWidth && !options.columns.is_empty() {
+        provider = Arc::new(FixedWidthProvider::with_columns(options.columns.clone()));
+    }
+    if options.chunk_strategy != ChunkStrategy::Fixed {
+        if let Some(rechunking) = provider.with_chunk_strategy(options.chunk_strategy) {
+            provider = rechunking;
+        }
+    }
+    if options.group_by_kind {
+        if let Some(grouping) = provider.with_kind_grouping(options.kind_summaries) {
+            provider = grouping;
+        }
+    }
+    if let Some(dispatching) = provider.with_dispatch_permits(config.dispatch_permits.clone()) {
+        provider = dispatching;
+    }
+
+    Ok(Upload {
+        provider: Arc::new(TracedProvider::new(content_type.clone(), file_name.clone(), provider)),
+        file_name,
+        body,
+        content_type,
+        options: options.clone(),
+        metadata: metadata.clone(),
+    })
+}
+
+/// Decompresses a gzipped upload on a blocking thread, streaming it into a
+/// temp file named for the inner file. The decompressed size is held to
+/// `Config::max_file_bytes`, and to `ResourceLimits::max_total_bytes` so a
+/// gzip bomb stops early whatever the configuration.
+async fn decompress_upload(config: &Config, file_name: &str, inner: &str, bytes: Bytes) -> Result<UploadBody, AppError> {
+    let mut temp_file = upload_temp_file(config, inner)?;
+    let max_file_bytes = config.max_file_bytes;
+
+    let decompressed = tokio::task::spawn_blocking(move || {
+        let mut output = BufWriter::new(temp_file.as_file_mut());
+        let size = gzip::decompress_into(&bytes, &mut output, &ResourceLimits::global(), max_file_bytes)?;
+        output.flush()?;
+        drop(output);
+        anyhow::Ok((temp_file, size))
+    })
+    .await?;
+
+    let (temp_file, size) = decompressed.map_err(|err| match err.downcast::<LimitExceeded>() {
+        Ok(limit) => AppError::LimitExceeded(limit),
+        Err(err) => AppError::BadRequest(format!("Could not decompress {}: {}", file_name, err)),
+    })?;
+    if size == 0 {
+        return Err(AppError::Rejected(RejectReason::Empty, "Uploaded file is empty".to_string()));
+    }
+    if let Some(max_file_bytes) = max_file_bytes.filter(|max| size > *max) {
+        return Err(AppError::Rejected(
+            RejectReason::TooLarge,
+            format!("Decompressed file is over the limit of {} bytes", max_file_bytes),
+        ));
+    }
+
+    Ok(UploadBody::Staged { file: Arc::new(temp_file), size })
+}
+
+/// Reads a small text field, refusing any over `MAX_FIELD_BYTES`.
+async fn read_field(field: &mut Field<'_>) -> Result<String, AppError> {
+    read_field_with_limit(field, MAX_FIELD_BYTES).await
+}
+
+async fn read_field_with_limit(field: &mut Field<'_>, max_bytes: usize) -> Result<String, AppError> {
+    let name = field.name().unwrap_or_default().to_string();
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = field.chunk().await.map_err(bad_multipart)? {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(AppError::BadRequest(format!(
+                "Field {} is over the limit of {} bytes",
+                name, max_bytes
+            )));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    String::from_utf8(bytes).map_err(|_| AppError::BadRequest(format!("Field {} is not valid UTF-8", name)))
+}
+
+/// Applies `overrides`, read from the upload field `field`, over `options`.
+fn merge_options(
+    options: &ProcessingOptions,
+    overrides: Map<String, Value>,
+    field: &str,
+) -> Result<ProcessingOptions, AppError> {
+    let invalid = |e: serde_json::Error| AppError::BadRequest(format!("Invalid {} field: {}", field, e));
+
+    let mut merged = serde_json::to_value(options).map_err(invalid)?;
+    if let Value::Object(fields) = &mut merged {
+        fields.extend(overrides);
+    }
+
+    serde_json::from_value(merged).map_err(invalid)
+}
+
+/// Stages the upload in a uniquely named temp file under the configured base
+/// directory and runs the provider over it. The file keeps the original
+/// extension for providers that sniff it, and is removed when the handle drops,
+/// including when processing fails. Uploads under `min_file_bytes` are not
+/// processed and come back as `Empty`; those up to `in_memory_max_bytes` skip
+/// the temp file when the provider can work from memory. The provider runs
+/// under one of `config.dispatch_permits`, waiting for it outside the timeout.
+///
+/// With `options.dedupe`, repeated chunks are dropped first. With
+/// `options.summary_chunk`, a summary chunk is put first. Every chunk
+/// gets a `content_hash`. With `options.prior_chunks`, chunks
+/// that are unchanged since then are dropped here, before anything is embedded.
+async fn process_upload(config: &Config, upload: &Upload) -> anyhow::Result<ContentProcessingResult> {
+    let permit = config.dispatch_permits.acquire().await?;
+    let mut result = within_timeout(config, run_provider(config, upload)).await?;
+    drop(permit);
+    fill_default_metadata(&mut result.metadata, &upload.file_name);
+
+    if upload.options.dedupe {
+        postprocess::drop_duplicate_chunks(&mut result);
+    }
+    if upload.options.summary_chunk {
+        postprocess::prepend_summary(&mut result, &ids::doc_hash(&upload.bytes().await?));
+    }
+    postprocess::attach_content_hashes(&mut result.chunks);
+    if let Some(prior) = &upload.options.prior_chunks {
+        postprocess::keep_changed_chunks(&mut result, prior);
+    }
+
+    if let Some(metadata) = &upload.metadata {
+        result.metadata.additional.insert("user_metadata".to_string(), Value::Object(metadata.clone()));
+    }
+
+    Ok(result)
+}
+
+async fn run_provider(config: &Config, upload: &Upload) -> anyhow::Result<ContentProcessingResult> {
+    let file_size = upload.size();
+    if file_size < config.min_file_bytes {
+        return Ok(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Empty,
+            chunks: Vec::new(),
+            metadata: ContentMetadata {
+                content_type: upload.content_type.clone(),
+                file_name: Some(upload.file_name.clone()),
+                file_size: Some(file_size),
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: None,
+                language: None,
+                additional: HashMap::new(),
+            },
+            error: None,
+        });
+    }
+
+    if let UploadBody::Memory(bytes) = &upload.body {
+        if file_size <= config.in_memory_max_bytes {
+            if let Some(result) = upload.provider.process_bytes(bytes).await? {
+                return Ok(result);
+            }
+        }
+    }
+
+    let temp_file = stage_upload(config, upload).await?;
+    let mut result = upload.provider.process_content(temp_file.path()).await?;
+
+    if upload.options.include_raw_metadata {
+        if let Some(raw) = upload.provider.raw_metadata(temp_file.path()).await? {
+            result.metadata.additional.insert("raw_metadata".to_string(), raw);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Provider work ran past `Config::process_timeout_secs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Processing did not finish within {0} seconds")]
+pub struct ProcessTimeout(pub u64);
+
+/// Runs provider work under `config.process_timeout_secs`, failing with
+/// `ProcessTimeout` when it runs over. The work is dropped at that point; a
+/// provider's `spawn_blocking` task cannot be interrupted, so it finishes in
+/// the background and its output is discarded.
+async fn within_timeout<T, E>(config: &Config, work: impl Future<Output = Result<T, E>>) -> Result<T, E>
+where
+    E: From<ProcessTimeout>,
+{
+    let limit = Duration::from_secs(config.process_timeout_secs);
+
+    match tokio::time::timeout(limit, work).await {
+        Ok(outcome) => outcome,
+        Err(_) => {
+            tracing::warn!(timeout_secs = config.process_timeout_secs, "Processing timed out");
+            Err(ProcessTimeout(config.process_timeout_secs).into())
+        }
+    }
+}
+
+/// The full `/content/process` pipeline for one upload: process, embed when
+/// asked to, then render with the upload's options.
+async fn process_and_embed(config: &Config, upload: &Upload) -> anyhow::Result<ContentProcessingResult> {
+    process_reporting(config, upload, |_| {}).await
+}
+
+/// `process_and_embed`, passing `report` the job's progress as each phase
+/// starts and once every chunk is done.
+async fn process_reporting(
+    config: &Config,
+    upload: &Upload,
+    report: impl Fn(JobProgress),
+) -> anyhow::Result<ContentProcessingResult> {
+    report(JobProgress { processed_chunks: 0, total_estimate: None, phase: JobPhase::Extracting });
+    let mut result = process_upload(config, upload).await?;
+    let total = result.chunks.len();
+
+    if upload.options.embed {
+        report(JobProgress { processed_chunks: 0, total_estimate: Some(total), phase: JobPhase::Embedding });
+        embed_chunks(upload, &mut result).await;
+    }
+
+    postprocess::apply_options(&mut result, &upload.options);
+    report(JobProgress { processed_chunks: total, total_estimate: Some(total), phase: JobPhase::Complete });
+
+    Ok(result)
+}
+
+/// Writes the upload to a uniquely named temp file under the configured base
+/// directory, keeping the original extension for providers that sniff it.
+/// The file is removed when the last handle drops. A decompressed upload is
+/// already on disk, so its file is returned as is.
+async fn stage_upload(config: &Config, upload: &Upload) -> anyhow::Result<Arc<NamedTempFile>> {
+    let bytes = match &upload.body {
+        UploadBody::Memory(bytes) => bytes,
+        UploadBody::Staged { file, .. } => return Ok(file.clone()),
+    };
+
+    let temp_file = upload_temp_file(config, &upload.file_name)?;
+    let started = Instant::now();
+    fs::write(temp_file.path(), bytes).await?;
+    tracing::debug!(file_name = %upload.file_name, elapsed_ms = started.elapsed().as_millis() as u64, "Staged upload");
+
+    Ok(Arc::new(temp_file))
+}
+
+/// A new temp file under the configured base directory, with `file_name`'s
+/// extension.
+fn upload_temp_file(config: &Config, file_name: &str) -> std::io::Result<NamedTempFile> {
+    let suffix = Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{}", ext))
+        .unwrap_or_default();
+
+    tempfile::Builder::new()
+        .prefix("p8fs-upload-")
+        .suffix(&suffix)
+        .tempfile_in(&config.temp_dir)
+}
+
+/// With `options.embed`, embeds the chunks `options.embed_filter` selects
+/// (before any output-format rendering, with `options.embed_metadata_fields`
+/// prepended) and attaches each vector as `metadata["embedding"]`. Skipped chunks get a null embedding and the reason.
+///
+/// If embedding fails, e.g. because the model cannot be loaded, the chunks
+/// are returned without vectors and the reason is put in
+/// `additional["embedding_warning"]`, so the processing is not lost.
+async fn embed_chunks(upload: &Upload, result: &mut ContentProcessingResult) {
+    if !upload.options.embed {
+        return;
+    }
+
+    let selected = postprocess::select_for_embedding(result, &upload.options.embed_filter);
+    if selected.is_empty() {
+        return;
+    }
+
+    let chunks = postprocess::embedding_inputs(result, &selected, &upload.options.embed_metadata_fields);
+    let embeddings = match upload.provider.to_embeddings(&chunks).await {
+        Ok(embeddings) => embeddings,
+        Err(err) => {
+            result.metadata.additional.insert(
+                "embedding_warning".to_string(),
+                json!(format!("Chunks were not embedded: {}", err)),
+            );
+            return;
+        }
+    };
+    for (i, embedding) in selected.into_iter().zip(embeddings) {
+        result.chunks[i].metadata.insert("embedding".to_string(), json!(embedding));
+    }
+}
+
+/// Processes the upload and embeds all of its chunks. Vectors are computed
+/// from the provider's markdown, before `options` renders the content.
+async fn embed_upload(config: &Config, upload: &Upload) -> anyhow::Result<EmbeddedContentResult> {
+    let mut result = process_upload(config, upload).await?;
+
+    let embeddings = if result.chunks.is_empty() {
+        Vec::new()
+    } else {
+        let all: Vec<usize> = (0..result.chunks.len()).collect();
+        let inputs = postprocess::embedding_inputs(&result, &all, &upload.options.embed_metadata_fields);
+        upload.provider.to_embeddings(&inputs).await?
+    };
+    if embeddings.len() != result.chunks.len() {
+        anyhow::bail!("Got {} embeddings for {} chunks", embeddings.len(), result.chunks.len());
+    }
+
+    postprocess::apply_options(&mut result, &upload.options);
+
+    Ok(EmbeddedContentResult {
+        success: result.success,
+        status: result.status,
+        chunks: result
+            .chunks
+            .into_iter()
+            .zip(embeddings)
+            .map(|(chunk, embedding)| EmbeddedChunk { chunk, embedding })
+            .collect(),
+        metadata: result.metadata,
+        error: result.error,
+    })
+}
+
+/// Reduces a client-supplied filename to its final path component with control
+/// characters removed. Only used for metadata and extension lookup, never as a path.
+fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base.chars().filter(|c| !c.is_control()).collect();
+
+    match cleaned.trim() {
+        "" | "." | ".." => "upload".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// A body cut off at `Config::max_upload_bytes` is `too_large`; anything
+/// else wrong with the body is a bad request.
+fn bad_multipart(err: MultipartError) -> AppError {
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return AppError::Rejected(
+            RejectReason::TooLarge,
+            "Upload is over the maximum request size (P8FS_MAX_UPLOAD_BYTES)".to_string(),
+        );
+    }
+    AppError::BadRequest(format!("Invalid multipart body: {}", err))
+}
+
+/// Why an upload was turned away, reported as `error.rejection.reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// No provider matches the extension, declared type or content.
+    UnsupportedExtension,
+    /// A provider exists but the type is listed in `Config::disabled_types`.
+    DisabledType,
+    /// The upload is over `Config::max_file_bytes`, or the whole body over
+    /// `Config::max_upload_bytes`.
+    TooLarge,
+    /// The upload has no content at all.
+    Empty,
+    /// The document is encrypted and cannot be read.
+    Encrypted,
+}
+
+pub enum AppError {
+    /// The upload was rejected without being processed; the string explains why.
+    Rejected(RejectReason, String),
+    /// The multipart body had no `file` field.
+    MissingFile,
+    BadRequest(String),
+    NotFound(String),
+    /// Processing stopped at a configured resource limit.
+    LimitExceeded(LimitExceeded),
+    /// Processing ran past `Config::process_timeout_secs`.
+    TimedOut(ProcessTimeout),
+    /// The embedding model could not be loaded.
+    Unavailable(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    fn status_and_code(&self) -> (StatusCode, &'static str) {
+        match self {
+            AppError::Rejected(reason, _) => match reason {
+                RejectReason::UnsupportedExtension | RejectReason::DisabledType => {
+                    (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_type")
+                }
+                RejectReason::TooLarge => (StatusCode::PAYLOAD_TOO_LARGE, "too_large"),
+                RejectReason::Empty => (StatusCode::BAD_REQUEST, "empty_file"),
+                RejectReason::Encrypted => (StatusCode::UNPROCESSABLE_ENTITY, "encrypted"),
+            },
+            AppError::MissingFile => (StatusCode::BAD_REQUEST, "missing_file"),
+            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, "bad_request"),
+            AppError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            AppError::LimitExceeded(_) => (StatusCode::UNPROCESSABLE_ENTITY, "limit_exceeded"),
+            AppError::TimedOut(_) => (StatusCode::GATEWAY_TIMEOUT, "timeout"),
+            AppError::Unavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+            AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal"),
+        }
+    }
+
+    /// The `error` object of an error response.
+    fn body(&self) -> Value {
+        let (_, code) = self.status_and_code();
+        let mut error = json!({
+            "code": code,
+            "message": self.message(),
+        });
+        if let AppError::Rejected(reason, detail) = self {
+            error["rejection"] = json!({ "reason": reason, "detail": detail });
+            if *reason == RejectReason::UnsupportedExtension {
+                error["rejection"]["supported_extensions"] = json!(supported_extensions());
+            }
+        }
+        error
+    }
+
+    fn message(&self) -> String {
+        match self {
+            AppError::Rejected(_, message)
+            | AppError::BadRequest(message)
+            | AppError::NotFound(message) => message.clone(),
+            AppError::MissingFile => "No file provided".to_string(),
+            AppError::LimitExceeded(limit) => limit.to_string(),
+            AppError::TimedOut(timeout) => timeout.to_string(),
+            AppError::Unavailable(err) => format!("Embedding model unavailable: {}", err),
+            AppError::Internal(err) => format!("Internal error: {}", err),
+        }
+    }
+}
+
+/// Every extension with a provider, sorted, so a client whose upload was
+/// turned away can see what it could send instead. Like `/types`, this is the
+/// whole registry, including types disabled in the config.
+fn supported_extensions() -> Vec<String> {
+    let mut extensions: Vec<String> = registry::supported_types()
+        .into_iter()
+        .flat_map(|supported| supported.extensions)
+        .collect();
+    extensions.sort();
+    extensions.dedup();
+    extensions
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Internal error: {}", self.0),
-        )
-            .into_response()
+        let (status, _) = self.status_and_code();
+
+        (status, Json(json!({ "error": self.body() }))).into_response()
     }
 }
 
@@ -99,6 +1149,22 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        let err = match err.into().downcast::<LimitExceeded>() {
+            Ok(limit) => return Self::LimitExceeded(limit),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<ProcessTimeout>() {
+            Ok(timeout) => return Self::TimedOut(timeout),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<ModelError>() {
+            Ok(ModelError::Load(err)) => return Self::Unavailable(err),
+            Ok(err) => return Self::Internal(err.into()),
+            Err(err) => err,
+        };
+        match err.downcast::<Encrypted>() {
+            Ok(encrypted) => Self::Rejected(RejectReason::Encrypted, encrypted.to_string()),
+            Err(err) => Self::Internal(err),
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::providers::ContentProvider;
+    use async_trait::async_trait;
+
+    /// A provider that never finishes in time, like `pdf_extract` stuck on a
+    /// malformed file.
+    struct SlowProvider;
+
+    #[async_trait]
+    impl ContentProvider for SlowProvider {
+        async fn process_content(&self, _file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            anyhow::bail!("should have timed out")
+        }
+
+        async fn to_markdown_chunks(&self, _file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+            Ok(vec![])
+        }
+
+        async fn to_metadata(&self, _file_path: &Path) -> anyhow::Result<ContentMetadata> {
+            anyhow::bail!("not used")
+        }
+
+        async fn to_embeddings(&self, _chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+            Ok(vec![])
+        }
+    }
+
+    /// A provider that records the text of every chunk it is asked to embed.
+    #[derive(Default)]
+    struct RecordingProvider {
+        embedded: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ContentProvider for RecordingProvider {
+        async fn process_content(&self, _file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+            anyhow::bail!("not used")
+        }
+
+        async fn to_markdown_chunks(&self, _file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+            Ok(vec![])
+        }
+
+        async fn to_metadata(&self, _file_path: &Path) -> anyhow::Result<ContentMetadata> {
+            anyhow::bail!("not used")
+        }
+
+        async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+            let mut embedded = self.embedded.lock().unwrap();
+            embedded.extend(chunks.iter().map(|chunk| chunk.content.clone()));
+            Ok(chunks.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_metadata_fields_prefix_the_embedded_text() {
+        let provider = Arc::new(RecordingProvider::default());
+        let upload = Upload {
+            file_name: "guide.md".to_string(),
+            body: UploadBody::Memory(Bytes::from_static(b"")),
+            content_type: ContentType::Markdown,
+            provider: provider.clone(),
+            options: ProcessingOptions {
+                embed: true,
+                embed_metadata_fields: vec!["title".to_string(), "section_title".to_string()],
+                ..ProcessingOptions::default()
+            },
+            metadata: None,
+        };
+        let chunk = |content: &str, section: Option<&str>| ContentChunk {
+            id: content.to_string(),
+            content: content.to_string(),
+            metadata: section
+                .map(|section| HashMap::from([("section_title".to_string(), json!(section))]))
+                .unwrap_or_default(),
+        };
+        let mut result = ContentProcessingResult {
+            success: true,
+            status: Default::default(),
+            chunks: vec![chunk("Install with cargo.", Some("Setup")), chunk("Preface.", None)],
+            metadata: ContentMetadata {
+                content_type: ContentType::Markdown,
+                file_name: Some("guide.md".to_string()),
+                file_size: None,
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: Some("User Guide".to_string()),
+                language: None,
+                additional: Default::default(),
+            },
+            error: None,
+        };
+
+        embed_chunks(&upload, &mut result).await;
+
+        assert_eq!(
+            *provider.embedded.lock().unwrap(),
+            vec!["User Guide\nSetup\n\nInstall with cargo.", "User Guide\n\nPreface."]
+        );
+        assert_eq!(result.chunks[0].content, "Install with cargo.");
+        assert_eq!(result.chunks[1].content, "Preface.");
+        assert_eq!(result.chunks[0].metadata["embedding"], json!([1.0, 0.0]));
+    }
+
+    /// A provider that extracts one chunk and then never finishes, like a
+    /// long document part way through.
+    struct TrickleProvider;
+
+    #[async_trait]
+    impl ContentProvider for TrickleProvider {
+        async fn process_content(&self, _file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+            anyhow::bail!("not used")
+        }
+
+        async fn to_markdown_chunks(&self, _file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+            anyhow::bail!("not used")
+        }
+
+        async fn to_metadata(&self, _file_path: &Path) -> anyhow::Result<ContentMetadata> {
+            anyhow::bail!("not used")
+        }
+
+        async fn to_embeddings(&self, _chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+            Ok(vec![])
+        }
+
+        fn stream_chunks<'a>(&'a self, _file_path: &'a Path) -> futures::stream::BoxStream<'a, anyhow::Result<ContentChunk>> {
+            let first = ContentChunk {
+                id: "page_1".to_string(),
+                content: "# Page 1\n\nSome **bold** text.".to_string(),
+                metadata: HashMap::new(),
+            };
+            stream::once(async { Ok(first) }).chain(stream::pending()).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_chunks_arrive_before_extraction_ends() {
+        let upload = Upload {
+            file_name: "book.pdf".to_string(),
+            body: UploadBody::Memory(Bytes::from_static(b"%PDF-1.7")),
+            content_type: ContentType::Pdf,
+            provider: Arc::new(TrickleProvider),
+            options: ProcessingOptions { output_format: crate::models::OutputFormat::Plain, ..ProcessingOptions::default() },
+            metadata: None,
+        };
+        let metadata = ContentMetadata {
+            content_type: ContentType::Pdf,
+            file_name: Some("book.pdf".to_string()),
+            file_size: None,
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional: Default::default(),
+        };
+
+        let mut results = std::pin::pin!(process_streamed(&upload, Path::new("book.pdf"), metadata));
+        let first = tokio::time::timeout(Duration::from_secs(5), results.next()).await.unwrap().unwrap().unwrap();
+
+        let chunk = &first.chunks[0];
+        assert_eq!(chunk.content, "Page 1\n\nSome bold text.");
+        assert_eq!(chunk.metadata["content_hash"], ids::content_hash("# Page 1\n\nSome **bold** text."));
+    }
+
+    #[tokio::test]
+    async fn test_process_timeout_returns_gateway_timeout() {
+        let config = Config { process_timeout_secs: 1, ..Config::default() };
+        let upload = Upload {
+            file_name: "stuck.pdf".to_string(),
+            body: UploadBody::Memory(Bytes::from_static(b"%PDF-1.7")),
+            content_type: ContentType::Pdf,
+            provider: Arc::new(SlowProvider),
+            options: ProcessingOptions::default(),
+            metadata: None,
+        };
+
+        let started = Instant::now();
+        let err = match process_batch(&config, vec![Ok(upload)]).await {
+            Ok(_) => panic!("processing should have timed out"),
+            Err(err) => err,
+        };
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(err.body()["code"], "timeout");
+        assert_eq!(err.into_response().status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[test]
+    fn test_model_load_failure_is_service_unavailable() {
+        let err: anyhow::Error = ModelError::Load(anyhow::anyhow!("model files missing")).into();
+        let err = AppError::from(err);
+
+        assert_eq!(err.body()["code"], "unavailable");
+        assert!(err.body()["message"].as_str().unwrap().contains("model files missing"));
+        assert_eq!(err.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_await_job_done_without_result_is_an_error() {
+        let jobs = JobStore::default();
+        let job = jobs.create();
+        jobs.set_status(&job.job_id, JobStatus::Done);
+
+        let err = await_job(&Config::default(), &jobs, job.job_id).await.err().unwrap();
+        assert_eq!(err.body()["code"], "internal");
+        assert!(err.message().contains("without a result"));
+    }
+
+    #[tokio::test]
+    async fn test_await_job_gives_up_at_process_timeout() {
+        let config = Config { process_timeout_secs: 1, ..Config::default() };
+        let jobs = JobStore::default();
+        let job = jobs.create();
+        jobs.set_status(&job.job_id, JobStatus::Running);
+
+        let started = Instant::now();
+        let err = await_job(&config, &jobs, job.job_id).await.err().unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(err.into_response().status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+}
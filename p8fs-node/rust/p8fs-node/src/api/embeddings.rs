@@ -1,35 +1,140 @@
-use crate::models::{EmbeddingRequest, EmbeddingResponse};
-use crate::services::EmbeddingService;
+use crate::config::Config;
+use crate::models::{EmbeddingRequest, EmbeddingVector};
+use crate::services::export::{embeddings_to_arrow_ipc, ARROW_STREAM_MIME};
+use crate::services::model_registry::ModelError;
+use crate::services::{normalize_l2, truncate_embeddings, EmbeddingService, ModelRegistry};
 use axum::{
     extract::Json,
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     routing::post,
-    Router,
+    Extension, Router,
 };
+use serde_json::json;
+use std::sync::Arc;
 
 pub fn routes() -> Router {
     Router::new().route("/", post(create_embeddings))
 }
 
-async fn create_embeddings(Json(request): Json<EmbeddingRequest>) -> Result<Json<EmbeddingResponse>, AppError> {
-    let service = EmbeddingService::global();
-    let service = service.lock().await;
+/// Responds with JSON unless the client asks for an Arrow IPC stream via `Accept`.
+async fn create_embeddings(
+    Extension(config): Extension<Arc<Config>>,
+    headers: HeaderMap,
+    Json(request): Json<EmbeddingRequest>,
+) -> Result<Response, AppError> {
+    if request.input.is_empty() {
+        return Err(AppError::InvalidRequest {
+            message: "'input' must contain at least one string".to_string(),
+            param: Some("input"),
+        });
+    }
+    if !config.allow_empty_input {
+        if let Some(index) = request.input.iter().position(String::is_empty) {
+            return Err(AppError::InvalidRequest {
+                message: format!("'input[{}]' is an empty string", index),
+                param: Some("input"),
+            });
+        }
+    }
+
+    let service = load_model(request.model.as_deref()).await?;
     
-    let response = service.embed(request.input).await?;
+    let mut response = service.embed(request.input, request.input_type).await?;
+
+    if let Some(dimensions) = request.dimensions {
+        if dimensions == 0 || dimensions > response.dimensions {
+            return Err(AppError::InvalidRequest {
+                message: format!(
+                    "'dimensions' must be between 1 and {}, the size of {}'s embeddings",
+                    response.dimensions, response.model
+                ),
+                param: Some("dimensions"),
+            });
+        }
+        truncate_embeddings(&mut response, dimensions);
+    }
+
+    // After truncation, so a shortened vector can be brought back to unit length
+    if request.normalize.unwrap_or(false) {
+        for data in &mut response.data {
+            if let EmbeddingVector::Float(values) = &mut data.embedding {
+                normalize_l2(values);
+            }
+        }
+    }
+
+    let wants_arrow = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| media_type.split(';').next().unwrap_or_default().trim() == ARROW_STREAM_MIME);
+
+    if wants_arrow {
+        let body = embeddings_to_arrow_ipc(&response)?;
+        return Ok(([(header::CONTENT_TYPE, ARROW_STREAM_MIME)], body).into_response());
+    }
+
+    // Anything other than "base64" gets the default float arrays
+    if request.encoding_format.as_deref() == Some("base64") {
+        for data in &mut response.data {
+            data.embedding = data.embedding.to_base64();
+        }
+    }
     
-    Ok(Json(response))
+    Ok(Json(response).into_response())
 }
 
-pub struct AppError(anyhow::Error);
+/// The service for `model`, or the default model when unset. An unknown name
+/// is the client's mistake; a model that fails to load is not.
+pub(crate) async fn load_model(model: Option<&str>) -> Result<Arc<EmbeddingService>, AppError> {
+    ModelRegistry::global().get(model).await.map_err(|err| match err {
+        ModelError::Unknown { .. } => AppError::InvalidRequest {
+            message: err.to_string(),
+            param: Some("model"),
+        },
+        ModelError::Load(err) => AppError::Unavailable(err),
+    })
+}
+
+pub enum AppError {
+    /// Client error reported with an OpenAI-style error body.
+    InvalidRequest {
+        message: String,
+        param: Option<&'static str>,
+    },
+    /// The embedding model could not be loaded.
+    Unavailable(anyhow::Error),
+    Internal(anyhow::Error),
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Internal error: {}", self.0),
-        )
-            .into_response()
+        match self {
+            AppError::InvalidRequest { message, param } => (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": {
+                        "message": message,
+                        "type": "invalid_request_error",
+                        "param": param,
+                        "code": null,
+                    }
+                })),
+            )
+                .into_response(),
+            AppError::Unavailable(err) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Embedding model unavailable: {}", err),
+            )
+                .into_response(),
+            AppError::Internal(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Internal error: {}", err),
+            )
+                .into_response(),
+        }
     }
 }
 
@@ -38,6 +143,6 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        Self::Internal(err.into())
     }
-}
\ No newline at end of file
+}
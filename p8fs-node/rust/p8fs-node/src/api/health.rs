@@ -0,0 +1,41 @@
+use crate::services::EmbeddingService;
+use axum::{http::StatusCode, routing::get, Json, Router};
+use serde_json::{json, Value};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+}
+
+/// Reports `degraded` with the configured and actual sizes when the model's
+/// output dimension differs from `EMBEDDING_DIMENSIONS`. Never loads the model
+/// itself; until something has, the check is skipped.
+async fn health() -> Json<Value> {
+    let mismatch = match EmbeddingService::try_global() {
+        Some(service) => service.dimension_mismatch().await.ok().flatten(),
+        None => None,
+    };
+
+    match mismatch {
+        Some(mismatch) => Json(json!({ "status": "degraded", "dimension_mismatch": mismatch })),
+        None => Json(json!({ "status": "ok" })),
+    }
+}
+
+/// `503` until the embedding service has loaded its model, then `200` with
+/// the model name and dimensions. Like `health`, never triggers the load.
+async fn ready() -> (StatusCode, Json<Value>) {
+    let Some(service) = EmbeddingService::try_global() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": "loading" })));
+    };
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "status": "ready",
+            "model": service.model_name(),
+            "dimensions": service.dimensions(),
+        })),
+    )
+}
@@ -0,0 +1,38 @@
+use crate::services::Metrics;
+use axum::{
+    extract::{MatchedPath, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::time::Instant;
+
+pub fn routes() -> Router {
+    Router::new().route("/metrics", get(metrics))
+}
+
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        Metrics::global().render(),
+    )
+}
+
+/// Counts each request and times it under its route pattern, so
+/// `/content/jobs/:id` is one endpoint however many jobs there are. Added as
+/// a route layer, which only sees requests that matched a route.
+pub async fn track(request: Request, next: Next) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path().to_string(), |path| path.as_str().to_string());
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    Metrics::global().record_request(&endpoint, &method, response.status().as_u16(), started.elapsed());
+    response
+}
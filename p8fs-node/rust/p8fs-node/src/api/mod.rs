@@ -1,10 +1,39 @@
 pub mod content;
 pub mod embeddings;
+pub mod health;
+pub mod metrics;
+pub mod rerank;
+pub mod similarity;
 
-use axum::Router;
+use crate::config::Config;
+use crate::services::JobStore;
+use axum::{extract::DefaultBodyLimit, middleware, Extension, Router};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 
-pub fn create_router() -> Router {
+pub fn create_router(mut config: Config) -> Router {
+    config.dispatch_permits = Arc::new(Semaphore::new(config.batch_concurrency.max(1)));
+    let upload_limit = DefaultBodyLimit::max(usize::try_from(config.max_upload_bytes).unwrap_or(usize::MAX));
+    let jobs = JobStore::with_ttl(Duration::from_secs(config.job_ttl_secs));
+
+    Router::new()
+        .nest("/embeddings", embeddings::routes())
+        .nest("/content", content::routes().layer(upload_limit))
+        .merge(rerank::routes())
+        .merge(similarity::routes())
+        .merge(health::routes())
+        .merge(metrics::routes())
+        .route_layer(middleware::from_fn(metrics::track))
+        .layer(Extension(Arc::new(config)))
+        .layer(Extension(Arc::new(jobs)))
+}
+
+/// Routes mirroring the OpenAI API layout, mounted at `/v1` so OpenAI SDK
+/// clients can use the node as a drop-in base URL.
+pub fn create_openai_router(config: Config) -> Router {
     Router::new()
         .nest("/embeddings", embeddings::routes())
-        .nest("/content", content::routes())
-}
\ No newline at end of file
+        .route_layer(middleware::from_fn(metrics::track))
+        .layer(Extension(Arc::new(config)))
+}
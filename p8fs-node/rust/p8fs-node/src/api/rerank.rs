@@ -0,0 +1,31 @@
+use crate::api::embeddings::{load_model, AppError};
+use crate::models::RerankRequest;
+use crate::services;
+use axum::{extract::Json, response::IntoResponse, routing::post, Router};
+
+pub fn routes() -> Router {
+    Router::new().route("/rerank", post(rerank))
+}
+
+async fn rerank(Json(request): Json<RerankRequest>) -> Result<impl IntoResponse, AppError> {
+    if request.documents.is_empty() {
+        return Err(AppError::InvalidRequest {
+            message: "'documents' must contain at least one string".to_string(),
+            param: Some("documents"),
+        });
+    }
+    if request.top_n == Some(0) {
+        return Err(AppError::InvalidRequest {
+            message: "'top_n' must be at least 1".to_string(),
+            param: Some("top_n"),
+        });
+    }
+
+    let service = load_model(request.model.as_deref()).await?;
+    let mut response = services::rerank(&service, &request.query, &request.documents).await?;
+    if let Some(top_n) = request.top_n {
+        response.results.truncate(top_n);
+    }
+
+    Ok(Json(response))
+}
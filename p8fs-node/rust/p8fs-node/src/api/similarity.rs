@@ -0,0 +1,23 @@
+use crate::api::embeddings::{load_model, AppError};
+use crate::models::{SimilarityRequest, SimilarityResponse};
+use crate::services::cosine_similarity;
+use axum::{extract::Json, response::IntoResponse, routing::post, Router};
+
+pub fn routes() -> Router {
+    Router::new().route("/similarity", post(similarity))
+}
+
+async fn similarity(Json(request): Json<SimilarityRequest>) -> Result<impl IntoResponse, AppError> {
+    let service = load_model(request.model.as_deref()).await?;
+    let response = service.embed(vec![request.a, request.b], None).await?;
+
+    let [a, b] = <[_; 2]>::try_from(response.data)
+        .map_err(|data| anyhow::anyhow!("Expected 2 embeddings, got {}", data.len()))?;
+    let similarity = cosine_similarity(&a.embedding.into_floats()?, &b.embedding.into_floats()?)?;
+
+    Ok(Json(SimilarityResponse {
+        model: response.model,
+        similarity,
+        usage: response.usage,
+    }))
+}
@@ -0,0 +1,169 @@
+use crate::models::ContentType;
+use crate::providers::hashing::Hasher;
+use crate::providers::limits::DEFAULT_BATCH_CONCURRENCY;
+use crate::services::DEFAULT_JOB_TTL;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+#[cfg(test)]
+#[path = "config_tests.rs"]
+mod tests;
+
+const DEFAULT_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const DEFAULT_PORT: u16 = 3000;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Config {
+    /// Base directory for staging uploaded files while they are processed.
+    pub temp_dir: PathBuf,
+    /// Uploads smaller than this are returned as `Empty` without being processed.
+    pub min_file_bytes: u64,
+    /// Uploads up to this size are processed from memory, without staging to
+    /// disk, when their provider supports it.
+    pub in_memory_max_bytes: u64,
+    /// Uploads larger than this are rejected as `too_large`. `None` means no limit.
+    pub max_file_bytes: Option<u64>,
+    /// Largest request body `/content` accepts, all fields included. Bodies
+    /// over it are cut off while streaming and rejected as `too_large`.
+    pub max_upload_bytes: u64,
+    /// Content types that are rejected as `disabled_type` even though a
+    /// provider exists for them.
+    pub disabled_types: Vec<ContentType>,
+    /// How many uploads, files of a multi-file upload and archive entries are
+    /// processed at the same time, across the whole server.
+    pub batch_concurrency: usize,
+    /// The `batch_concurrency` permits every upload and archive entry is
+    /// processed under. `create_router` rebuilds them from the final
+    /// `batch_concurrency`, so the router's requests share one set.
+    #[serde(skip, default = "default_dispatch_permits")]
+    pub dispatch_permits: Arc<Semaphore>,
+    /// Address the HTTP server listens on.
+    pub bind_addr: SocketAddr,
+    /// Whether `/embeddings` accepts empty strings in `input`. Off by default,
+    /// since an empty string is usually a client bug.
+    pub allow_empty_input: bool,
+    /// How long shutdown waits for in-flight requests before exiting anyway.
+    pub shutdown_timeout_secs: u64,
+    /// How long a provider may spend on one upload before the request fails
+    /// with `504 Gateway Timeout`.
+    pub process_timeout_secs: u64,
+    /// How long a finished background job stays available before it is removed.
+    pub job_ttl_secs: u64,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        // The hasher itself is read lazily on first use; check it here so a
+        // typo stops the server instead of silently changing every chunk ID.
+        Hasher::from_env()?;
+
+        Ok(Config {
+            temp_dir: env::var("P8FS_TEMP_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| env::temp_dir()),
+            min_file_bytes: env_setting("P8FS_MIN_FILE_BYTES", "0")?,
+            in_memory_max_bytes: env_setting("P8FS_IN_MEMORY_MAX_BYTES", "1048576")?,
+            max_file_bytes: env::var("P8FS_MAX_FILE_BYTES")
+                .ok()
+                .map(|value| parse_setting("P8FS_MAX_FILE_BYTES", &value))
+                .transpose()?,
+            max_upload_bytes: env_setting("P8FS_MAX_UPLOAD_BYTES", "104857600")?,
+            disabled_types: env::var("P8FS_DISABLED_TYPES")
+                .unwrap_or_default()
+                .split(',')
+                .filter(|name| !name.trim().is_empty())
+                .map(|name| parse_setting("P8FS_DISABLED_TYPES", name))
+                .collect::<anyhow::Result<_>>()?,
+            batch_concurrency: env_setting::<usize>("P8FS_BATCH_CONCURRENCY", &DEFAULT_BATCH_CONCURRENCY.to_string())?
+                .max(1),
+            dispatch_permits: default_dispatch_permits(),
+            bind_addr: bind_address(
+                env::var("P8FS_BIND").ok().as_deref(),
+                env::var("P8FS_HOST").ok().as_deref(),
+                env::var("P8FS_PORT").ok().as_deref(),
+            )?,
+            allow_empty_input: env_setting("P8FS_ALLOW_EMPTY_INPUT", "false")?,
+            shutdown_timeout_secs: env_setting("P8FS_SHUTDOWN_TIMEOUT_SECS", "30")?,
+            process_timeout_secs: env_setting("P8FS_PROCESS_TIMEOUT_SECS", "300")?,
+            job_ttl_secs: env_setting("P8FS_JOB_TTL_SECS", &DEFAULT_JOB_TTL.as_secs().to_string())?,
+        })
+    }
+}
+
+/// The environment variable `name` parsed as a `T`, or `default` when it is unset.
+fn env_setting<T>(name: &str, default: &str) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    parse_setting(name, &env::var(name).unwrap_or_else(|_| default.to_string()))
+}
+
+/// Parses `value`, read from the environment variable `name`. The error names
+/// both, since the parser's own message does not say which setting is wrong.
+pub fn parse_setting<T>(name: &str, value: &str) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|err| anyhow::anyhow!("Invalid {} '{}': {}", name, value, err))
+}
+
+/// `bind` as a whole `ip:port` when set, otherwise `host` and `port` over the
+/// default `127.0.0.1:3000`. The host must be an IP address, not a name.
+pub fn bind_address(bind: Option<&str>, host: Option<&str>, port: Option<&str>) -> anyhow::Result<SocketAddr> {
+    if let Some(bind) = bind.filter(|bind| !bind.trim().is_empty()) {
+        return bind.trim().parse().map_err(|_| {
+            anyhow::anyhow!("Invalid P8FS_BIND '{}': expected an address and port such as 0.0.0.0:8080", bind)
+        });
+    }
+
+    let host = match host.filter(|host| !host.trim().is_empty()) {
+        Some(host) => host
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid P8FS_HOST '{}': expected an IP address such as 0.0.0.0", host))?,
+        None => DEFAULT_HOST,
+    };
+    let port = match port.filter(|port| !port.trim().is_empty()) {
+        Some(port) => port
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid P8FS_PORT '{}': expected a number from 0 to 65535", port))?,
+        None => DEFAULT_PORT,
+    };
+
+    Ok(SocketAddr::new(host, port))
+}
+
+fn default_dispatch_permits() -> Arc<Semaphore> {
+    Arc::new(Semaphore::new(DEFAULT_BATCH_CONCURRENCY))
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            temp_dir: env::temp_dir(),
+            min_file_bytes: 0,
+            in_memory_max_bytes: 1024 * 1024,
+            max_file_bytes: None,
+            max_upload_bytes: 100 * 1024 * 1024,
+            disabled_types: Vec::new(),
+            batch_concurrency: DEFAULT_BATCH_CONCURRENCY,
+            dispatch_permits: default_dispatch_permits(),
+            bind_addr: SocketAddr::new(DEFAULT_HOST, DEFAULT_PORT),
+            allow_empty_input: false,
+            shutdown_timeout_secs: 30,
+            process_timeout_secs: 300,
+            job_ttl_secs: DEFAULT_JOB_TTL.as_secs(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    #[test]
+    fn test_bind_address_defaults() {
+        let addr = bind_address(None, None, None).unwrap();
+        assert_eq!(addr, "127.0.0.1:3000".parse().unwrap());
+    }
+
+    #[test]
+    fn test_bind_address_from_host_and_port() {
+        assert_eq!(bind_address(None, Some("0.0.0.0"), None).unwrap(), "0.0.0.0:3000".parse().unwrap());
+        assert_eq!(bind_address(None, None, Some("8080")).unwrap(), "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(bind_address(None, Some("::"), Some("8080")).unwrap(), "[::]:8080".parse().unwrap());
+    }
+
+    #[test]
+    fn test_bind_address_prefers_bind() {
+        let addr = bind_address(Some("0.0.0.0:9000"), Some("10.0.0.1"), Some("8080")).unwrap();
+        assert_eq!(addr, "0.0.0.0:9000".parse().unwrap());
+    }
+
+    #[test]
+    fn test_parse_setting_names_the_variable() {
+        assert_eq!(parse_setting::<u64>("P8FS_MIN_FILE_BYTES", "512").unwrap(), 512);
+
+        let err = parse_setting::<u64>("P8FS_PROCESS_TIMEOUT_SECS", "5m").unwrap_err().to_string();
+        assert!(err.contains("P8FS_PROCESS_TIMEOUT_SECS") && err.contains("'5m'"), "{}", err);
+
+        let err = parse_setting::<ContentType>("P8FS_DISABLED_TYPES", "spreadsheets").unwrap_err().to_string();
+        assert!(err.contains("P8FS_DISABLED_TYPES") && err.contains("'spreadsheets'"), "{}", err);
+    }
+
+    #[test]
+    fn test_hash_algo_setting() {
+        assert_eq!(Hasher::from_setting(None).unwrap(), Hasher::Blake3);
+        assert_eq!(Hasher::from_setting(Some(" ")).unwrap(), Hasher::Blake3);
+        assert_eq!(Hasher::from_setting(Some("sha256")).unwrap(), Hasher::Sha256);
+
+        let err = Hasher::from_setting(Some("md5")).unwrap_err();
+        assert!(err.to_string().contains("P8FS_HASH_ALGO"));
+    }
+
+    #[test]
+    fn test_bind_address_rejects_invalid_values() {
+        let err = bind_address(Some("0.0.0.0"), None, None).unwrap_err();
+        assert!(err.to_string().contains("P8FS_BIND"));
+
+        let err = bind_address(None, Some("localhost"), None).unwrap_err();
+        assert!(err.to_string().contains("P8FS_HOST"));
+
+        let err = bind_address(None, None, Some("70000")).unwrap_err();
+        assert!(err.to_string().contains("P8FS_PORT"));
+    }
+}
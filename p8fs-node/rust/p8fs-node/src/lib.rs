@@ -1,4 +1,8 @@
 pub mod api;
+pub mod config;
 pub mod models;
 pub mod providers;
-pub mod services;
\ No newline at end of file
+pub mod services;
+pub mod shutdown;
+
+pub use config::Config;
@@ -1,11 +1,14 @@
 mod api;
+mod config;
 mod models;
 mod providers;
 mod services;
+mod shutdown;
 
 use axum::Router;
-use std::net::SocketAddr;
-use tracing::info;
+use config::Config;
+use services::EmbeddingService;
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -20,15 +23,32 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting p8fs-node server");
 
+    let config = Config::from_env()?;
+    let addr = config.bind_addr;
+    let shutdown_timeout = std::time::Duration::from_secs(config.shutdown_timeout_secs);
+
+    // Load the model up front so a dimension mismatch is logged at startup
+    // rather than on the first request
+    tokio::spawn(async {
+        match EmbeddingService::global().await {
+            Ok(service) => {
+                if let Err(err) = service.dimension_mismatch().await {
+                    warn!("Could not check the embedding dimension: {}", err);
+                }
+            }
+            Err(err) => warn!("Embedding service failed to initialize: {}", err),
+        }
+    });
+
     let app = Router::new()
-        .nest("/api/v1", api::create_router())
+        .nest("/api/v1", api::create_router(config.clone()))
+        .nest("/v1", api::create_openai_router(config))
         .fallback(|| async { "p8fs-node server" });
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     info!("Server listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    shutdown::serve(listener, app, shutdown::signal(), shutdown_timeout).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
-use serde::{Deserialize, Serialize};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use uuid::Uuid;
 
 #[cfg(test)]
 mod tests;
@@ -14,6 +17,7 @@ pub enum ContentType {
     Text,
     Markdown,
     StructuredData,
+    Yaml,
     Document,
     Spreadsheet,
     Presentation,
@@ -21,9 +25,22 @@ pub enum ContentType {
     Code,
     Email,
     Web,
+    /// Fixed-width columnar text. Never detected; uploads must force it.
+    FixedWidth,
+    /// Timed captions (SubRip or WebVTT).
+    Subtitle,
     Unknown,
 }
 
+impl std::str::FromStr for ContentType {
+    type Err = serde_json::Error;
+
+    /// Parses a serialized name case-insensitively, e.g. `pdf` or `PDF`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.trim().to_uppercase()))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentChunk {
     pub id: String,
@@ -44,29 +61,334 @@ pub struct ContentMetadata {
     pub additional: HashMap<String, serde_json::Value>,
 }
 
+/// Whether the provider actually ran over the upload.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessingStatus {
+    #[default]
+    Processed,
+    /// The upload was below the configured minimum size and was not processed.
+    Empty,
+    /// The provider could not read the upload, or one file of a batch was
+    /// rejected; `error` says why.
+    Failed,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentProcessingResult {
     pub success: bool,
+    #[serde(default)]
+    pub status: ProcessingStatus,
     pub chunks: Vec<ContentChunk>,
     pub metadata: ContentMetadata,
     pub error: Option<String>,
 }
 
+/// A chunk with its vector, as returned by `/content/embed`. The chunk's
+/// fields are flattened alongside `embedding`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedChunk {
+    #[serde(flatten)]
+    pub chunk: ContentChunk,
+    pub embedding: Vec<f32>,
+}
+
+/// `ContentProcessingResult` with every chunk embedded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedContentResult {
+    pub success: bool,
+    #[serde(default)]
+    pub status: ProcessingStatus,
+    pub chunks: Vec<EmbeddedChunk>,
+    pub metadata: ContentMetadata,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+/// A background processing job. `result` is set once the job is `Done`,
+/// `error` once it has `Failed`. A job whose provider could not read the
+/// upload has both, the result carrying `success: false`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobState {
+    pub job_id: Uuid,
+    pub status: JobStatus,
+    #[serde(default)]
+    pub progress: JobProgress,
+    pub result: Option<ContentProcessingResult>,
+    pub error: Option<String>,
+}
+
+/// How far a background job has got. `total_estimate` is unknown until the
+/// provider has extracted the chunks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JobProgress {
+    pub processed_chunks: usize,
+    pub total_estimate: Option<usize>,
+    pub phase: JobPhase,
+}
+
+/// The pipeline step a background job is in.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPhase {
+    #[default]
+    Queued,
+    /// The provider is reading the upload.
+    Extracting,
+    /// The chunks are being embedded.
+    Embedding,
+    /// Every chunk is processed; the job is about to finish.
+    Complete,
+}
+
+/// A content type with a provider, and the file extensions that resolve to it.
+/// Types without extensions are only reachable by forcing the type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportedType {
+    pub content_type: ContentType,
+    pub extensions: Vec<String>,
+}
+
+/// How chunk content is rendered in processing responses. Providers always
+/// produce markdown; other formats are derived from it after processing.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Markdown,
+    Plain,
+    Html,
+}
+
+/// How text-only formats (DOCX, RTF) are cut into chunks. Formats with their
+/// own structure (markdown sections, slides, JSON kinds) keep it either way.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChunkStrategy {
+    /// Windows of a fixed number of characters, overlapping the previous one.
+    #[default]
+    Fixed,
+    /// Sentences merged while each stays at least `similarity_threshold`
+    /// (cosine) from the one before it, up to the fixed window size. Needs
+    /// the embedding model.
+    Semantic { similarity_threshold: f32 },
+}
+
+/// Per-request options for the content processing endpoints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessingOptions {
+    pub output_format: OutputFormat,
+    /// Attach the provider's native metadata under `additional["raw_metadata"]`.
+    /// Off by default since it can be large.
+    pub include_raw_metadata: bool,
+    /// Embed the chunks and attach each vector as `metadata["embedding"]`.
+    pub embed: bool,
+    /// Limits `embed` to matching chunks. Given in the query string as JSON,
+    /// e.g. `embed_filter={"exclude_sections":["Appendix"]}`.
+    #[serde(deserialize_with = "deserialize_json_string")]
+    pub embed_filter: EmbedFilter,
+    /// Chunk metadata fields whose values are put, one per line, before the
+    /// content that is embedded; the returned content is unchanged. `title`
+    /// falls back to the document title. Given in the query string as JSON,
+    /// e.g. `embed_metadata_fields=["title","section_title"]`.
+    #[serde(deserialize_with = "deserialize_json_string")]
+    pub embed_metadata_fields: Vec<String>,
+    /// Attach the sentence boundaries within each chunk as
+    /// `metadata["sentences"]`, a list of `{start, end}` byte offsets into the
+    /// chunk content.
+    pub sentence_offsets: bool,
+    /// Drop chunks that repeat an earlier one, such as a page header or
+    /// footer, listing them in `additional["duplicate_chunks"]`.
+    pub dedupe: bool,
+    /// Put a chunk summarizing the whole document, marked
+    /// `metadata["chunk_type"] = "summary"`, before the provider's chunks.
+    pub summary_chunk: bool,
+    /// Column spec for `FIXEDWIDTH` uploads, given in the query string as
+    /// JSON, e.g. `columns=[{"name":"id","width":6},{"name":"city","width":12}]`.
+    #[serde(deserialize_with = "deserialize_json_string")]
+    pub columns: Vec<FixedWidthColumn>,
+    /// The chunks from an earlier processing of the same document. When set,
+    /// only chunks whose content hash is not among them are returned, and
+    /// prior chunks that no longer exist are listed as deleted.
+    #[serde(deserialize_with = "deserialize_json_string")]
+    pub prior_chunks: Option<Vec<PriorChunk>>,
+    /// Given in the query string as JSON, e.g.
+    /// `chunk_strategy={"type":"semantic","similarity_threshold":0.6}`.
+    #[serde(deserialize_with = "deserialize_json_string")]
+    pub chunk_strategy: ChunkStrategy,
+    /// Emit JSON and YAML chunks grouped by `kind` instead of in document
+    /// order. Each chunk records `kind_group`, `group_position` and `group_size`.
+    pub group_by_kind: bool,
+    /// With `group_by_kind`, lead each group with a chunk listing its members.
+    pub kind_summaries: bool,
+}
+
+/// A chunk as stored by a client after an earlier processing run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PriorChunk {
+    pub id: String,
+    pub content_hash: String,
+}
+
+/// One column of a fixed-width layout, `width` characters wide.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    pub width: usize,
+}
+
+/// Which chunks `embed` applies to. A chunk is embedded only if it meets every
+/// criterion that is set; the default filter embeds everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct EmbedFilter {
+    /// Embed only chunks whose `section_title` starts with one of these.
+    pub sections: Vec<String>,
+    /// Skip chunks whose `section_title` starts with one of these.
+    pub exclude_sections: Vec<String>,
+    /// Skip chunks with fewer characters of content than this.
+    pub min_length: usize,
+    /// Embed only chunks of these content types.
+    pub content_types: Vec<ContentType>,
+}
+
+/// Query strings cannot nest, so structured options arrive there as a JSON
+/// string. JSON bodies (e.g. the `chunk_options` upload field) may give the
+/// value directly.
+fn deserialize_json_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(json) => serde_json::from_str(&json).map_err(serde::de::Error::custom),
+        value => serde_json::from_value(value).map_err(serde::de::Error::custom),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingRequest {
+    #[serde(deserialize_with = "deserialize_input")]
     pub input: Vec<String>,
     pub model: Option<String>,
     pub encoding_format: Option<String>,
     pub dimensions: Option<usize>,
+    pub normalize: Option<bool>,
+    /// Whether the inputs are search queries or the passages searched, for
+    /// models that expect a different prefix on each.
+    pub input_type: Option<InputType>,
+}
+
+/// The side of an asymmetric retrieval an input is on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+    Query,
+    Passage,
+}
+
+/// OpenAI clients send `input` either as a single string or as an array of strings.
+fn deserialize_input<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Input {
+        Single(String),
+        Batch(Vec<String>),
+    }
+
+    Ok(match Input::deserialize(deserializer)? {
+        Input::Single(text) => vec![text],
+        Input::Batch(texts) => texts,
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingData {
     pub object: String,
-    pub embedding: Vec<f32>,
+    pub embedding: EmbeddingVector,
     pub index: usize,
 }
 
+/// An embedding as returned to clients: an array of floats, or with
+/// `encoding_format: "base64"` the base64 encoding of its little-endian f32
+/// bytes, which is about half the size in JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingVector {
+    Float(Vec<f32>),
+    Base64(String),
+}
+
+impl EmbeddingVector {
+    /// The number of dimensions.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Float(values) => values.len(),
+            Self::Base64(encoded) => {
+                let padding = encoded.bytes().rev().take_while(|&b| b == b'=').count();
+                (encoded.len() / 4 * 3).saturating_sub(padding) / 4
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn to_base64(&self) -> Self {
+        match self {
+            Self::Float(values) => {
+                let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+                Self::Base64(BASE64_STANDARD.encode(bytes))
+            }
+            encoded => encoded.clone(),
+        }
+    }
+
+    /// The values as floats, decoding a base64 buffer.
+    pub fn to_floats(&self) -> anyhow::Result<Cow<'_, [f32]>> {
+        match self {
+            Self::Float(values) => Ok(Cow::Borrowed(values)),
+            Self::Base64(encoded) => {
+                let bytes = BASE64_STANDARD.decode(encoded)?;
+                if bytes.len() % 4 != 0 {
+                    anyhow::bail!("Base64 embedding is {} bytes, not a whole number of f32 values", bytes.len());
+                }
+                Ok(Cow::Owned(
+                    bytes
+                        .chunks_exact(4)
+                        .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+                        .collect(),
+                ))
+            }
+        }
+    }
+
+    pub fn into_floats(self) -> anyhow::Result<Vec<f32>> {
+        match self {
+            Self::Float(values) => Ok(values),
+            encoded => encoded.to_floats().map(Cow::into_owned),
+        }
+    }
+}
+
+impl From<Vec<f32>> for EmbeddingVector {
+    fn from(values: Vec<f32>) -> Self {
+        Self::Float(values)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: usize,
@@ -78,5 +400,47 @@ pub struct EmbeddingResponse {
     pub object: String,
     pub data: Vec<EmbeddingData>,
     pub model: String,
+    /// Length of each returned embedding, after any `dimensions` truncation.
+    #[serde(default)]
+    pub dimensions: usize,
     pub usage: Usage,
-}
\ No newline at end of file
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankRequest {
+    pub query: String,
+    pub documents: Vec<String>,
+    /// How many of the best documents to return; all of them when unset.
+    pub top_n: Option<usize>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RerankResult {
+    /// Position of the document in the request's `documents`.
+    pub index: usize,
+    pub score: f32,
+    pub document: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankResponse {
+    pub model: String,
+    /// Most relevant first.
+    pub results: Vec<RerankResult>,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityRequest {
+    pub a: String,
+    pub b: String,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityResponse {
+    pub model: String,
+    /// Cosine similarity of the two embeddings, from -1.0 to 1.0.
+    pub similarity: f32,
+    pub usage: Usage,
+}
@@ -13,6 +13,13 @@ mod tests {
         assert_eq!(deserialized, ContentType::Pdf);
     }
 
+    #[test]
+    fn test_content_type_from_str() {
+        assert_eq!("pdf".parse::<ContentType>().unwrap(), ContentType::Pdf);
+        assert_eq!(" Markdown ".parse::<ContentType>().unwrap(), ContentType::Markdown);
+        assert!("bogus".parse::<ContentType>().is_err());
+    }
+
     #[test]
     fn test_content_chunk_creation() {
         let mut metadata = HashMap::new();
@@ -74,6 +81,7 @@ mod tests {
         
         let result = ContentProcessingResult {
             success: true,
+            status: ProcessingStatus::Processed,
             chunks: vec![chunk],
             metadata,
             error: None,
@@ -91,6 +99,8 @@ mod tests {
             model: Some("test-model".to_string()),
             encoding_format: Some("float".to_string()),
             dimensions: Some(384),
+            normalize: None,
+            input_type: None,
         };
         
         assert_eq!(request.input.len(), 2);
@@ -98,17 +108,27 @@ mod tests {
         assert_eq!(request.dimensions, Some(384));
     }
 
+    #[test]
+    fn test_embedding_request_accepts_single_string_input() {
+        let request: EmbeddingRequest = serde_json::from_value(json!({ "input": "Hello" })).unwrap();
+        assert_eq!(request.input, vec!["Hello".to_string()]);
+
+        let request: EmbeddingRequest = serde_json::from_value(json!({ "input": ["Hello", "World"] })).unwrap();
+        assert_eq!(request.input, vec!["Hello".to_string(), "World".to_string()]);
+        assert!(request.model.is_none());
+    }
+
     #[test]
     fn test_embedding_response() {
         let data = vec![
             EmbeddingData {
                 object: "embedding".to_string(),
-                embedding: vec![0.1, 0.2, 0.3],
+                embedding: vec![0.1, 0.2, 0.3].into(),
                 index: 0,
             },
             EmbeddingData {
                 object: "embedding".to_string(),
-                embedding: vec![0.4, 0.5, 0.6],
+                embedding: vec![0.4, 0.5, 0.6].into(),
                 index: 1,
             },
         ];
@@ -117,6 +137,7 @@ mod tests {
             object: "list".to_string(),
             data: data.clone(),
             model: "test-model".to_string(),
+            dimensions: 3,
             usage: Usage {
                 prompt_tokens: 4,
                 total_tokens: 4,
@@ -129,4 +150,25 @@ mod tests {
         assert_eq!(response.object, "list");
         assert_eq!(response.usage.total_tokens, 4);
     }
+
+    #[test]
+    fn test_base64_embedding_round_trip() {
+        let values = vec![0.1, -2.5, 3.0e-8, f32::MAX];
+        let data = EmbeddingData {
+            object: "embedding".to_string(),
+            embedding: EmbeddingVector::from(values.clone()).to_base64(),
+            index: 0,
+        };
+
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json["embedding"].is_string());
+
+        let decoded: EmbeddingData = serde_json::from_value(json).unwrap();
+        assert!(matches!(decoded.embedding, EmbeddingVector::Base64(_)));
+        assert_eq!(decoded.embedding.len(), 4);
+        assert_eq!(decoded.embedding.into_floats().unwrap(), values);
+
+        let floats: EmbeddingData = serde_json::from_value(json!({ "object": "embedding", "embedding": [0.5], "index": 0 })).unwrap();
+        assert_eq!(floats.embedding, EmbeddingVector::Float(vec![0.5]));
+    }
 }
\ No newline at end of file
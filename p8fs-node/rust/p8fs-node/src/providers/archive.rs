@@ -0,0 +1,206 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::limits::{ResourceLimits, DEFAULT_BATCH_CONCURRENCY};
+use crate::providers::registry::{self, ProviderFactory};
+use crate::providers::{fill_default_metadata, ids, outcome, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use futures::{stream, FutureExt, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Chunks a ZIP archive by running every entry with a supported extension
+/// through its own provider. Each chunk records the entry it came from as
+/// `archive_path` and that entry's own `content_type` and `title`, so a
+/// PDF's chunks can be told from a markdown file's in the same archive.
+///
+/// Expansion is bounded by the resource limits: the entry count by
+/// `max_entries` and the total uncompressed size by `max_total_bytes`,
+/// counted as entries are actually inflated rather than trusting the sizes
+/// the archive declares. Nested archives are skipped, so a self-replicating
+/// zip cannot recurse.
+///
+/// Entries are inflated one at a time, then dispatched to their providers.
+/// One entry runs under the permit the archive itself was processed with;
+/// each further entry running alongside it needs one of the server's
+/// dispatch permits that is free at the start. Waiting for a permit the
+/// caller might hold could deadlock, so an archive never does. Chunks are
+/// collected in path order, so the result does not depend on which entry
+/// finishes first.
+pub struct ArchiveProvider {
+    limits: ResourceLimits,
+    permits: Arc<Semaphore>,
+}
+
+/// An entry that was not turned into chunks.
+enum Outcome {
+    Skipped,
+    Failed(String),
+}
+
+impl ArchiveProvider {
+    pub fn new() -> Self {
+        Self::with_limits(ResourceLimits::global())
+    }
+
+    pub fn with_limits(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            permits: Arc::new(Semaphore::new(DEFAULT_BATCH_CONCURRENCY)),
+        }
+    }
+
+    pub(crate) async fn process_archive(&self, bytes: &[u8], file_name: Option<String>) -> anyhow::Result<ContentProcessingResult> {
+        let doc_hash = ids::doc_hash(bytes);
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+        let mut entries = Vec::new();
+        let mut total_bytes = 0u64;
+
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            if entry.is_dir() {
+                continue;
+            }
+            self.limits.check_entries(entries.len() + 1)?;
+
+            // Read one byte past the remaining budget to notice an overrun
+            let remaining = self.limits.max_total_bytes.saturating_sub(total_bytes);
+            let mut data = Vec::new();
+            entry.by_ref().take(remaining + 1).read_to_end(&mut data)?;
+            total_bytes += data.len() as u64;
+            self.limits.check_total_bytes(total_bytes)?;
+
+            entries.push((entry.name().to_string(), data));
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let extra_permits: Vec<_> = (1..entries.len())
+            .map_while(|_| self.permits.clone().try_acquire_owned().ok())
+            .collect();
+        let dispatches: Vec<_> = entries.iter().map(|(path, data)| process_entry(path, data).boxed()).collect();
+        let outcomes: Vec<_> = stream::iter(dispatches)
+            .buffered(1 + extra_permits.len())
+            .collect()
+            .await;
+        drop(extra_permits);
+
+        let mut chunks = Vec::new();
+        let mut processed = Vec::new();
+        let mut skipped = Vec::new();
+        let mut failed = Vec::new();
+
+        for ((path, _), outcome) in entries.into_iter().zip(outcomes) {
+            match outcome {
+                Ok(mut result) => {
+                    processed.push(path.clone());
+                    fill_default_metadata(&mut result.metadata, &path);
+                    let content_type = serde_json::json!(result.metadata.content_type);
+                    let title = serde_json::json!(result.metadata.title);
+                    for mut chunk in result.chunks {
+                        let i = chunks.len();
+                        chunk.metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                        chunk.metadata.insert("archive_path".to_string(), serde_json::json!(path));
+                        chunk.metadata.insert("content_type".to_string(), content_type.clone());
+                        chunk.metadata.insert("title".to_string(), title.clone());
+                        chunks.push(chunk);
+                    }
+                }
+                Err(Outcome::Skipped) => skipped.push(path),
+                Err(Outcome::Failed(error)) => failed.push(serde_json::json!({ "path": path, "error": error })),
+            }
+        }
+
+        ids::assign_chunk_ids("archive", &doc_hash, &mut chunks);
+
+        let mut additional = HashMap::new();
+        additional.insert("entries".to_string(), serde_json::json!(processed));
+        additional.insert("skipped".to_string(), serde_json::json!(skipped));
+        if !failed.is_empty() {
+            additional.insert("failed".to_string(), Value::Array(failed));
+        }
+
+        Ok(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Processed,
+            chunks,
+            metadata: ContentMetadata {
+                content_type: ContentType::Archive,
+                file_name,
+                file_size: Some(bytes.len() as u64),
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: None,
+                language: None,
+                additional,
+            },
+            error: None,
+        })
+    }
+}
+
+/// Runs one entry through the provider for its extension, from memory when
+/// the provider supports it and from a temp file otherwise.
+async fn process_entry(path: &str, data: &[u8]) -> Result<ContentProcessingResult, Outcome> {
+    let extension = Path::new(path).extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let provider: ProviderFactory = match registry::get_provider_by_extension(extension) {
+        Some((ContentType::Archive, _)) | None => return Err(Outcome::Skipped),
+        Some((_, provider)) => provider,
+    };
+
+    let run = async {
+        if let Some(result) = provider.process_bytes(data).await? {
+            return Ok(result);
+        }
+
+        let temp_file = tempfile::Builder::new()
+            .prefix("p8fs-archive-")
+            .suffix(&format!(".{}", extension))
+            .tempfile()?;
+        tokio::fs::write(temp_file.path(), data).await?;
+        provider.process_content(temp_file.path()).await
+    };
+
+    run.await.map_err(|e: anyhow::Error| Outcome::Failed(e.to_string()))
+}
+
+#[async_trait]
+impl ContentProvider for ArchiveProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        match self.process_archive(&file_bytes, file_name.clone()).await {
+            Err(err) => {
+                let metadata = outcome::basic_metadata(ContentType::Archive, file_name, Some(file_bytes.len() as u64));
+                outcome::failed(err, metadata)
+            }
+            result => result,
+        }
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        Ok(self.process_content(file_path).await?.chunks)
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        Ok(self.process_content(file_path).await?.metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    fn with_dispatch_permits(&self, permits: Arc<Semaphore>) -> Option<Arc<dyn ContentProvider>> {
+        Some(Arc::new(ArchiveProvider { limits: self.limits, permits }))
+    }
+}
@@ -1,9 +1,10 @@
-use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
-use crate::providers::ContentProvider;
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::{ids, outcome, ContentProvider};
 use crate::services::EmbeddingService;
 use async_trait::async_trait;
-use hound::{WavReader, WavSpec};
+use hound::{SampleFormat, WavReader, WavSpec};
 use std::collections::HashMap;
+use std::io::Cursor;
 use std::path::Path;
 
 pub struct AudioProvider;
@@ -13,55 +14,119 @@ impl AudioProvider {
         Self
     }
 
-    fn extract_wav_info(&self, file_path: &Path) -> anyhow::Result<(WavSpec, Vec<i16>)> {
-        let mut reader = WavReader::open(file_path)?;
+    /// Decodes every sample, in whatever format the file declares, and
+    /// returns the spec with the number of frames (samples per channel).
+    fn extract_wav_info(&self, bytes: &[u8]) -> anyhow::Result<(WavSpec, usize)> {
+        let mut reader = WavReader::new(Cursor::new(bytes))?;
         let spec = reader.spec();
-        let samples: Vec<i16> = reader.samples::<i16>().collect::<Result<Vec<_>, _>>()?;
-        Ok((spec, samples))
+
+        let sample_count = match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Int, 8) => count_samples(reader.samples::<i8>())?,
+            (SampleFormat::Int, 16) => count_samples(reader.samples::<i16>())?,
+            (SampleFormat::Int, 24 | 32) => count_samples(reader.samples::<i32>())?,
+            (SampleFormat::Float, 32) => count_samples(reader.samples::<f32>())?,
+            (format, bits) => anyhow::bail!("Unsupported WAV sample format: {}-bit {:?}", bits, format),
+        };
+
+        Ok((spec, sample_count / spec.channels.max(1) as usize))
     }
 
-    fn segment_audio(&self, samples: &[i16], sample_rate: u32, segment_duration_secs: f32) -> Vec<(usize, usize)> {
-        let samples_per_segment = (sample_rate as f32 * segment_duration_secs) as usize;
+    /// Splits `frame_count` frames into consecutive `(start, end)` frame ranges
+    /// of `segment_duration_secs` each; the last may be shorter.
+    pub(crate) fn segment_audio(&self, frame_count: usize, sample_rate: u32, segment_duration_secs: f32) -> Vec<(usize, usize)> {
+        let frames_per_segment = (sample_rate as f32 * segment_duration_secs) as usize;
         let mut segments = Vec::new();
         let mut start = 0;
 
-        while start < samples.len() {
-            let end = (start + samples_per_segment).min(samples.len());
+        while start < frame_count {
+            let end = (start + frames_per_segment).min(frame_count);
             segments.push((start, end));
             start = end;
         }
 
         segments
     }
+
+    /// Reads the text tags from a WAV file's `LIST`/`INFO` chunk, keyed by
+    /// their four-character ID (e.g. `INAM`, `IART`). Empty when there are none.
+    pub(crate) fn read_info_tags(&self, bytes: &[u8]) -> HashMap<String, String> {
+        let mut tags = HashMap::new();
+        if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return tags;
+        }
+
+        for (id, data) in riff_chunks(&bytes[12..]) {
+            if id != b"LIST" || data.len() < 4 || &data[..4] != b"INFO" {
+                continue;
+            }
+
+            for (tag, value) in riff_chunks(&data[4..]) {
+                let value = String::from_utf8_lossy(value);
+                let value = value.trim_end_matches('\0').trim();
+                if !value.is_empty() {
+                    tags.insert(String::from_utf8_lossy(tag).into_owned(), value.to_string());
+                }
+            }
+        }
+
+        tags
+    }
+
+}
+
+/// INFO tags copied into `additional` when present, as `(key, tag)`. Artist,
+/// title and creation date have their own fields.
+const EXTRA_TAGS: [(&str, &str); 5] = [
+    ("album", "IPRD"),
+    ("genre", "IGNR"),
+    ("comment", "ICMT"),
+    ("copyright", "ICOP"),
+    ("software", "ISFT"),
+];
+
+fn count_samples<S, E>(mut samples: impl Iterator<Item = Result<S, E>>) -> Result<usize, E> {
+    samples.try_fold(0, |count, sample| sample.map(|_| count + 1))
+}
+
+/// Splits RIFF chunk data into `(id, data)` pairs, stopping at the first
+/// truncated chunk. Chunks are padded to an even length.
+fn riff_chunks(mut bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+
+    while bytes.len() >= 8 {
+        let size = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+        let Some(data) = bytes.get(8..8 + size) else {
+            break;
+        };
+        chunks.push((&bytes[..4], data));
+        bytes = bytes.get(8 + size + size % 2..).unwrap_or_default();
+    }
+
+    chunks
 }
 
 #[async_trait]
 impl ContentProvider for AudioProvider {
     async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
-        let chunks = self.to_markdown_chunks(file_path).await?;
-        let metadata = self.to_metadata(file_path).await?;
-        
-        Ok(ContentProcessingResult {
-            success: true,
-            chunks,
-            metadata,
-            error: None,
-        })
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Audio, file_path, chunks, metadata).await
     }
 
     async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
-        let (spec, samples) = tokio::task::spawn_blocking({
-            let path = file_path.to_owned();
-            move || {
-                let provider = AudioProvider::new();
-                provider.extract_wav_info(&path)
-            }
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let doc_hash = ids::doc_hash(&file_bytes);
+
+        let (spec, frame_count) = tokio::task::spawn_blocking(move || {
+            let provider = AudioProvider::new();
+            provider.extract_wav_info(&file_bytes)
         })
         .await??;
 
-        let segments = self.segment_audio(&samples, spec.sample_rate, 30.0);
+        let segments = self.segment_audio(frame_count, spec.sample_rate, 30.0);
         
-        let chunks: Vec<ContentChunk> = segments
+        let mut chunks: Vec<ContentChunk> = segments
             .into_iter()
             .enumerate()
             .map(|(i, (start, end))| {
@@ -74,7 +139,7 @@ impl ContentProvider for AudioProvider {
                 metadata.insert("bits_per_sample".to_string(), serde_json::json!(spec.bits_per_sample));
                 
                 ContentChunk {
-                    id: format!("audio_segment_{}", i),
+                    id: String::new(),
                     content: format!("## Audio Segment {}\n\n**Duration:** {:.1}s - {:.1}s  \n**Samples:** {} - {}  \n**Sample Rate:** {} Hz  \n**Channels:** {}  \n**Bit Depth:** {} bits\n\n*[Audio content analysis would go here - transcription, audio features, etc.]*", 
                         i + 1,
                         start as f32 / spec.sample_rate as f32,
@@ -90,49 +155,53 @@ impl ContentProvider for AudioProvider {
             })
             .collect();
 
+        ids::assign_chunk_ids("audio", &doc_hash, &mut chunks);
         Ok(chunks)
     }
 
     async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
         let file_metadata = tokio::fs::metadata(file_path).await?;
+        let file_bytes = tokio::fs::read(file_path).await?;
         
-        let (spec, samples) = tokio::task::spawn_blocking({
-            let path = file_path.to_owned();
-            move || {
-                let provider = AudioProvider::new();
-                provider.extract_wav_info(&path)
-            }
+        let (spec, frame_count, mut tags) = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let provider = AudioProvider::new();
+            let (spec, frame_count) = provider.extract_wav_info(&file_bytes)?;
+            Ok((spec, frame_count, provider.read_info_tags(&file_bytes)))
         })
         .await??;
 
-        let duration_secs = samples.len() as f32 / spec.sample_rate as f32;
+        let duration_secs = frame_count as f32 / spec.sample_rate as f32;
         
         let mut additional = HashMap::new();
         additional.insert("duration_seconds".to_string(), serde_json::json!(duration_secs));
         additional.insert("sample_rate".to_string(), serde_json::json!(spec.sample_rate));
         additional.insert("channels".to_string(), serde_json::json!(spec.channels));
         additional.insert("bits_per_sample".to_string(), serde_json::json!(spec.bits_per_sample));
+        for (key, tag) in EXTRA_TAGS {
+            if let Some(value) = tags.remove(tag) {
+                additional.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
 
         Ok(ContentMetadata {
             content_type: ContentType::Audio,
             file_name: file_path.file_name().map(|n| n.to_string_lossy().to_string()),
             file_size: Some(file_metadata.len()),
-            created_at: None,
+            created_at: tags.remove("ICRD"),
             modified_at: None,
-            author: None,
-            title: None,
+            author: tags.remove("IART"),
+            title: tags.remove("INAM"),
             language: None,
             additional,
         })
     }
 
     async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
-        let service = EmbeddingService::global();
-        let service = service.lock().await;
+        let service = EmbeddingService::global().await?;
         
         let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-        let response = service.embed(texts).await?;
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
         
-        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
     }
 }
\ No newline at end of file
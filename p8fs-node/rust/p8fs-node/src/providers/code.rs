@@ -0,0 +1,353 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::limits::env_or;
+use crate::providers::{ids, outcome, text, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Java,
+    C,
+    Cpp,
+}
+
+/// Lines that start a top-level definition, per language. Each pattern names
+/// the defined symbol `name` and only ever sees unindented lines.
+static DEFINITIONS: Lazy<HashMap<Language, Vec<Regex>>> = Lazy::new(|| {
+    let js = [
+        r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(?:function\s*\*?|class|interface|type|enum|namespace)\s+(?P<name>[\w$]+)",
+        r"^(?:export\s+)?(?:const|let|var)\s+(?P<name>[\w$]+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|(?:\([^)]*\)|[\w$]+)\s*(?::[^=]+)?=>)",
+    ];
+    let c_types = r"^(?:typedef\s+)?(?:struct|union|enum)\s+(?P<name>\w+)\s*\{";
+
+    let patterns: [(Language, Vec<&str>); 8] = [
+        (
+            Language::Rust,
+            vec![
+                r#"^(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*(?:fn|struct|enum|trait|union|mod|type|static|const)\s+(?P<name>\w+)"#,
+                r"^macro_rules!\s*(?P<name>\w+)",
+                r"^(?:unsafe\s+)?impl\b(?:\s*<[^{]*?>)?\s+(?P<name>[^{]+?)\s*\{?\s*$",
+            ],
+        ),
+        (Language::Python, vec![r"^(?:async\s+)?(?:def|class)\s+(?P<name>\w+)"]),
+        (Language::JavaScript, js.to_vec()),
+        (Language::TypeScript, js.to_vec()),
+        (Language::Go, vec![r"^func\s+(?:\([^)]*\)\s*)?(?P<name>\w+)", r"^type\s+(?P<name>\w+)"]),
+        (
+            Language::Java,
+            vec![r"^(?:(?:public|protected|private|abstract|final|static|sealed|non-sealed|strictfp)\s+)*(?:class|interface|enum|record|@interface)\s+(?P<name>\w+)"],
+        ),
+        (
+            Language::C,
+            vec![
+                c_types,
+                // A function definition: no `;` on the line, unlike a prototype
+                r"^(?:(?:static|inline|extern|const|unsigned|signed|struct|enum)\s+)*[A-Za-z_]\w*(?:\s*\*+\s*|\s+)(?P<name>[A-Za-z_]\w*)\s*\([^;]*$",
+            ],
+        ),
+        (
+            Language::Cpp,
+            vec![
+                c_types,
+                r"^(?:template\s*<[^>]*>\s*)?(?:class|struct|namespace)\s+(?P<name>\w+)",
+                r"^(?:(?:static|inline|extern|const|virtual|constexpr|unsigned|signed)\s+)*[A-Za-z_][\w:<>,]*(?:\s*[*&]+\s*|\s+)(?P<name>[A-Za-z_~][\w:~]*)\s*\([^;]*$",
+            ],
+        ),
+    ];
+
+    patterns
+        .into_iter()
+        .map(|(language, patterns)| (language, patterns.into_iter().map(|p| Regex::new(p).unwrap()).collect()))
+        .collect()
+});
+
+impl Language {
+    pub const ALL: [Self; 8] = [
+        Self::Rust,
+        Self::Python,
+        Self::JavaScript,
+        Self::TypeScript,
+        Self::Go,
+        Self::Java,
+        Self::C,
+        Self::Cpp,
+    ];
+
+    /// The file extensions the registry routes to this language.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["rs"],
+            Self::Python => &["py"],
+            Self::JavaScript => &["js"],
+            Self::TypeScript => &["ts"],
+            Self::Go => &["go"],
+            Self::Java => &["java"],
+            Self::C => &["c"],
+            Self::Cpp => &["cpp"],
+        }
+    }
+
+    /// The MIME types the registry routes to this language.
+    pub fn mime_types(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["text/rust", "text/x-rust"],
+            Self::Python => &["text/x-python", "application/x-python"],
+            Self::JavaScript => &["text/javascript", "application/javascript"],
+            Self::TypeScript => &["application/typescript", "text/typescript"],
+            Self::Go => &["text/x-go"],
+            Self::Java => &["text/x-java", "text/x-java-source"],
+            Self::C => &["text/x-c", "text/x-csrc"],
+            Self::Cpp => &["text/x-c++", "text/x-c++src"],
+        }
+    }
+
+    /// The language whose `extensions` include `extension`, ignoring case.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.extensions().iter().any(|known| known.eq_ignore_ascii_case(extension)))
+    }
+
+    /// The lowercase name used in metadata and as the code fence info string.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+            Self::TypeScript => "typescript",
+            Self::Go => "go",
+            Self::Java => "java",
+            Self::C => "c",
+            Self::Cpp => "cpp",
+        }
+    }
+
+    /// Whether an unindented line is a comment, attribute or decorator that
+    /// belongs to the definition below it.
+    fn is_leading(self, line: &str) -> bool {
+        let line = line.trim_start();
+        let comment = line.starts_with("//") || line.starts_with("/*") || line.starts_with('*');
+
+        match self {
+            Self::Rust => comment || line.starts_with("#["),
+            Self::Python => line.starts_with('#') || line.starts_with('@'),
+            _ => comment || line.starts_with('@'),
+        }
+    }
+}
+
+/// Chunks source code at top-level definitions (functions, types, impls and
+/// the like), found per language by pattern rather than by parsing. Files
+/// with no recognizable definitions, or in no known language, are cut into
+/// fixed line windows.
+pub struct CodeProvider {
+    language: Option<Language>,
+    lines_per_chunk: usize,
+}
+
+impl CodeProvider {
+    /// Uses `P8FS_CODE_LINES_PER_CHUNK` lines per window (default 60).
+    pub fn new(language: Language) -> Self {
+        Self {
+            language: Some(language),
+            ..Self::generic()
+        }
+    }
+
+    /// The default provider for `ContentType::Code`, which takes the language
+    /// from the file's extension. It has no file name to go on for an upload
+    /// held in memory, so it always reads from the staged file.
+    pub fn generic() -> Self {
+        Self {
+            language: None,
+            lines_per_chunk: env_or("P8FS_CODE_LINES_PER_CHUNK", 60).max(1),
+        }
+    }
+
+    pub fn lines_per_chunk(mut self, lines_per_chunk: usize) -> Self {
+        self.lines_per_chunk = lines_per_chunk.max(1);
+        self
+    }
+
+    /// This provider, with the language of `file_path`'s extension if it has
+    /// none of its own.
+    fn for_file(&self, file_path: &Path) -> Self {
+        Self {
+            language: self.language.or_else(|| {
+                file_path.extension().and_then(|ext| ext.to_str()).and_then(Language::from_extension)
+            }),
+            lines_per_chunk: self.lines_per_chunk,
+        }
+    }
+
+    fn definition_name(&self, line: &str) -> Option<String> {
+        let language = self.language?;
+        if line.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        DEFINITIONS[&language]
+            .iter()
+            .find_map(|pattern| pattern.captures(line))
+            .map(|captures| captures["name"].trim().to_string())
+    }
+
+    /// `(first line, last line, symbol)` for each piece of the file, with
+    /// 0-based inclusive line indices. A definition runs until the next one
+    /// starts, taking its leading comments and attributes along.
+    fn segments(&self, lines: &[&str]) -> Vec<(usize, usize, Option<String>)> {
+        let mut starts: Vec<(usize, String)> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let Some(name) = self.definition_name(line) else { continue };
+
+            let floor = starts.last().map_or(0, |(start, _)| start + 1);
+            let mut start = i;
+            while start > floor && !lines[start - 1].trim().is_empty() && self.language.is_some_and(|language| language.is_leading(lines[start - 1])) {
+                start -= 1;
+            }
+            starts.push((start, name));
+        }
+
+        if starts.is_empty() {
+            return (0..lines.len())
+                .step_by(self.lines_per_chunk)
+                .map(|start| (start, (start + self.lines_per_chunk).min(lines.len()) - 1, None))
+                .collect();
+        }
+
+        let mut segments = Vec::new();
+        if starts[0].0 > 0 {
+            segments.push((0, starts[0].0 - 1, None));
+        }
+        for (i, (start, name)) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).map_or(lines.len(), |(next, _)| *next) - 1;
+            segments.push((*start, end, Some(name.clone())));
+        }
+
+        // Drop blank lines at the edges, and pieces that are nothing but blanks
+        segments
+            .into_iter()
+            .filter_map(|(mut start, mut end, name)| {
+                while start <= end && lines[start].trim().is_empty() {
+                    start += 1;
+                }
+                while end > start && lines[end].trim().is_empty() {
+                    end -= 1;
+                }
+                (start <= end).then_some((start, end, name))
+            })
+            .collect()
+    }
+
+    pub(crate) fn chunk_str(&self, content: &str) -> Vec<ContentChunk> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let lines: Vec<&str> = content.lines().collect();
+
+        let mut chunks: Vec<ContentChunk> = self
+            .segments(&lines)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (start, end, name))| {
+                let mut metadata = HashMap::new();
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("code"));
+                if let Some(language) = self.language {
+                    metadata.insert("language".to_string(), serde_json::json!(language.name()));
+                }
+                metadata.insert("line_range".to_string(), serde_json::json!([start + 1, end + 1]));
+                if let Some(name) = name {
+                    metadata.insert("symbol_name".to_string(), serde_json::json!(name));
+                }
+
+                ContentChunk {
+                    id: String::new(),
+                    content: format!("```{}\n{}\n```", self.language.map_or("", Language::name), lines[start..=end].join("\n")),
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("code", &doc_hash, &mut chunks);
+        chunks
+    }
+
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> ContentMetadata {
+        let symbols: Vec<String> = content.lines().filter_map(|line| self.definition_name(line)).collect();
+
+        let mut additional = HashMap::new();
+        additional.insert("line_count".to_string(), serde_json::json!(content.lines().count()));
+        additional.insert("symbols".to_string(), serde_json::json!(symbols));
+
+        ContentMetadata {
+            content_type: ContentType::Code,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: self.language.map(|language| language.name().to_string()),
+            additional,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentProvider for CodeProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Code, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        Ok(self.for_file(file_path).chunk_str(&content.text))
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.for_file(file_path).metadata_from_str(&content.text, file_name, file_metadata.len());
+        content.record(&mut metadata);
+        Ok(metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        if self.language.is_none() {
+            return Ok(None);
+        }
+        let content = text::decode(bytes);
+        let mut metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64);
+        content.record(&mut metadata);
+
+        Ok(Some(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Processed,
+            chunks: self.chunk_str(&content.text),
+            metadata,
+            error: None,
+        }))
+    }
+}
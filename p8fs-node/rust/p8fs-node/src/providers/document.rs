@@ -1,16 +1,35 @@
-use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
-use crate::providers::ContentProvider;
+use crate::models::{ChunkStrategy, ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::language::detect_language;
+use crate::providers::{ids, outcome, ContentProvider};
+use crate::services::semantic::semantic_chunks;
 use crate::services::EmbeddingService;
 use async_trait::async_trait;
-use docx_rs::{read_docx, Docx};
+use docx_rs::{read_docx, Docx, Paragraph, Table, TableCell, TableCellContent, TableChild, TableRowChild};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
 
-pub struct DocumentProvider;
+/// Characters per fixed-size chunk, with `CHUNK_OVERLAP` carried over from
+/// the previous one. Also the most a semantic chunk may hold.
+pub(crate) const CHUNK_SIZE: usize = 1000;
+pub(crate) const CHUNK_OVERLAP: usize = 200;
+
+pub struct DocumentProvider {
+    strategy: ChunkStrategy,
+}
 
 impl DocumentProvider {
     pub fn new() -> Self {
-        Self
+        Self::with_strategy(ChunkStrategy::Fixed)
+    }
+
+    pub fn with_strategy(strategy: ChunkStrategy) -> Self {
+        Self { strategy }
     }
 
     fn extract_text_from_docx(&self, docx: &Docx) -> String {
@@ -19,23 +38,18 @@ impl DocumentProvider {
         for child in &docx.document.children {
             match child {
                 docx_rs::DocumentChild::Paragraph(p) => {
-                    let mut para_text = String::new();
-                    for run in &p.children {
-                        if let docx_rs::ParagraphChild::Run(r) = run {
-                            for text_child in &r.children {
-                                if let docx_rs::RunChild::Text(t) = text_child {
-                                    para_text.push_str(&t.text);
-                                }
-                            }
-                        }
-                    }
+                    let para_text = paragraph_text(p);
                     if !para_text.trim().is_empty() {
                         text.push_str(&para_text);
                         text.push_str("\n\n");
                     }
                 }
-                docx_rs::DocumentChild::Table(_) => {
-                    text.push_str("| Table Content |\n|---------------|\n| *[Table data extracted from DOCX]* |\n\n");
+                docx_rs::DocumentChild::Table(table) => {
+                    let table_text = self.render_table(table);
+                    if !table_text.is_empty() {
+                        text.push_str(&table_text);
+                        text.push_str("\n\n");
+                    }
                 }
                 _ => {}
             }
@@ -44,7 +58,63 @@ impl DocumentProvider {
         text.trim().to_string()
     }
 
-    fn chunk_text(&self, text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
+    /// Renders a table as a markdown table with the first row as the header and
+    /// columns padded to a common width. A cell spanning several columns keeps
+    /// its text in the first and leaves the rest blank; a cell continuing a
+    /// vertical merge repeats the text above it so every row reads on its own.
+    pub(crate) fn render_table(&self, table: &Table) -> String {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+
+        for TableChild::TableRow(row) in &table.rows {
+            let mut cells = Vec::new();
+            for TableRowChild::TableCell(cell) in &row.cells {
+                let (span, merge) = cell_merge(cell);
+                let text = match merge.as_deref() {
+                    Some("continue") => rows
+                        .last()
+                        .and_then(|above| above.get(cells.len()))
+                        .cloned()
+                        .unwrap_or_default(),
+                    _ => cell_text(cell).replace('|', "\\|"),
+                };
+                cells.push(text);
+                cells.resize(cells.len() + span.saturating_sub(1), String::new());
+            }
+            rows.push(cells);
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0).max(table.grid.len());
+        if rows.is_empty() || columns == 0 {
+            return String::new();
+        }
+
+        let mut widths = vec![3; columns];
+        for row in &mut rows {
+            row.resize(columns, String::new());
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let render_row = |cells: &[String]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            format!("| {} |", padded.join(" | "))
+        };
+        let separator: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+
+        let mut lines = vec![render_row(&rows[0]), format!("| {} |", separator.join(" | "))];
+        lines.extend(rows[1..].iter().map(|row| render_row(row)));
+        lines.join("\n")
+    }
+
+    /// Cuts `text` into windows of `chunk_size` characters, each starting
+    /// `overlap` characters before the previous one ended. Returns each
+    /// window with its character range in `text`.
+    pub(crate) fn chunk_text(&self, text: &str, chunk_size: usize, overlap: usize) -> Vec<(Range<usize>, String)> {
         let chars: Vec<char> = text.chars().collect();
         let mut chunks = Vec::new();
         let mut start = 0;
@@ -52,7 +122,7 @@ impl DocumentProvider {
         while start < chars.len() {
             let end = (start + chunk_size).min(chars.len());
             let chunk: String = chars[start..end].iter().collect();
-            chunks.push(chunk);
+            chunks.push((start..end, chunk));
             
             if end >= chars.len() {
                 break;
@@ -63,24 +133,184 @@ impl DocumentProvider {
 
         chunks
     }
+
+    /// Cuts `text` into chunks with the provider's strategy, each with its
+    /// character range in `text`.
+    pub(crate) async fn chunk_document(&self, text: &str) -> anyhow::Result<Vec<(Range<usize>, String)>> {
+        match self.strategy {
+            ChunkStrategy::Fixed => Ok(self.chunk_text(text, CHUNK_SIZE, CHUNK_OVERLAP)),
+            ChunkStrategy::Semantic { similarity_threshold } => {
+                semantic_chunks(&*EmbeddingService::global().await?, text, similarity_threshold, CHUNK_SIZE).await
+            }
+        }
+    }
+}
+
+fn paragraph_text(paragraph: &Paragraph) -> String {
+    let mut text = String::new();
+    for run in &paragraph.children {
+        if let docx_rs::ParagraphChild::Run(r) = run {
+            for text_child in &r.children {
+                if let docx_rs::RunChild::Text(t) = text_child {
+                    text.push_str(&t.text);
+                }
+            }
+        }
+    }
+    text
+}
+
+/// Joins a cell's paragraphs, and the cells of any table nested in it, onto one line.
+fn cell_text(cell: &TableCell) -> String {
+    let mut parts = Vec::new();
+    for content in &cell.children {
+        match content {
+            TableCellContent::Paragraph(p) => parts.push(paragraph_text(p)),
+            TableCellContent::Table(nested) => {
+                for TableChild::TableRow(row) in &nested.rows {
+                    for TableRowChild::TableCell(nested_cell) in &row.cells {
+                        parts.push(cell_text(nested_cell));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns how many grid columns a cell spans and its vertical merge state
+/// (`restart` or `continue`). `TableCellProperty` keeps these private, so they
+/// are read back from its serialized form.
+fn cell_merge(cell: &TableCell) -> (usize, Option<String>) {
+    let property = serde_json::to_value(&cell.property).unwrap_or_default();
+    let unwrap_val = |value: &Value| value.get("val").cloned().unwrap_or_else(|| value.clone());
+
+    let span = property
+        .get("gridSpan")
+        .map(unwrap_val)
+        .and_then(|span| span.as_u64())
+        .map_or(1, |span| span.max(1) as usize);
+    let merge = property
+        .get("verticalMerge")
+        .map(unwrap_val)
+        .and_then(|merge| merge.as_str().map(str::to_lowercase));
+
+    (span, merge)
+}
+
+/// Package properties copied into `additional` when present, as
+/// `(key, part, element)`. Author, title, dates and language have their own fields.
+const EXTRA_PROPERTIES: [(&str, &str, &str); 8] = [
+    ("subject", "core", "dc:subject"),
+    ("description", "core", "dc:description"),
+    ("keywords", "core", "cp:keywords"),
+    ("category", "core", "cp:category"),
+    ("last_modified_by", "core", "cp:lastModifiedBy"),
+    ("revision", "core", "cp:revision"),
+    ("company", "app", "Company"),
+    ("application", "app", "Application"),
+];
+
+/// Reads every property from the package's `docProps/core.xml` and
+/// `docProps/app.xml` parts, keyed by part and then by qualified element name.
+pub(crate) fn read_docx_properties(bytes: &[u8]) -> anyhow::Result<Value> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut properties = serde_json::Map::new();
+
+    for (key, part) in [("core", "docProps/core.xml"), ("app", "docProps/app.xml")] {
+        let mut xml = String::new();
+        match archive.by_name(part) {
+            Ok(mut file) => file.read_to_string(&mut xml)?,
+            Err(zip::result::ZipError::FileNotFound) => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        properties.insert(key.to_string(), xml_properties_to_json(&xml)?);
+    }
+
+    Ok(Value::Object(properties))
+}
+
+/// Flattens the children of a properties part's root element. Leaf elements
+/// become strings; elements with nested content (e.g. `HeadingPairs`) become
+/// arrays of the text values inside them.
+fn xml_properties_to_json(xml: &str) -> anyhow::Result<Value> {
+    struct Property {
+        name: String,
+        texts: Vec<String>,
+        nested: bool,
+    }
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut properties = serde_json::Map::new();
+    let mut current: Option<Property> = None;
+    let mut depth = 0;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                depth += 1;
+                if depth == 2 {
+                    current = Some(Property {
+                        name: String::from_utf8_lossy(element.name().as_ref()).into_owned(),
+                        texts: Vec::new(),
+                        nested: false,
+                    });
+                } else if let Some(property) = current.as_mut() {
+                    property.nested = true;
+                }
+            }
+            Event::Empty(element) if depth == 1 => {
+                let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+                properties.insert(name, Value::String(String::new()));
+            }
+            Event::Text(text) => {
+                if let Some(property) = current.as_mut() {
+                    property.texts.push(text.unescape()?.into_owned());
+                }
+            }
+            Event::End(_) => {
+                if depth == 2 {
+                    if let Some(property) = current.take() {
+                        let value = if property.nested {
+                            Value::Array(property.texts.into_iter().map(Value::String).collect())
+                        } else {
+                            Value::String(property.texts.concat())
+                        };
+                        properties.insert(property.name, value);
+                    }
+                }
+                depth -= 1;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(Value::Object(properties))
 }
 
 #[async_trait]
 impl ContentProvider for DocumentProvider {
     async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
-        let chunks = self.to_markdown_chunks(file_path).await?;
-        let metadata = self.to_metadata(file_path).await?;
-        
-        Ok(ContentProcessingResult {
-            success: true,
-            chunks,
-            metadata,
-            error: None,
-        })
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Document, file_path, chunks, metadata).await
     }
 
     async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
         let file_bytes = tokio::fs::read(file_path).await?;
+        let doc_hash = ids::doc_hash(&file_bytes);
         
         let text = tokio::task::spawn_blocking(move || -> anyhow::Result<String> {
             let docx = read_docx(&file_bytes)?;
@@ -89,16 +319,18 @@ impl ContentProvider for DocumentProvider {
         })
         .await??;
 
-        let chunk_texts = self.chunk_text(&text, 1000, 200);
+        let chunk_texts = self.chunk_document(&text).await?;
         
-        let chunks: Vec<ContentChunk> = chunk_texts
+        let mut chunks: Vec<ContentChunk> = chunk_texts
             .into_iter()
             .enumerate()
-            .map(|(i, content)| {
+            .map(|(i, (range, content))| {
                 let mut metadata = HashMap::new();
                 metadata.insert("chunk_index".to_string(), serde_json::json!(i));
                 metadata.insert("source".to_string(), serde_json::json!("docx"));
                 metadata.insert("section".to_string(), serde_json::json!(format!("Document Section {}", i + 1)));
+                metadata.insert("char_start".to_string(), serde_json::json!(range.start));
+                metadata.insert("char_end".to_string(), serde_json::json!(range.end));
                 
                 // Format content as markdown with proper structure
                 let markdown_content = if i == 0 {
@@ -108,39 +340,76 @@ impl ContentProvider for DocumentProvider {
                 };
                 
                 ContentChunk {
-                    id: format!("doc_chunk_{}", i),
+                    id: String::new(),
                     content: markdown_content,
                     metadata,
                 }
             })
             .collect();
 
+        ids::assign_chunk_ids("docx", &doc_hash, &mut chunks);
         Ok(chunks)
     }
 
     async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
         let file_metadata = tokio::fs::metadata(file_path).await?;
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        let (properties, text) = tokio::task::spawn_blocking(move || -> anyhow::Result<(Value, Option<String>)> {
+            let properties = read_docx_properties(&file_bytes)?;
+            // Only needed when the properties don't name the language
+            let text = read_docx(&file_bytes)
+                .ok()
+                .map(|docx| DocumentProvider::new().extract_text_from_docx(&docx));
+            Ok((properties, text))
+        })
+        .await??;
+        let property = |part: &str, name: &str| {
+            properties[part][name]
+                .as_str()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        let mut additional = HashMap::new();
+        for (key, part, name) in EXTRA_PROPERTIES {
+            if let Some(value) = property(part, name) {
+                additional.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
         
         Ok(ContentMetadata {
             content_type: ContentType::Document,
             file_name: file_path.file_name().map(|n| n.to_string_lossy().to_string()),
             file_size: Some(file_metadata.len()),
-            created_at: None,
-            modified_at: None,
-            author: None,
-            title: None,
-            language: None,
-            additional: HashMap::new(),
+            created_at: property("core", "dcterms:created"),
+            modified_at: property("core", "dcterms:modified"),
+            author: property("core", "dc:creator"),
+            title: property("core", "dc:title"),
+            language: property("core", "dc:language").or_else(|| text.as_deref().and_then(detect_language)),
+            additional,
         })
     }
 
     async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
-        let service = EmbeddingService::global();
-        let service = service.lock().await;
+        let service = EmbeddingService::global().await?;
         
         let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-        let response = service.embed(texts).await?;
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
         
-        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<Value>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        let properties = tokio::task::spawn_blocking(move || read_docx_properties(&file_bytes)).await??;
+
+        Ok(Some(properties))
+    }
+
+    fn with_chunk_strategy(&self, strategy: ChunkStrategy) -> Option<Arc<dyn ContentProvider>> {
+        Some(Arc::new(DocumentProvider::with_strategy(strategy)))
     }
 }
\ No newline at end of file
@@ -0,0 +1,163 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, FixedWidthColumn, InputType, ProcessingStatus};
+use crate::providers::{ids, outcome, text, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Lines per chunk, in both table and plain text mode.
+const ROWS_PER_CHUNK: usize = 50;
+
+/// Chunks fixed-width columnar text (e.g. mainframe exports). With a column
+/// spec each line is cut into fields and rendered as a markdown table; without
+/// one the lines are kept verbatim in a code block so their alignment survives.
+pub struct FixedWidthProvider {
+    columns: Vec<FixedWidthColumn>,
+}
+
+impl FixedWidthProvider {
+    /// A provider with no column spec, which treats the input as plain text.
+    pub fn new() -> Self {
+        Self { columns: Vec::new() }
+    }
+
+    pub fn with_columns(columns: Vec<FixedWidthColumn>) -> Self {
+        Self { columns }
+    }
+
+    /// Cuts `line` into one trimmed field per column. Widths count characters;
+    /// fields past the end of a short line are empty and anything after the
+    /// last column is dropped.
+    pub(crate) fn parse_line(&self, line: &str) -> Vec<String> {
+        let mut chars = line.chars();
+
+        self.columns
+            .iter()
+            .map(|column| chars.by_ref().take(column.width).collect::<String>().trim().to_string())
+            .collect()
+    }
+
+    pub(crate) fn chunk_str(&self, content: &str) -> Vec<ContentChunk> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let lines: Vec<(usize, &str)> = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| (i + 1, line))
+            .collect();
+
+        let mut chunks: Vec<ContentChunk> = lines
+            .chunks(ROWS_PER_CHUNK)
+            .enumerate()
+            .map(|(i, rows)| {
+                let first_line = rows.first().map_or(0, |(number, _)| *number);
+                let last_line = rows.last().map_or(0, |(number, _)| *number);
+
+                let mut metadata = HashMap::new();
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("fixedwidth"));
+                metadata.insert("line_range".to_string(), serde_json::json!([first_line, last_line]));
+
+                let content = if self.columns.is_empty() {
+                    let text: Vec<&str> = rows.iter().map(|(_, line)| *line).collect();
+                    format!("```text\n{}\n```", text.join("\n"))
+                } else {
+                    let names: Vec<&str> = self.columns.iter().map(|column| column.name.as_str()).collect();
+                    metadata.insert("columns".to_string(), serde_json::json!(names));
+                    self.render_table(rows.iter().map(|(_, line)| *line))
+                };
+
+                ContentChunk {
+                    id: String::new(),
+                    content,
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("fixedwidth", &doc_hash, &mut chunks);
+        chunks
+    }
+
+    fn render_table<'a>(&self, lines: impl Iterator<Item = &'a str>) -> String {
+        let row = |cells: Vec<String>| {
+            let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut table = vec![
+            row(self.columns.iter().map(|column| column.name.clone()).collect()),
+            row(self.columns.iter().map(|_| "---".to_string()).collect()),
+        ];
+        table.extend(lines.map(|line| row(self.parse_line(line))));
+        table.join("\n")
+    }
+
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> ContentMetadata {
+        let mut additional = HashMap::new();
+        additional.insert(
+            "row_count".to_string(),
+            serde_json::json!(content.lines().filter(|line| !line.trim().is_empty()).count()),
+        );
+
+        ContentMetadata {
+            content_type: ContentType::FixedWidth,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentProvider for FixedWidthProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::FixedWidth, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        Ok(self.chunk_str(&content.text))
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.metadata_from_str(&content.text, file_name, file_metadata.len());
+        content.record(&mut metadata);
+        Ok(metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let mut metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64);
+        content.record(&mut metadata);
+
+        Ok(Some(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Processed,
+            chunks: self.chunk_str(&content.text),
+            metadata,
+            error: None,
+        }))
+    }
+}
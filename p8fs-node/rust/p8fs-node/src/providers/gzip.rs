@@ -0,0 +1,35 @@
+//! Gzipped uploads (`records.json.gz`, `notes.md.gz`).
+//!
+//! The upload is decompressed before a provider is picked, so the inner file
+//! is dispatched on its own extension and every provider works unchanged.
+//! Output is capped at `ResourceLimits::max_total_bytes`: a few kilobytes of
+//! gzip can expand to gigabytes.
+
+use super::limits::ResourceLimits;
+use flate2::read::MultiGzDecoder;
+use std::io::{self, Read, Write};
+
+/// The name of the compressed file inside `file_name`, if it ends in `.gz`.
+pub fn inner_name(file_name: &str) -> Option<&str> {
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    (extension.eq_ignore_ascii_case("gz") && !stem.is_empty()).then_some(stem)
+}
+
+/// Decompresses `bytes` into `output` and returns how many bytes were
+/// written. Concatenated gzip members are read in full.
+///
+/// Fails with `LimitExceeded` as soon as the output passes
+/// `limits.max_total_bytes`. Reading also stops one byte past `max_bytes`,
+/// so a count over `max_bytes` means the file is larger than that.
+pub fn decompress_into(
+    bytes: &[u8],
+    output: &mut impl Write,
+    limits: &ResourceLimits,
+    max_bytes: Option<u64>,
+) -> anyhow::Result<u64> {
+    let cap = max_bytes.map_or(limits.max_total_bytes, |max| max.min(limits.max_total_bytes));
+    let written = io::copy(&mut MultiGzDecoder::new(bytes).take(cap.saturating_add(1)), output)?;
+
+    limits.check_total_bytes(written)?;
+    Ok(written)
+}
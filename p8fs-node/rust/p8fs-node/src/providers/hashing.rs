@@ -0,0 +1,66 @@
+//! The hash algorithm behind document hashes and chunk IDs.
+//!
+//! Set once per process with `P8FS_HASH_ALGO` (`blake3`, `sha256` or `sha1`;
+//! default `blake3`). Changing it changes every chunk ID, so anything keyed by
+//! them (downstream stores, caches) has to be rebuilt afterwards.
+
+use once_cell::sync::Lazy;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::str::FromStr;
+
+// `Config::from_env` has already rejected an invalid value by the time this runs.
+static HASHER: Lazy<Hasher> = Lazy::new(|| Hasher::from_env().unwrap_or_default());
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Hasher {
+    #[default]
+    Blake3,
+    Sha256,
+    Sha1,
+}
+
+impl Hasher {
+    /// Reads `P8FS_HASH_ALGO`, keeping the default if it is unset.
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_setting(env::var("P8FS_HASH_ALGO").ok().as_deref())
+    }
+
+    /// Parses a `P8FS_HASH_ALGO` value; unset or blank means the default.
+    pub fn from_setting(value: Option<&str>) -> anyhow::Result<Self> {
+        match value.filter(|value| !value.trim().is_empty()) {
+            Some(value) => value.parse().map_err(|_| {
+                anyhow::anyhow!("Invalid P8FS_HASH_ALGO '{}': expected blake3, sha256 or sha1", value)
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// The algorithm read from the environment on first use.
+    pub fn global() -> Hasher {
+        *HASHER
+    }
+
+    /// The full digest of `bytes` as lowercase hex.
+    pub fn hex(&self, bytes: &[u8]) -> String {
+        match self {
+            Hasher::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+            Hasher::Sha256 => format!("{:x}", Sha256::digest(bytes)),
+            Hasher::Sha1 => format!("{:x}", Sha1::digest(bytes)),
+        }
+    }
+}
+
+impl FromStr for Hasher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "blake3" => Ok(Hasher::Blake3),
+            "sha256" => Ok(Hasher::Sha256),
+            "sha1" => Ok(Hasher::Sha1),
+            other => anyhow::bail!("Unknown hash algorithm: {}", other),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Canonical chunk IDs.
+//!
+//! Downstream stores upsert by chunk ID, so IDs must depend only on the input
+//! file and the chunk's own text. Every provider builds IDs as
+//! `{provider}_{doc_hash}_{content_hash}`:
+//!
+//! - `provider` is the provider's stable name (`pdf`, `docx`, `markdown`, `code`, ...)
+//! - `doc_hash` is the first 16 hex characters of the hash of the raw file bytes,
+//!   using the `P8FS_HASH_ALGO` algorithm (BLAKE3 by default; see `hashing`)
+//! - `content_hash` is the same for the chunk's markdown
+//!
+//! A chunk that repeats earlier content of the same document gets `_{n}`
+//! appended, `n` counting the repeats. The chunk's position is kept in its
+//! `chunk_index` metadata rather than in the ID, so chunking the same file
+//! differently never gives one ID to two different texts.
+//!
+//! Re-processing the same bytes yields the same IDs on any machine, regardless
+//! of file name, and chunks from different files never share an ID.
+
+use crate::models::ContentChunk;
+use crate::providers::hashing::Hasher;
+use std::collections::HashMap;
+
+/// Content hash identifying a source document.
+pub fn doc_hash(bytes: &[u8]) -> String {
+    doc_hash_with(Hasher::global(), bytes)
+}
+
+pub fn doc_hash_with(hasher: Hasher, bytes: &[u8]) -> String {
+    hasher.hex(bytes)[..16].to_string()
+}
+
+pub fn chunk_id(provider: &str, doc_hash: &str, content: &str) -> String {
+    format!("{}_{}_{}", provider, doc_hash, content_hash(content))
+}
+
+/// Sets the ID of each of a document's chunks from its content, numbering
+/// repeats of the same content so IDs stay unique within the document.
+pub fn assign_chunk_ids(provider: &str, doc_hash: &str, chunks: &mut [ContentChunk]) {
+    let mut repeats: HashMap<String, usize> = HashMap::new();
+
+    for chunk in chunks {
+        let id = chunk_id(provider, doc_hash, &chunk.content);
+        let count = repeats.entry(id.clone()).or_default();
+        chunk.id = if *count == 0 { id } else { format!("{}_{}", id, count) };
+        *count += 1;
+    }
+}
+
+/// Hash of one chunk's content. Unlike the chunk ID it does not change when
+/// other parts of the document are edited, so it identifies unchanged chunks
+/// across versions of a document.
+pub fn content_hash(content: &str) -> String {
+    doc_hash(content.as_bytes())
+}
@@ -0,0 +1,176 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::{outcome, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use exif::{Exif, In, Tag};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Reads image dimensions and EXIF metadata. Text is only extracted when the
+/// `ocr` feature is enabled; otherwise images produce no chunks.
+pub struct ImageProvider;
+
+impl ImageProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The recognized text as a single chunk, or no chunks when there is none.
+    #[cfg(feature = "ocr")]
+    pub(crate) fn chunk_bytes(&self, bytes: &[u8]) -> anyhow::Result<Vec<ContentChunk>> {
+        use crate::providers::{ids, limits::env_or};
+
+        let language: String = env_or("P8FS_OCR_LANGUAGE", "eng".to_string());
+        let text = tesseract::Tesseract::new(None, Some(language.as_str()))?
+            .set_image_from_mem(bytes)?
+            .recognize()?
+            .get_text()?;
+        if text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert("chunk_index".to_string(), serde_json::json!(0));
+        metadata.insert("source".to_string(), serde_json::json!("image"));
+        metadata.insert("ocr_language".to_string(), serde_json::json!(language));
+
+        let content = text.trim().to_string();
+        Ok(vec![ContentChunk {
+            id: ids::chunk_id("image", &ids::doc_hash(bytes), &content),
+            content,
+            metadata,
+        }])
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub(crate) fn chunk_bytes(&self, _bytes: &[u8]) -> anyhow::Result<Vec<ContentChunk>> {
+        Ok(Vec::new())
+    }
+
+    fn metadata_from_bytes(&self, bytes: &[u8], file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        let size = imagesize::blob_size(bytes).map_err(|e| anyhow::anyhow!("Failed to read image dimensions: {}", e))?;
+
+        let mut additional = HashMap::new();
+        additional.insert("width".to_string(), serde_json::json!(size.width));
+        additional.insert("height".to_string(), serde_json::json!(size.height));
+        if let Ok(format) = imagesize::image_type(bytes) {
+            additional.insert("format".to_string(), serde_json::json!(format!("{:?}", format).to_lowercase()));
+        }
+
+        // Most images carry no EXIF at all, which is not an error
+        let exif = read_exif(bytes);
+        let text = |tag: Tag| exif.as_ref().and_then(|exif| ascii_field(exif, tag));
+
+        for (key, tag) in [("camera_make", Tag::Make), ("camera_model", Tag::Model)] {
+            if let Some(value) = text(tag) {
+                additional.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
+        if let Some((latitude, longitude)) = exif.as_ref().and_then(gps_position) {
+            additional.insert("gps".to_string(), serde_json::json!({ "latitude": latitude, "longitude": longitude }));
+        }
+
+        Ok(ContentMetadata {
+            content_type: ContentType::Image,
+            file_name,
+            file_size: Some(file_size),
+            created_at: text(Tag::DateTimeOriginal).or_else(|| text(Tag::DateTimeDigitized)).map(|date| exif_date(&date)),
+            modified_at: text(Tag::DateTime).map(|date| exif_date(&date)),
+            author: text(Tag::Artist),
+            title: text(Tag::ImageDescription),
+            language: None,
+            additional,
+        })
+    }
+}
+
+fn read_exif(bytes: &[u8]) -> Option<Exif> {
+    exif::Reader::new().read_from_container(&mut Cursor::new(bytes)).ok()
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    match &field.value {
+        exif::Value::Ascii(values) => values
+            .iter()
+            .map(|value| String::from_utf8_lossy(value).trim_matches(['\0', ' ']).to_string())
+            .find(|value| !value.is_empty()),
+        _ => None,
+    }
+}
+
+/// EXIF dates are `YYYY:MM:DD HH:MM:SS` in the camera's local time; this
+/// rewrites them as ISO 8601 without a zone. Anything else is kept as is.
+fn exif_date(date: &str) -> String {
+    match date.split_once(' ') {
+        Some((day, time)) if day.len() == 10 => format!("{}T{}", day.replace(':', "-"), time),
+        _ => date.to_string(),
+    }
+}
+
+/// Latitude and longitude in signed decimal degrees, south and west negative.
+fn gps_position(exif: &Exif) -> Option<(f64, f64)> {
+    let coordinate = |value_tag: Tag, ref_tag: Tag, negative: &str| {
+        let degrees = match &exif.get_field(value_tag, In::PRIMARY)?.value {
+            exif::Value::Rational(parts) if parts.len() == 3 => {
+                parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0
+            }
+            _ => return None,
+        };
+        let sign = if ascii_field(exif, ref_tag).is_some_and(|r| r.eq_ignore_ascii_case(negative)) { -1.0 } else { 1.0 };
+        Some(sign * degrees)
+    };
+
+    Some((
+        coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?,
+        coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?,
+    ))
+}
+
+#[async_trait]
+impl ContentProvider for ImageProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Image, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        tokio::task::spawn_blocking(move || ImageProvider.chunk_bytes(&file_bytes)).await?
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        self.metadata_from_bytes(&file_bytes, file_name, file_bytes.len() as u64)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    /// Every EXIF field, keyed by tag name, with its display value.
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<Value>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        Ok(read_exif(&file_bytes).map(|exif| {
+            let fields: serde_json::Map<String, Value> = exif
+                .fields()
+                .filter(|field| field.ifd_num == In::PRIMARY)
+                .map(|field| (field.tag.to_string(), Value::String(field.display_value().to_string())))
+                .collect();
+            Value::Object(fields)
+        }))
+    }
+}
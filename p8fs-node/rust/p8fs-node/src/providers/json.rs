@@ -1,26 +1,110 @@
-use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
-use crate::providers::ContentProvider;
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::limits::ResourceLimits;
+use crate::providers::{ids, outcome, text, ContentProvider};
 use crate::services::EmbeddingService;
 use async_trait::async_trait;
+use rayon::prelude::*;
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
 use std::path::Path;
+use std::sync::Arc;
 
-pub struct JsonProvider;
+/// `(path, content, metadata)` of a chunk before it is given its ID.
+pub(crate) type RawChunk = (String, String, HashMap<String, Value>);
+
+/// Stands in for a value nested deeper than `ResourceLimits::max_depth`.
+pub(crate) const TRUNCATED: &str = "… (nested too deep)";
+
+/// Arrays and objects with at least this many children have them chunked in
+/// parallel. Below it, the cost of splitting the work outweighs the gain.
+const PARALLEL_MIN_CHILDREN: usize = 256;
+
+pub struct JsonProvider {
+    limits: ResourceLimits,
+    group_by_kind: bool,
+    kind_summaries: bool,
+}
 
 impl JsonProvider {
+    /// Uses the global limits, in document order. A request asks for grouping
+    /// with the `group_by_kind` and `kind_summaries` options.
     pub fn new() -> Self {
-        Self
+        Self::with_limits(ResourceLimits::global())
+    }
+
+    pub fn with_limits(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            group_by_kind: false,
+            kind_summaries: false,
+        }
+    }
+
+    /// Emit chunks grouped by `kind` instead of in document order.
+    pub fn group_by_kind(mut self, group_by_kind: bool) -> Self {
+        self.group_by_kind = group_by_kind;
+        self
+    }
+
+    /// When grouping by kind, lead each group with a chunk summarizing it.
+    pub fn kind_summaries(mut self, kind_summaries: bool) -> Self {
+        self.kind_summaries = kind_summaries;
+        self
+    }
+
+    /// Parses `content`, replacing every value nested deeper than the depth
+    /// limit with `TRUNCATED`. The parser skips those values without building
+    /// them, so no amount of nesting can exhaust the stack.
+    pub(crate) fn parse(&self, content: &str) -> serde_json::Result<Value> {
+        let mut deserializer = serde_json::Deserializer::from_str(content);
+        // The seed bounds the recursion instead
+        deserializer.disable_recursion_limit();
+
+        let value = DepthLimited { depth: 0, max_depth: self.limits.max_depth }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    /// Replaces every value in an already parsed document that is nested
+    /// deeper than the depth limit with `TRUNCATED`.
+    pub(crate) fn truncate_depth(&self, value: &mut Value) {
+        truncate_below(value, 0, self.limits.max_depth);
+    }
+
+    /// Walks the whole document before any rendering, so oversized input fails
+    /// up front rather than partway through.
+    pub(crate) fn check_limits(&self, value: &Value, entries: &mut usize) -> anyhow::Result<()> {
+        *entries += 1;
+        self.limits.check_entries(*entries)?;
+
+        match value {
+            Value::Array(arr) => {
+                for item in arr {
+                    self.check_limits(item, entries)?;
+                }
+            }
+            Value::Object(obj) => {
+                for item in obj.values() {
+                    self.check_limits(item, entries)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
     }
 
-    fn json_to_markdown(&self, value: &Value, indent: usize) -> String {
+    pub(crate) fn json_to_markdown(&self, value: &Value, indent: usize) -> String {
         let indent_str = "  ".repeat(indent);
         
         match value {
             Value::Null => "null".to_string(),
             Value::Bool(b) => b.to_string(),
             Value::Number(n) => n.to_string(),
-            Value::String(s) => format!("\"{}\"", s),
+            Value::String(s) => escape_markdown(s),
             Value::Array(arr) => {
                 let items: Vec<String> = arr
                     .iter()
@@ -58,109 +142,386 @@ impl JsonProvider {
         entries.join("\n")
     }
 
-    fn extract_chunks(&self, value: &Value, path: String) -> Vec<(String, String, HashMap<String, Value>)> {
+    pub(crate) fn extract_chunks(&self, value: &Value, path: String) -> Vec<RawChunk> {
         let mut chunks = Vec::new();
+        self.collect_chunks(value, path, None, &mut chunks);
+        chunks
+    }
+
+    /// `parent_kind` is the `kind` of the nearest enclosing `kind` object. That
+    /// object's chunk already renders its fields, so below it only nested `kind`
+    /// objects get chunks of their own.
+    fn collect_chunks(
+        &self,
+        value: &Value,
+        path: String,
+        parent_kind: Option<&Value>,
+        chunks: &mut Vec<RawChunk>,
+    ) {
+        let start = chunks.len();
         
         match value {
             Value::Object(obj) => {
-                if obj.contains_key("kind") {
-                    let content = self.json_to_markdown(value, 0);
-                    let mut metadata = HashMap::new();
-                    metadata.insert("path".to_string(), Value::String(path.clone()));
-                    metadata.insert("kind".to_string(), obj.get("kind").cloned().unwrap_or(Value::Null));
-                    chunks.push((path.clone(), content, metadata));
+                if let Some(kind) = obj.get("kind") {
+                    if !is_kind_container(obj) {
+                        let content = self.json_to_markdown(value, 0);
+                        let mut metadata = HashMap::new();
+                        metadata.insert("path".to_string(), Value::String(path.clone()));
+                        metadata.insert("kind".to_string(), kind.clone());
+                        if let Some(parent_kind) = parent_kind {
+                            metadata.insert("parent_kind".to_string(), parent_kind.clone());
+                        }
+                        if is_truncated(value) {
+                            metadata.insert("truncated_depth".to_string(), Value::Bool(true));
+                        }
+                        chunks.push((path.clone(), content, metadata));
+                    }
+                    
+                    let children = obj.iter().map(|(key, val)| (child_path(&path, key), val)).collect();
+                    self.collect_children(children, Some(kind), chunks);
+                    return;
                 }
                 
-                for (key, val) in obj {
-                    let new_path = if path.is_empty() {
-                        key.clone()
-                    } else {
-                        format!("{}.{}", path, key)
-                    };
-                    chunks.extend(self.extract_chunks(val, new_path));
-                }
+                let children = obj.iter().map(|(key, val)| (child_path(&path, key), val)).collect();
+                self.collect_children(children, parent_kind, chunks);
             }
             Value::Array(arr) => {
-                for (i, val) in arr.iter().enumerate() {
-                    let new_path = format!("{}[{}]", path, i);
-                    chunks.extend(self.extract_chunks(val, new_path));
-                }
+                let children = arr.iter().enumerate().map(|(i, val)| (format!("{}[{}]", path, i), val)).collect();
+                self.collect_children(children, parent_kind, chunks);
             }
             _ => {}
         }
         
-        if chunks.is_empty() && !path.is_empty() {
+        if chunks.len() == start && parent_kind.is_none() && !path.is_empty() {
             let content = self.json_to_markdown(value, 0);
             let mut metadata = HashMap::new();
             metadata.insert("path".to_string(), Value::String(path.clone()));
+            if is_truncated(value) {
+                metadata.insert("truncated_depth".to_string(), Value::Bool(true));
+            }
             chunks.push((path, content, metadata));
         }
-        
-        chunks
     }
-}
 
-#[async_trait]
-impl ContentProvider for JsonProvider {
-    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
-        let chunks = self.to_markdown_chunks(file_path).await?;
-        let metadata = self.to_metadata(file_path).await?;
-        
-        Ok(ContentProcessingResult {
-            success: true,
-            chunks,
-            metadata,
-            error: None,
-        })
+    /// Collects the chunks of each `(path, value)` child in turn. Large
+    /// collections, such as an export's top-level array of records, are split
+    /// across the rayon pool; the chunks still come out in child order.
+    fn collect_children(&self, children: Vec<(String, &Value)>, parent_kind: Option<&Value>, chunks: &mut Vec<RawChunk>) {
+        if children.len() < PARALLEL_MIN_CHILDREN {
+            for (path, value) in children {
+                self.collect_chunks(value, path, parent_kind, chunks);
+            }
+            return;
+        }
+
+        let parts: Vec<Vec<RawChunk>> = children
+            .into_par_iter()
+            .map(|(path, value)| {
+                let mut part = Vec::new();
+                self.collect_chunks(value, path, parent_kind, &mut part);
+                part
+            })
+            .collect();
+        chunks.extend(parts.into_iter().flatten());
     }
 
-    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
-        let content = tokio::fs::read_to_string(file_path).await?;
-        let json_value: Value = serde_json::from_str(&content)?;
+    /// Applies `group_by_kind`: each `kind` becomes a contiguous run, in order
+    /// of first appearance, with chunks that have no `kind` last. Chunks record
+    /// their run as `kind_group`, `group_position` and `group_size`.
+    pub(crate) fn group_chunks(&self, chunks: Vec<RawChunk>) -> Vec<RawChunk> {
+        if !self.group_by_kind {
+            return chunks;
+        }
+
+        let mut groups: Vec<(Option<Value>, Vec<RawChunk>)> = Vec::new();
+        for chunk in chunks {
+            let kind = chunk.2.get("kind").cloned();
+            match groups.iter_mut().find(|(group_kind, _)| *group_kind == kind) {
+                Some((_, members)) => members.push(chunk),
+                None => groups.push((kind, vec![chunk])),
+            }
+        }
+        groups.sort_by_key(|(kind, _)| kind.is_none());
+
+        let mut grouped = Vec::new();
+        for (kind, members) in groups {
+            let group_size = members.len();
+            let kind_group = kind.clone().unwrap_or(Value::Null);
+
+            if let Some(kind) = kind.filter(|_| self.kind_summaries) {
+                grouped.push(self.kind_summary(&kind, &members));
+            }
+
+            for (position, (path, content, mut metadata)) in members.into_iter().enumerate() {
+                metadata.insert("kind_group".to_string(), kind_group.clone());
+                metadata.insert("group_position".to_string(), serde_json::json!(position));
+                metadata.insert("group_size".to_string(), serde_json::json!(group_size));
+                grouped.push((path, content, metadata));
+            }
+        }
+
+        grouped
+    }
+
+    /// A chunk naming the kind and listing where each of its members is.
+    fn kind_summary(&self, kind: &Value, members: &[RawChunk]) -> RawChunk {
+        let locations: Vec<String> = members
+            .iter()
+            .map(|(path, _, metadata)| match (path.as_str(), metadata.get("document_index")) {
+                ("", Some(document_index)) => format!("- document {}", document_index),
+                ("", None) => "- (root)".to_string(),
+                (path, Some(document_index)) => format!("- document {}: {}", document_index, path),
+                (path, None) => format!("- {}", path),
+            })
+            .collect();
+        let name = kind.as_str().unwrap_or("Unknown");
+        let content = format!("## {} ({})\n{}", name, members.len(), locations.join("\n"));
+
+        let mut metadata = HashMap::new();
+        metadata.insert("kind_group".to_string(), kind.clone());
+        metadata.insert("group_size".to_string(), serde_json::json!(members.len()));
+        metadata.insert("summary".to_string(), Value::Bool(true));
+
+        (String::new(), content, metadata)
+    }
+
+    pub(crate) fn chunk_str(&self, content: &str) -> anyhow::Result<Vec<ContentChunk>> {
+        self.limits.check_total_bytes(content.len() as u64)?;
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let json_value = self.parse(content)?;
+        self.check_limits(&json_value, &mut 0)?;
         
-        let raw_chunks = self.extract_chunks(&json_value, String::new());
+        let raw_chunks = self.group_chunks(self.extract_chunks(&json_value, String::new()));
         
-        let chunks: Vec<ContentChunk> = raw_chunks
+        let mut chunks: Vec<ContentChunk> = raw_chunks
             .into_iter()
             .enumerate()
-            .map(|(i, (path, content, mut metadata))| {
+            .map(|(i, (_path, content, mut metadata))| {
                 metadata.insert("chunk_index".to_string(), serde_json::json!(i));
                 metadata.insert("source".to_string(), serde_json::json!("json"));
                 
                 ContentChunk {
-                    id: format!("json_chunk_{}_{}", i, path.replace('.', "_").replace('[', "").replace(']', "")),
+                    id: String::new(),
                     content,
                     metadata,
                 }
             })
             .collect();
 
+        ids::assign_chunk_ids("json", &doc_hash, &mut chunks);
         Ok(chunks)
     }
 
-    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
-        let file_metadata = tokio::fs::metadata(file_path).await?;
-        
-        Ok(ContentMetadata {
+    fn build_metadata(&self, file_name: Option<String>, file_size: u64) -> ContentMetadata {
+        ContentMetadata {
             content_type: ContentType::StructuredData,
-            file_name: file_path.file_name().map(|n| n.to_string_lossy().to_string()),
-            file_size: Some(file_metadata.len()),
+            file_name,
+            file_size: Some(file_size),
             created_at: None,
             modified_at: None,
             author: None,
             title: None,
             language: None,
             additional: HashMap::new(),
-        })
+        }
+    }
+}
+
+/// Backslash-escapes characters that would otherwise be read as markdown
+/// (emphasis, code, links, table cells or HTML), leaving other text untouched.
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '`' | '*' | '[' | ']' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Builds a `Value` like `Value::deserialize`, but skips anything below
+/// `max_depth` and puts `TRUNCATED` in its place.
+struct DepthLimited {
+    depth: usize,
+    max_depth: usize,
+}
+
+impl DepthLimited {
+    fn child(&self) -> Self {
+        Self { depth: self.depth + 1, max_depth: self.max_depth }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DepthLimited {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        if self.depth > self.max_depth {
+            IgnoredAny::deserialize(deserializer)?;
+            return Ok(Value::String(TRUNCATED.to_string()));
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DepthLimited {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(self.child())? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut obj = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(self.child())?;
+            obj.insert(key, value);
+        }
+        Ok(Value::Object(obj))
+    }
+}
+
+fn truncate_below(value: &mut Value, depth: usize, max_depth: usize) {
+    if depth > max_depth {
+        *value = Value::String(TRUNCATED.to_string());
+        return;
+    }
+
+    match value {
+        Value::Array(arr) => arr.iter_mut().for_each(|item| truncate_below(item, depth + 1, max_depth)),
+        Value::Object(obj) => obj.values_mut().for_each(|item| truncate_below(item, depth + 1, max_depth)),
+        _ => {}
+    }
+}
+
+/// Whether `value` or anything in it was cut off by the depth limit.
+fn is_truncated(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s == TRUNCATED,
+        Value::Array(arr) => arr.iter().any(is_truncated),
+        Value::Object(obj) => obj.values().any(is_truncated),
+        _ => false,
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Whether a `kind` object only wraps other `kind` objects, like a Kubernetes
+/// `List` and its `items`. Besides those it may hold scalars and flat maps such
+/// as `metadata`, none of which is worth a chunk of its own.
+fn is_kind_container(obj: &serde_json::Map<String, Value>) -> bool {
+    let is_kind = |value: &Value| value.get("kind").is_some();
+    let mut has_children = false;
+
+    for value in obj.values() {
+        match value {
+            Value::Array(items) if !items.is_empty() && items.iter().all(is_kind) => has_children = true,
+            Value::Object(_) if is_kind(value) => has_children = true,
+            Value::Object(map) if map.values().all(|v| !v.is_object() && !v.is_array()) => {}
+            Value::Object(_) | Value::Array(_) => return false,
+            _ => {}
+        }
+    }
+
+    has_children
+}
+
+#[async_trait]
+impl ContentProvider for JsonProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::StructuredData, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        self.chunk_str(&content.text)
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.build_metadata(file_name, file_metadata.len());
+        content.record(&mut metadata);
+        Ok(metadata)
     }
 
     async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
-        let service = EmbeddingService::global();
-        let service = service.lock().await;
+        let service = EmbeddingService::global().await?;
         
         let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-        let response = service.embed(texts).await?;
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
         
-        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let mut metadata = self.build_metadata(None, bytes.len() as u64);
+        content.record(&mut metadata);
+
+        match self.chunk_str(&content.text) {
+            Ok(chunks) => Ok(Some(ContentProcessingResult {
+                success: true,
+                status: ProcessingStatus::Processed,
+                chunks,
+                metadata,
+                error: None,
+            })),
+            Err(err) => outcome::failed(err, metadata).map(Some),
+        }
+    }
+
+    fn with_kind_grouping(&self, kind_summaries: bool) -> Option<Arc<dyn ContentProvider>> {
+        Some(Arc::new(
+            JsonProvider::with_limits(self.limits).group_by_kind(true).kind_summaries(kind_summaries),
+        ))
     }
 }
\ No newline at end of file
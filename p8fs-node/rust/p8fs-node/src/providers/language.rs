@@ -0,0 +1,25 @@
+//! Language detection for the text providers extract.
+
+/// How much of a document detection looks at. The opening is enough to tell
+/// the language, and it keeps detection cheap on large documents.
+pub const SAMPLE_BYTES: usize = 16 * 1024;
+
+/// The ISO 639-3 code (e.g. `eng`, `fra`) of the language `text` is written
+/// in, or `None` when the text is too short or mixed to tell reliably.
+pub fn detect_language(text: &str) -> Option<String> {
+    let info = whatlang::detect(sample(text))?;
+    info.is_reliable().then(|| info.lang().code().to_string())
+}
+
+/// The first `SAMPLE_BYTES` of `text`, cut back to a character boundary.
+fn sample(text: &str) -> &str {
+    if text.len() <= SAMPLE_BYTES {
+        return text;
+    }
+
+    let mut end = SAMPLE_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
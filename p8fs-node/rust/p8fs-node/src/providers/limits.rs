@@ -0,0 +1,82 @@
+//! Resource limits for providers that expand or recurse into their input.
+//!
+//! Nested formats (JSON and YAML today, archives and other formats as they
+//! are added) can describe far more work than their size suggests. Every such
+//! provider consults the same `ResourceLimits` and aborts with `LimitExceeded`
+//! instead of exhausting memory. Nesting is the exception for JSON and YAML:
+//! they stop descending at `max_depth` and keep what they have.
+
+use once_cell::sync::Lazy;
+use std::env;
+
+static LIMITS: Lazy<ResourceLimits> = Lazy::new(ResourceLimits::from_env);
+
+/// Files processed at the same time when `P8FS_BATCH_CONCURRENCY` is unset.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Deepest nesting a provider will traverse. JSON and YAML replace values
+    /// nested below it with a placeholder instead of failing.
+    pub max_depth: usize,
+    /// Most entries (values, archive members) a single upload may expand to.
+    pub max_entries: usize,
+    /// Most bytes a single upload may expand to.
+    pub max_total_bytes: u64,
+}
+
+impl ResourceLimits {
+    /// Reads `P8FS_MAX_DEPTH`, `P8FS_MAX_ENTRIES` and `P8FS_MAX_TOTAL_BYTES`,
+    /// keeping the default for any that are unset or invalid.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            max_depth: env_or("P8FS_MAX_DEPTH", defaults.max_depth),
+            max_entries: env_or("P8FS_MAX_ENTRIES", defaults.max_entries),
+            max_total_bytes: env_or("P8FS_MAX_TOTAL_BYTES", defaults.max_total_bytes),
+        }
+    }
+
+    /// The limits read from the environment on first use.
+    pub fn global() -> ResourceLimits {
+        *LIMITS
+    }
+
+    pub fn check_entries(&self, entries: usize) -> Result<(), LimitExceeded> {
+        if entries > self.max_entries {
+            return Err(LimitExceeded::Entries(self.max_entries));
+        }
+        Ok(())
+    }
+
+    pub fn check_total_bytes(&self, bytes: u64) -> Result<(), LimitExceeded> {
+        if bytes > self.max_total_bytes {
+            return Err(LimitExceeded::TotalBytes(self.max_total_bytes));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 64,
+            max_entries: 100_000,
+            max_total_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+pub(crate) fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+/// A limit that processing stopped at. Each variant holds the configured maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum LimitExceeded {
+    #[error("Entry count exceeds the limit of {0}")]
+    Entries(usize),
+    #[error("Content size exceeds the limit of {0} bytes")]
+    TotalBytes(u64),
+}
@@ -1,47 +1,207 @@
-use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
-use crate::providers::ContentProvider;
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::tables::WideTables;
+use crate::providers::language::detect_language;
+use crate::providers::{ids, outcome, text, ContentProvider};
 use crate::services::EmbeddingService;
 use async_trait::async_trait;
-use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use pulldown_cmark::{Alignment, Event, Options, Parser, Tag, TagEnd};
 use std::collections::HashMap;
 use std::path::Path;
 
-pub struct MarkdownProvider;
+/// Chunks markdown at headings, one chunk per section. A table wider than the
+/// table limit is cut into parts, each a chunk of its own under the section's
+/// heading.
+pub struct MarkdownProvider {
+    tables: WideTables,
+}
+
+/// `(title, content, heading level, tasks)`, where `tasks` holds the text and
+/// checked state of each task-list item in the section.
+pub(crate) type Section = (String, String, usize, Vec<(String, bool)>);
 
 impl MarkdownProvider {
     pub fn new() -> Self {
-        Self
+        Self {
+            tables: WideTables::global(),
+        }
     }
 
-    fn extract_sections(&self, markdown: &str) -> Vec<(String, String, usize)> {
+    /// How tables with more columns than `tables.max_columns` are cut up.
+    pub fn wide_tables(mut self, tables: WideTables) -> Self {
+        self.tables = tables;
+        self
+    }
+
+    /// Splits the document at headings into sections.
+    pub(crate) fn extract_sections(&self, markdown: &str) -> Vec<Section> {
         let mut sections = Vec::new();
-        let parser = Parser::new(markdown);
+        let parser = Parser::new_ext(markdown, Options::ENABLE_TASKLISTS | Options::ENABLE_TABLES);
         
         let mut current_section = String::new();
         let mut current_content = String::new();
         let mut current_level = 0;
+        let mut current_tasks = Vec::new();
         let mut in_code_block = false;
+        let mut in_heading = false;
+        // Ordered lists hold their next number, bullet lists `None`
+        let mut lists: Vec<Option<u64>> = Vec::new();
+        // Content offset and checked state of each open task item, `None` for plain items
+        let mut items: Vec<Option<(usize, bool)>> = Vec::new();
+        // Content offsets of the open paragraph and of each soft break inside it
+        let mut paragraph: Option<(usize, Vec<usize>)> = None;
+        // Content offsets of each open blockquote
+        let mut quotes: Vec<usize> = Vec::new();
+        // Column alignments of the open table
+        let mut table: Vec<Alignment> = Vec::new();
+        let mut in_table_cell = false;
         
         for event in parser {
             match event {
                 Event::Start(Tag::Heading { level, .. }) => {
+                    // A heading inside a task item ends the section the task's
+                    // text is in, so record the task there and forget its offset
+                    for item in items.iter_mut() {
+                        if let Some((start, checked)) = item.take() {
+                            current_tasks.push((task_text(&current_content[start..]), checked));
+                        }
+                    }
                     if !current_section.is_empty() {
-                        sections.push((current_section.clone(), current_content.trim().to_string(), current_level));
+                        sections.push((
+                            current_section.clone(),
+                            current_content.trim().to_string(),
+                            current_level,
+                            std::mem::take(&mut current_tasks),
+                        ));
                     }
                     current_section.clear();
                     current_content.clear();
                     current_level = level as usize;
+                    in_heading = true;
                 }
                 Event::End(TagEnd::Heading(_)) => {
-                    current_content = format!("{}\n\n", current_section);
+                    // The title is prepended when the chunk is built, not kept in the content
+                    in_heading = false;
+                    current_section = current_section.trim().to_string();
+                }
+                Event::Start(Tag::Paragraph) => {
+                    paragraph = Some((current_content.len(), Vec::new()));
+                }
+                Event::End(TagEnd::Paragraph) => {
+                    if let Some((start, breaks)) = paragraph.take() {
+                        if let Some(definitions) = definition_list(&current_content[start..], &breaks, start) {
+                            current_content.truncate(start);
+                            current_content.push_str(&definitions);
+                        }
+                    }
+                    if items.is_empty() {
+                        current_content.push_str("\n\n");
+                    }
+                }
+                Event::Start(Tag::List(first)) => {
+                    lists.push(first);
+                }
+                Event::End(TagEnd::List(_)) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        current_content.push('\n');
+                    }
+                }
+                Event::Start(Tag::Item) => {
+                    if !current_content.is_empty() && !current_content.ends_with('\n') {
+                        current_content.push('\n');
+                    }
+                    current_content.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                    match lists.last_mut() {
+                        Some(Some(number)) => {
+                            current_content.push_str(&format!("{}. ", number));
+                            *number += 1;
+                        }
+                        _ => current_content.push_str("- "),
+                    }
+                    items.push(None);
+                }
+                Event::Start(Tag::BlockQuote(_)) => {
+                    quotes.push(current_content.len());
+                }
+                Event::End(TagEnd::BlockQuote) => {
+                    // Stale if a heading inside the quote restarted the content
+                    let quoted = quotes.pop().and_then(|start| {
+                        let lines: Vec<String> = current_content
+                            .get(start..)?
+                            .trim_end()
+                            .lines()
+                            .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                            .collect();
+                        Some((start, lines.join("\n")))
+                    });
+                    if let Some((start, quoted)) = quoted {
+                        current_content.truncate(start);
+                        current_content.push_str(&quoted);
+                        current_content.push_str(if quotes.is_empty() && items.is_empty() { "\n\n" } else { "\n" });
+                    }
+                }
+                Event::Start(Tag::Table(alignments)) => {
+                    if !current_content.is_empty() && !current_content.ends_with('\n') {
+                        current_content.push('\n');
+                    }
+                    table = alignments;
+                }
+                Event::End(TagEnd::Table) => {
+                    table.clear();
+                    current_content.push('\n');
+                }
+                Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                    current_content.push('|');
+                }
+                Event::End(TagEnd::TableHead) => {
+                    current_content.push_str("\n|");
+                    for alignment in &table {
+                        current_content.push_str(match alignment {
+                            Alignment::None => " --- |",
+                            Alignment::Left => " :--- |",
+                            Alignment::Center => " :---: |",
+                            Alignment::Right => " ---: |",
+                        });
+                    }
+                    current_content.push('\n');
+                }
+                Event::End(TagEnd::TableRow) => {
+                    current_content.push('\n');
+                }
+                Event::Start(Tag::TableCell) => {
+                    in_table_cell = true;
+                    current_content.push(' ');
+                }
+                Event::End(TagEnd::TableCell) => {
+                    in_table_cell = false;
+                    current_content.push_str(" |");
+                }
+                Event::TaskListMarker(checked) => {
+                    current_content.push_str(if checked { "[x] " } else { "[ ] " });
+                    if let Some(item) = items.last_mut() {
+                        *item = Some((current_content.len(), checked));
+                    }
+                }
+                Event::End(TagEnd::Item) => {
+                    if let Some(Some((start, checked))) = items.pop() {
+                        current_tasks.push((task_text(&current_content[start..]), checked));
+                    }
+                    if !current_content.ends_with('\n') {
+                        current_content.push('\n');
+                    }
                 }
                 Event::Text(text) => {
-                    if current_section.is_empty() && current_level > 0 {
-                        current_section = text.to_string();
+                    if in_heading {
+                        current_section.push_str(&text);
+                    } else if in_table_cell {
+                        current_content.push_str(&text.replace('|', "\\|"));
                     } else {
                         current_content.push_str(&text);
                     }
                 }
+                Event::Code(code) if in_heading => {
+                    current_section.push_str(&code);
+                }
                 Event::Code(code) => {
                     current_content.push('`');
                     current_content.push_str(&code);
@@ -55,8 +215,14 @@ impl MarkdownProvider {
                     in_code_block = false;
                     current_content.push_str("\n```\n");
                 }
+                Event::SoftBreak if in_heading => {
+                    current_section.push(' ');
+                }
                 Event::SoftBreak => {
                     if !in_code_block {
+                        if let Some((_, breaks)) = paragraph.as_mut() {
+                            breaks.push(current_content.len());
+                        }
                         current_content.push(' ');
                     } else {
                         current_content.push('\n');
@@ -70,91 +236,175 @@ impl MarkdownProvider {
         }
         
         if !current_section.is_empty() || !current_content.is_empty() {
-            sections.push((current_section, current_content.trim().to_string(), current_level));
+            sections.push((current_section, current_content.trim().to_string(), current_level, current_tasks));
         }
         
         if sections.is_empty() && !markdown.is_empty() {
-            sections.push(("Document".to_string(), markdown.to_string(), 1));
+            sections.push(("Document".to_string(), markdown.to_string(), 1, Vec::new()));
         }
         
         sections
     }
-}
 
-#[async_trait]
-impl ContentProvider for MarkdownProvider {
-    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
-        let chunks = self.to_markdown_chunks(file_path).await?;
-        let metadata = self.to_metadata(file_path).await?;
+    pub(crate) fn chunk_str(&self, content: &str) -> Vec<ContentChunk> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let sections = self.extract_sections(content);
+        let pieces = sections.into_iter().flat_map(|(title, content, level, tasks)| {
+            // Tasks are reported once, on the section's first piece
+            let mut tasks = Some(tasks);
+            self.tables
+                .split_markdown(&content)
+                .into_iter()
+                .map(move |piece| (title.clone(), piece, level, tasks.take().unwrap_or_default()))
+        });
         
-        Ok(ContentProcessingResult {
-            success: true,
-            chunks,
-            metadata,
-            error: None,
-        })
-    }
-
-    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
-        let content = tokio::fs::read_to_string(file_path).await?;
-        let sections = self.extract_sections(&content);
-        
-        let chunks: Vec<ContentChunk> = sections
-            .into_iter()
+        let mut chunks: Vec<ContentChunk> = pieces
             .enumerate()
-            .map(|(i, (title, content, level))| {
+            .map(|(i, (title, piece, level, tasks))| {
                 let mut metadata = HashMap::new();
                 metadata.insert("chunk_index".to_string(), serde_json::json!(i));
                 metadata.insert("section_title".to_string(), serde_json::json!(title));
                 metadata.insert("heading_level".to_string(), serde_json::json!(level));
                 metadata.insert("source".to_string(), serde_json::json!("markdown"));
+                if !tasks.is_empty() {
+                    let tasks: Vec<_> = tasks
+                        .into_iter()
+                        .map(|(text, checked)| serde_json::json!({ "text": text, "checked": checked }))
+                        .collect();
+                    metadata.insert("tasks".to_string(), serde_json::json!(tasks));
+                }
+                if let Some((part, parts)) = piece.part {
+                    metadata.insert("table_part".to_string(), serde_json::json!(part));
+                    metadata.insert("table_parts".to_string(), serde_json::json!(parts));
+                }
                 
                 let full_content = if !title.is_empty() {
-                    format!("{} {}\n\n{}", "#".repeat(level), title, content)
+                    format!("{} {}\n\n{}", "#".repeat(level), title, piece.content)
                 } else {
-                    content
+                    piece.content
                 };
                 
                 ContentChunk {
-                    id: format!("md_chunk_{}", i),
+                    id: String::new(),
                     content: full_content,
                     metadata,
                 }
             })
             .collect();
 
-        Ok(chunks)
+        ids::assign_chunk_ids("markdown", &doc_hash, &mut chunks);
+        chunks
     }
 
-    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
-        let file_metadata = tokio::fs::metadata(file_path).await?;
-        let content = tokio::fs::read_to_string(file_path).await?;
-        
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> ContentMetadata {
         let lines: Vec<&str> = content.lines().collect();
         let title = lines.iter()
             .find(|line| line.starts_with('#'))
             .map(|line| line.trim_start_matches('#').trim().to_string());
         
-        Ok(ContentMetadata {
+        ContentMetadata {
             content_type: ContentType::Markdown,
-            file_name: file_path.file_name().map(|n| n.to_string_lossy().to_string()),
-            file_size: Some(file_metadata.len()),
+            file_name,
+            file_size: Some(file_size),
             created_at: None,
             modified_at: None,
             author: None,
             title,
-            language: None,
+            language: detect_language(content),
             additional: HashMap::new(),
-        })
+        }
+    }
+}
+
+/// The text of a task item from its content onwards. A nested list belongs to
+/// its own items, so only the first line is kept.
+fn task_text(content: &str) -> String {
+    content.lines().next().unwrap_or_default().trim().to_string()
+}
+
+/// Recognizes a definition list written as a term line followed by one or more
+/// `: definition` lines, which CommonMark parses as a single paragraph, and
+/// rewrites it as one `term: definition` line per definition. `breaks` are the
+/// absolute offsets of the paragraph's soft breaks and `start` its own offset.
+fn definition_list(text: &str, breaks: &[usize], start: usize) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    for &offset in breaks {
+        lines.push(&text[line_start..offset - start]);
+        // Skip the space that stood in for the break
+        line_start = offset - start + 1;
+    }
+    lines.push(&text[line_start..]);
+
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let mut pairs = Vec::new();
+    let mut term: Option<&str> = None;
+    let mut term_defined = true;
+    for line in lines {
+        match line.trim().strip_prefix(':') {
+            Some(definition) => {
+                pairs.push(format!("{}: {}", term?, definition.trim()));
+                term_defined = true;
+            }
+            None if term_defined => {
+                term = Some(line.trim());
+                term_defined = false;
+            }
+            // Two term lines in a row is not a definition list
+            None => return None,
+        }
+    }
+
+    term_defined.then(|| pairs.join("\n"))
+}
+
+#[async_trait]
+impl ContentProvider for MarkdownProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Markdown, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        Ok(self.chunk_str(&content.text))
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.metadata_from_str(&content.text, file_name, file_metadata.len());
+        content.record(&mut metadata);
+        Ok(metadata)
     }
 
     async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
-        let service = EmbeddingService::global();
-        let service = service.lock().await;
+        let service = EmbeddingService::global().await?;
         
         let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-        let response = service.embed(texts).await?;
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
         
-        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let mut metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64);
+        content.record(&mut metadata);
+
+        Ok(Some(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Processed,
+            chunks: self.chunk_str(&content.text),
+            metadata,
+            error: None,
+        }))
     }
 }
\ No newline at end of file
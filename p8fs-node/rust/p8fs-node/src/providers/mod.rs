@@ -1,16 +1,76 @@
 pub mod pdf;
+pub mod archive;
 pub mod audio;
+pub mod code;
 pub mod document;
+pub mod fixed_width;
+pub mod gzip;
+pub mod hashing;
+pub mod image;
+pub mod ids;
 pub mod json;
+pub mod language;
+pub mod limits;
 pub mod markdown;
+pub mod odt;
+pub mod outcome;
+pub mod pptx;
 pub mod registry;
+pub mod rtf;
+pub mod sql;
+pub mod subtitle;
+pub mod tables;
+pub mod text;
+pub mod toml;
+pub mod traced;
+pub mod xlsx;
+pub mod yaml;
 
 #[cfg(test)]
 mod tests;
 
-use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult};
+use crate::models::{ChunkStrategy, ContentChunk, ContentMetadata, ContentProcessingResult};
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// The document is encrypted and cannot be opened without a password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Document is encrypted and cannot be read without a password")]
+pub struct Encrypted;
+
+/// A title made from a file's name, for documents that do not carry one:
+/// the extension is dropped, dashes and underscores become spaces and each
+/// word is capitalized, so `my_cool_doc.pdf` becomes `My Cool Doc`. `None`
+/// if the name has no letters or digits.
+pub fn title_from_filename(path: impl AsRef<Path>) -> Option<String> {
+    let stem = path.as_ref().file_stem()?.to_string_lossy();
+    let words: Vec<String> = stem
+        .split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Fills in the metadata every result carries whatever its provider: the
+/// upload's own `file_name`, since providers only see a staged temp file,
+/// and a `title` made from it when the document has none of its own.
+pub fn fill_default_metadata(metadata: &mut ContentMetadata, file_name: &str) {
+    metadata.file_name = Some(file_name.to_string());
+    if metadata.title.as_deref().is_none_or(|title| title.trim().is_empty()) {
+        metadata.title = title_from_filename(file_name);
+    }
+}
 
 #[async_trait]
 pub trait ContentProvider: Send + Sync {
@@ -21,4 +81,48 @@ pub trait ContentProvider: Send + Sync {
     async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata>;
     
     async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>>;
+
+    /// The file's complete native metadata (e.g. the PDF info dictionary) as
+    /// JSON, for callers that need more than the normalized `ContentMetadata`.
+    async fn raw_metadata(&self, _file_path: &Path) -> anyhow::Result<Option<serde_json::Value>> {
+        Ok(None)
+    }
+
+    /// Processes an upload straight from memory. Text-based providers override
+    /// this; `None` means the upload has to be written to disk and passed to
+    /// `process_content` instead.
+    async fn process_bytes(&self, _bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        Ok(None)
+    }
+
+    /// Yields the file's chunks as they are extracted. Providers that can
+    /// produce chunks incrementally override this; the default waits for
+    /// `to_markdown_chunks` and yields its chunks one by one.
+    fn stream_chunks<'a>(&'a self, file_path: &'a Path) -> BoxStream<'a, anyhow::Result<ContentChunk>> {
+        stream::once(self.to_markdown_chunks(file_path))
+            .flat_map(|chunks| match chunks {
+                Ok(chunks) => stream::iter(chunks.into_iter().map(Ok)).boxed(),
+                Err(err) => stream::once(async { Err(err) }).boxed(),
+            })
+            .boxed()
+    }
+
+    /// This provider chunking text with `strategy` instead, or `None` if its
+    /// chunks follow the format's own structure and `strategy` does not apply.
+    fn with_chunk_strategy(&self, _strategy: ChunkStrategy) -> Option<Arc<dyn ContentProvider>> {
+        None
+    }
+
+    /// This provider emitting chunks grouped by `kind`, each group led by a
+    /// summary chunk when `kind_summaries` is set, or `None` if the format
+    /// has no `kind` objects to group.
+    fn with_kind_grouping(&self, _kind_summaries: bool) -> Option<Arc<dyn ContentProvider>> {
+        None
+    }
+
+    /// This provider drawing on `permits` for work it dispatches to other
+    /// providers, or `None` if it runs no other providers.
+    fn with_dispatch_permits(&self, _permits: Arc<Semaphore>) -> Option<Arc<dyn ContentProvider>> {
+        None
+    }
 }
\ No newline at end of file
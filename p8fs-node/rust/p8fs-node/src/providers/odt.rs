@@ -0,0 +1,297 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType};
+use crate::providers::language::detect_language;
+use crate::providers::markdown::MarkdownProvider;
+use crate::providers::pptx::{attribute, read_part};
+use crate::providers::{ids, outcome, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Elements of `content.xml` whose text is not part of the body: footnotes
+/// and endnotes, comments, and the record of tracked changes.
+const SKIPPED_ELEMENTS: &[&[u8]] = &[b"note", b"annotation", b"tracked-changes"];
+
+/// The most spaces one `<text:s text:c="…"/>` expands to. The count comes from
+/// the file, and a huge one would otherwise allocate without bound.
+const MAX_SPACE_RUN: usize = 64;
+
+/// `meta.xml` fields copied into `additional` when present, as `(key, element)`.
+/// Title, author, dates and language have their own fields.
+const EXTRA_PROPERTIES: [(&str, &str); 5] = [
+    ("subject", "dc:subject"),
+    ("description", "dc:description"),
+    ("keywords", "meta:keyword"),
+    ("last_modified_by", "dc:creator"),
+    ("application", "meta:generator"),
+];
+
+/// Reads OpenDocument Text (`.odt`) packages. The body is rendered as
+/// markdown, headings at their outline level, and chunked by section like a
+/// markdown upload.
+pub struct OdtProvider;
+
+impl OdtProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub(crate) fn chunk_bytes(&self, bytes: &[u8]) -> anyhow::Result<Vec<ContentChunk>> {
+        let markdown = read_markdown(bytes)?;
+
+        let mut chunks = MarkdownProvider::new().chunk_str(&markdown);
+        for chunk in &mut chunks {
+            chunk.metadata.insert("source".to_string(), serde_json::json!("odt"));
+        }
+        ids::assign_chunk_ids("odt", &ids::doc_hash(bytes), &mut chunks);
+        Ok(chunks)
+    }
+
+    fn metadata_from_bytes(&self, bytes: &[u8], file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        let properties = read_meta(bytes)?;
+        let property = |name: &str| {
+            properties
+                .get(name)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        let mut additional = HashMap::new();
+        for (key, name) in EXTRA_PROPERTIES {
+            if let Some(value) = property(name) {
+                additional.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
+        for (key, name) in [("page_count", "meta:page-count"), ("word_count", "meta:word-count")] {
+            if let Some(count) = property(name).and_then(|count| count.parse::<u64>().ok()) {
+                additional.insert(key.to_string(), serde_json::json!(count));
+            }
+        }
+
+        // Only read the body when the document doesn't name its language
+        let language = match property("dc:language") {
+            Some(language) => Some(language),
+            None => read_markdown(bytes).ok().as_deref().and_then(detect_language),
+        };
+
+        Ok(ContentMetadata {
+            content_type: ContentType::Document,
+            file_name,
+            file_size: Some(file_size),
+            created_at: property("meta:creation-date"),
+            modified_at: property("dc:date"),
+            author: property("meta:initial-creator").or_else(|| property("dc:creator")),
+            title: property("dc:title"),
+            language,
+            additional,
+        })
+    }
+}
+
+/// The package's `content.xml` rendered as markdown.
+fn read_markdown(bytes: &[u8]) -> anyhow::Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let xml = read_part(&mut archive, "content.xml")?
+        .ok_or_else(|| anyhow::anyhow!("Not an OpenDocument package: content.xml is missing"))?;
+
+    content_to_markdown(&xml)
+}
+
+/// Every field of the package's `meta.xml`, keyed by qualified element name.
+/// Repeated elements (`meta:keyword`) are joined with commas, and the
+/// document statistics are keyed by attribute (`meta:page-count`, ...). A
+/// package without `meta.xml` has no fields.
+fn read_meta(bytes: &[u8]) -> anyhow::Result<Map<String, Value>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut fields = Map::new();
+    let Some(xml) = read_part(&mut archive, "meta.xml")? else {
+        return Ok(fields);
+    };
+
+    let mut reader = Reader::from_str(&xml);
+    let mut current: Option<(String, String)> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+                if !matches!(element.local_name().as_ref(), b"document-meta" | b"meta") {
+                    current = Some((name, String::new()));
+                }
+            }
+            Event::Empty(element) if element.local_name().as_ref() == b"document-statistic" => {
+                for attribute in element.attributes().flatten() {
+                    let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+                    fields.insert(name, Value::String(attribute.unescape_value()?.into_owned()));
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, value)) = current.as_mut() {
+                    value.push_str(&text.unescape()?);
+                }
+            }
+            Event::End(_) => {
+                if let Some((name, value)) = current.take() {
+                    let value = match fields.get(&name).and_then(Value::as_str) {
+                        Some(earlier) => format!("{}, {}", earlier, value.trim()),
+                        None => value.trim().to_string(),
+                    };
+                    fields.insert(name, Value::String(value));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(fields)
+}
+
+/// One heading, paragraph or list item of the body.
+struct Block {
+    heading_level: Option<usize>,
+    list_depth: usize,
+    text: String,
+}
+
+/// Renders the body as markdown: `text:h` headings as `#` headings at their
+/// outline level, paragraphs separated by blank lines, and list items as `-`
+/// bullets indented by nesting. Paragraphs nested inside another (e.g. in a
+/// frame) run into it.
+fn content_to_markdown(xml: &str) -> anyhow::Result<String> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut block: Option<Block> = None;
+    let mut nested_paragraphs = 0;
+    let mut list_depth = 0;
+    let mut skip_depth = 0;
+
+    loop {
+        let event = reader.read_event()?;
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(element) => match element.local_name().as_ref() {
+                name if SKIPPED_ELEMENTS.contains(&name) => skip_depth = 1,
+                b"h" | b"p" if block.is_some() => nested_paragraphs += 1,
+                b"h" => {
+                    let level = attribute(&element, b"text:outline-level")?.and_then(|level| level.parse().ok());
+                    block = Some(Block { heading_level: Some(level.unwrap_or(1)), list_depth, text: String::new() });
+                }
+                b"p" => block = Some(Block { heading_level: None, list_depth, text: String::new() }),
+                b"list" => list_depth += 1,
+                _ => {}
+            },
+            Event::Empty(element) => {
+                let Some(block) = block.as_mut() else { continue };
+                match element.local_name().as_ref() {
+                    b"s" => {
+                        let count = attribute(&element, b"text:c")?.and_then(|count| count.parse().ok()).unwrap_or(1);
+                        block.text.push_str(&" ".repeat(count.min(MAX_SPACE_RUN)));
+                    }
+                    b"tab" => block.text.push('\t'),
+                    b"line-break" => block.text.push('\n'),
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                if let Some(block) = block.as_mut() {
+                    block.text.push_str(&text.unescape()?);
+                }
+            }
+            Event::End(element) => match element.local_name().as_ref() {
+                b"h" | b"p" if nested_paragraphs > 0 => nested_paragraphs -= 1,
+                b"h" | b"p" => {
+                    if let Some(block) = block.take().filter(|block| !block.text.trim().is_empty()) {
+                        blocks.push(block);
+                    }
+                }
+                b"list" => list_depth -= 1,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut markdown = String::new();
+    let mut previous_in_list = false;
+    for block in blocks {
+        let text = block.text.trim();
+        let in_list = block.list_depth > 0 && block.heading_level.is_none();
+        if !markdown.is_empty() {
+            markdown.push_str(if in_list && previous_in_list { "\n" } else { "\n\n" });
+        }
+
+        match block.heading_level {
+            Some(level) => {
+                markdown.push_str(&format!("{} {}", "#".repeat(level.clamp(1, 6)), text.replace('\n', " ")));
+            }
+            None if in_list => {
+                markdown.push_str(&format!("{}- {}", "  ".repeat(block.list_depth - 1), text.replace('\n', " ")));
+            }
+            None => markdown.push_str(text),
+        }
+        previous_in_list = in_list;
+    }
+
+    Ok(markdown)
+}
+
+#[async_trait]
+impl ContentProvider for OdtProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Document, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        tokio::task::spawn_blocking(move || OdtProvider::new().chunk_bytes(&file_bytes)).await?
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        tokio::task::spawn_blocking(move || {
+            OdtProvider::new().metadata_from_bytes(&file_bytes, file_name, file_bytes.len() as u64)
+        })
+        .await?
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<Value>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        let fields = tokio::task::spawn_blocking(move || read_meta(&file_bytes)).await??;
+
+        Ok(Some(Value::Object(fields)))
+    }
+}
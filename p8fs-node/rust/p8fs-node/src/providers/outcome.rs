@@ -0,0 +1,83 @@
+//! Turning a provider run into a `ContentProcessingResult`.
+//!
+//! A file the provider cannot make sense of (invalid JSON, a corrupt PDF) is
+//! reported in the result, with `success: false`, status `Failed`, the error
+//! message and no chunks, so clients get the same shape back either way.
+//! `LimitExceeded` and `Encrypted` stay errors: the API rejects those uploads
+//! with their own status codes.
+
+use super::limits::LimitExceeded;
+use super::Encrypted;
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, ProcessingStatus};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The result of a run that produced `chunks` and `metadata`. If either
+/// failed, the upload is reported as failed, keeping the metadata when it
+/// could be read and `fallback` otherwise.
+pub fn build(
+    chunks: anyhow::Result<Vec<ContentChunk>>,
+    metadata: anyhow::Result<ContentMetadata>,
+    fallback: impl FnOnce() -> ContentMetadata,
+) -> anyhow::Result<ContentProcessingResult> {
+    let (metadata, metadata_error) = match metadata {
+        Ok(metadata) => (metadata, None),
+        Err(err) => (fallback(), Some(err)),
+    };
+
+    match (chunks, metadata_error) {
+        (Ok(chunks), None) => Ok(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Processed,
+            chunks,
+            metadata,
+            error: None,
+        }),
+        (Err(err), _) | (Ok(_), Some(err)) => failed(err, metadata),
+    }
+}
+
+/// Like `build`, for `process_content`: the fallback metadata is the file's
+/// name and size.
+pub async fn build_for_file(
+    content_type: ContentType,
+    file_path: &Path,
+    chunks: anyhow::Result<Vec<ContentChunk>>,
+    metadata: anyhow::Result<ContentMetadata>,
+) -> anyhow::Result<ContentProcessingResult> {
+    let file_size = tokio::fs::metadata(file_path).await.ok().map(|metadata| metadata.len());
+    let file_name = file_path.file_name().map(|name| name.to_string_lossy().to_string());
+
+    build(chunks, metadata, || basic_metadata(content_type, file_name, file_size))
+}
+
+/// Reports `err` as a failed result with `metadata`, unless it is one of the
+/// errors the API turns into a rejection.
+pub fn failed(err: anyhow::Error, metadata: ContentMetadata) -> anyhow::Result<ContentProcessingResult> {
+    if err.is::<LimitExceeded>() || err.is::<Encrypted>() {
+        return Err(err);
+    }
+
+    Ok(ContentProcessingResult {
+        success: false,
+        status: ProcessingStatus::Failed,
+        chunks: Vec::new(),
+        metadata,
+        error: Some(err.to_string()),
+    })
+}
+
+/// Metadata for an upload whose own metadata could not be read.
+pub fn basic_metadata(content_type: ContentType, file_name: Option<String>, file_size: Option<u64>) -> ContentMetadata {
+    ContentMetadata {
+        content_type,
+        file_name,
+        file_size,
+        created_at: None,
+        modified_at: None,
+        author: None,
+        title: None,
+        language: None,
+        additional: HashMap::new(),
+    }
+}
@@ -1,8 +1,10 @@
-use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
-use crate::providers::ContentProvider;
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::language::{detect_language, SAMPLE_BYTES};
+use crate::providers::{ids, outcome, ContentProvider, Encrypted};
 use crate::services::EmbeddingService;
 use async_trait::async_trait;
-use pdf_extract::extract_text;
+use pdf_extract::{decode_text_string, output_doc_page, Document, Object, PlainTextOutput};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -13,58 +15,282 @@ impl PdfProvider {
         Self
     }
 
-    fn chunk_text(&self, text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
-        let chars: Vec<char> = text.chars().collect();
-        let mut chunks = Vec::new();
-        let mut start = 0;
-
-        while start < chars.len() {
-            let end = (start + chunk_size).min(chars.len());
-            let chunk: String = chars[start..end].iter().collect();
-            chunks.push(chunk);
-            
-            if end >= chars.len() {
-                break;
+    /// Extracts the document one page at a time and feeds it to a
+    /// `PageChunker`, so only the current page and the unchunked tail are held
+    /// in memory. Each chunk is returned with the 1-based pages it spans.
+    fn chunk_pages(&self, bytes: &[u8], chunk_size: usize, overlap: usize) -> anyhow::Result<Vec<PageChunk>> {
+        let mut document = Document::load_mem(bytes)?;
+        if document.is_encrypted() {
+            // Only documents encrypted with an empty user password can be read
+            document.decrypt("").map_err(|_| Encrypted)?;
+        }
+
+        let mut chunker = PageChunker::new(chunk_size, overlap);
+        for page_number in document.get_pages().into_keys() {
+            let mut text = String::new();
+            output_doc_page(&document, &mut PlainTextOutput::new(&mut text), page_number)?;
+            chunker.push_page(page_number, &text);
+        }
+
+        Ok(chunker.finish())
+    }
+}
+
+/// A chunk cut by `PageChunker`. `char_start..char_end` is its span, in
+/// characters, of the document's extracted text with each page followed by
+/// a newline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PageChunk {
+    pub pages: Vec<u32>,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub text: String,
+}
+
+/// Fixed-size chunking with overlap over text that arrives page by page.
+/// Chunks may cross page breaks; each records the pages its characters came from.
+pub(crate) struct PageChunker {
+    chunk_size: usize,
+    overlap: usize,
+    /// Text not yet fully consumed by emitted chunks.
+    buffer: Vec<char>,
+    /// Offset of `buffer[0]` in the whole document's text.
+    buffer_start: usize,
+    /// Start offset of each page still overlapping `buffer`, in page order.
+    page_starts: Vec<(usize, u32)>,
+    chunks: Vec<PageChunk>,
+}
+
+impl PageChunker {
+    pub(crate) fn new(chunk_size: usize, overlap: usize) -> Self {
+        Self {
+            chunk_size,
+            overlap,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            page_starts: Vec::new(),
+            chunks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push_page(&mut self, page_number: u32, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+
+        self.page_starts.push((self.buffer_start + self.buffer.len(), page_number));
+        self.buffer.extend(text.chars());
+        // Keep words on either side of a page break apart
+        self.buffer.push('\n');
+
+        // Only emit a full chunk once more text follows it, so the final chunk
+        // is never just the previous chunk's overlap
+        while self.buffer.len() > self.chunk_size {
+            self.emit(self.chunk_size);
+
+            let step = self.chunk_size - self.overlap;
+            self.buffer.drain(..step);
+            self.buffer_start += step;
+
+            while self.page_starts.len() > 1 && self.page_starts[1].0 <= self.buffer_start {
+                self.page_starts.remove(0);
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<PageChunk> {
+        if !self.buffer.is_empty() {
+            self.emit(self.buffer.len());
+        }
+        self.chunks
+    }
+
+    fn emit(&mut self, len: usize) {
+        let text: String = self.buffer[..len].iter().collect();
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let end = self.buffer_start + len;
+        let pages = self
+            .page_starts
+            .iter()
+            .filter(|&&(start, _)| start < end)
+            .map(|&(_, page_number)| page_number)
+            .collect();
+
+        self.chunks.push(PageChunk {
+            pages,
+            char_start: self.buffer_start,
+            char_end: end,
+            text,
+        });
+    }
+}
+
+/// The normalized fields `to_metadata` takes from a PDF.
+struct DocumentInfo {
+    title: Option<String>,
+    author: Option<String>,
+    created_at: Option<String>,
+    modified_at: Option<String>,
+    page_count: usize,
+    /// Detected from the text of the first pages.
+    language: Option<String>,
+}
+
+/// Reads the page count and the Info dictionary's title, author and dates,
+/// and detects the language from the first pages' text. Fields are `None`
+/// when absent, or when the document is encrypted with a password other than
+/// the empty one, since its strings can't be read then.
+fn read_document_info(bytes: &[u8]) -> anyhow::Result<DocumentInfo> {
+    let mut document = Document::load_mem(bytes)?;
+    let readable = !document.is_encrypted() || document.decrypt("").is_ok();
+
+    let info = document
+        .trailer
+        .get(b"Info")
+        .ok()
+        .filter(|_| readable)
+        .and_then(|info| document.dereference(info).ok())
+        .and_then(|(_, info)| info.as_dict().ok());
+
+    let text = |key: &[u8]| {
+        let value = info?.get(key).ok()?;
+        let (_, value) = document.dereference(value).ok()?;
+        let text = decode_text_string(value).ok()?;
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    };
+
+    Ok(DocumentInfo {
+        title: text(b"Title"),
+        author: text(b"Author"),
+        created_at: text(b"CreationDate").and_then(|date| pdf_date_to_iso(&date)),
+        modified_at: text(b"ModDate").and_then(|date| pdf_date_to_iso(&date)),
+        page_count: document.get_pages().len(),
+        language: readable.then(|| detect_language(&opening_text(&document))).flatten(),
+    })
+}
+
+/// The extracted text of the first pages, up to about `SAMPLE_BYTES`. Pages
+/// that fail to extract are skipped.
+fn opening_text(document: &Document) -> String {
+    let mut text = String::new();
+    for page_number in document.get_pages().into_keys() {
+        if text.len() >= SAMPLE_BYTES {
+            break;
+        }
+        let _ = output_doc_page(document, &mut PlainTextOutput::new(&mut text), page_number);
+    }
+    text
+}
+
+/// Converts a PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`, where everything after the
+/// year is optional) to ISO 8601, e.g. `2024-03-15T09:30:00+01:00`.
+pub(crate) fn pdf_date_to_iso(date: &str) -> Option<String> {
+    let date = date.trim();
+    let date = date.strip_prefix("D:").unwrap_or(date);
+    let digits = date.chars().take_while(char::is_ascii_digit).count();
+    if digits < 4 || digits % 2 != 0 || digits > 14 {
+        return None;
+    }
+
+    let (stamp, zone) = date.split_at(digits);
+    let field = |start: usize, default: &'static str| stamp.get(start..start + 2).unwrap_or(default);
+    let mut iso = format!(
+        "{}-{}-{}T{}:{}:{}",
+        &stamp[..4],
+        field(4, "01"),
+        field(6, "01"),
+        field(8, "00"),
+        field(10, "00"),
+        field(12, "00"),
+    );
+
+    let zone: String = zone.chars().filter(|c| *c != '\'').collect();
+    match zone.chars().next() {
+        None => {}
+        Some('Z') => iso.push('Z'),
+        Some(sign @ ('+' | '-')) => {
+            let hours = zone.get(1..3)?;
+            let minutes = zone.get(3..5).unwrap_or("00");
+            if !(hours.chars().chain(minutes.chars()).all(|c| c.is_ascii_digit())) {
+                return None;
             }
-            
-            start = end - overlap;
+            iso.push_str(&format!("{}{}:{}", sign, hours, minutes));
         }
+        Some(_) => return None,
+    }
 
-        chunks
+    Some(iso)
+}
+
+/// Converts a PDF object to JSON, following references up to a fixed depth.
+fn pdf_object_to_json(document: &Document, object: &Object, depth: usize) -> Value {
+    match object {
+        Object::Null => Value::Null,
+        Object::Boolean(b) => Value::Bool(*b),
+        Object::Integer(i) => serde_json::json!(i),
+        Object::Real(r) => serde_json::json!(r),
+        Object::Name(name) => Value::String(String::from_utf8_lossy(name).into_owned()),
+        Object::String(bytes, _) => Value::String(
+            decode_text_string(object).unwrap_or_else(|_| String::from_utf8_lossy(bytes).into_owned()),
+        ),
+        Object::Array(items) => Value::Array(
+            items.iter().map(|item| pdf_object_to_json(document, item, depth + 1)).collect(),
+        ),
+        Object::Dictionary(dict) => Value::Object(
+            dict.iter()
+                .map(|(key, value)| (String::from_utf8_lossy(key).into_owned(), pdf_object_to_json(document, value, depth + 1)))
+                .collect(),
+        ),
+        Object::Reference(_) if depth < 8 => match document.dereference(object) {
+            Ok((_, resolved)) => pdf_object_to_json(document, resolved, depth + 1),
+            Err(_) => Value::Null,
+        },
+        _ => Value::Null,
     }
 }
 
 #[async_trait]
 impl ContentProvider for PdfProvider {
     async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
-        let chunks = self.to_markdown_chunks(file_path).await?;
-        let metadata = self.to_metadata(file_path).await?;
-        
-        Ok(ContentProcessingResult {
-            success: true,
-            chunks,
-            metadata,
-            error: None,
-        })
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Pdf, file_path, chunks, metadata).await
     }
 
     async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
-        let text = tokio::task::spawn_blocking({
-            let path = file_path.to_owned();
-            move || extract_text(&path)
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let doc_hash = ids::doc_hash(&file_bytes);
+
+        let page_chunks = tokio::task::spawn_blocking(move || {
+            let provider = PdfProvider::new();
+            provider.chunk_pages(&file_bytes, 1000, 200)
         })
         .await??;
-
-        let chunk_texts = self.chunk_text(&text, 1000, 200);
         
-        let chunks: Vec<ContentChunk> = chunk_texts
+        let mut chunks: Vec<ContentChunk> = page_chunks
             .into_iter()
             .enumerate()
-            .map(|(i, content)| {
+            .map(|(i, PageChunk { pages, char_start, char_end, text: content })| {
+                let page_reference = match (pages.first(), pages.last()) {
+                    (Some(first), Some(last)) if first != last => format!("Pages {}-{}", first, last),
+                    (Some(first), _) => format!("Page {}", first),
+                    _ => String::new(),
+                };
+
                 let mut metadata = HashMap::new();
                 metadata.insert("chunk_index".to_string(), serde_json::json!(i));
                 metadata.insert("source".to_string(), serde_json::json!("pdf"));
-                metadata.insert("page_reference".to_string(), serde_json::json!(format!("Page ~{}", i + 1)));
+                metadata.insert("pages".to_string(), serde_json::json!(pages));
+                metadata.insert("page_number".to_string(), serde_json::json!(pages.first()));
+                metadata.insert("page".to_string(), serde_json::json!(pages.first()));
+                metadata.insert("char_start".to_string(), serde_json::json!(char_start));
+                metadata.insert("char_end".to_string(), serde_json::json!(char_end));
+                metadata.insert("page_reference".to_string(), serde_json::json!(page_reference));
                 
                 // Format content as markdown with proper structure
                 let markdown_content = if i == 0 {
@@ -74,39 +300,59 @@ impl ContentProvider for PdfProvider {
                 };
                 
                 ContentChunk {
-                    id: format!("pdf_chunk_{}", i),
+                    id: String::new(),
                     content: markdown_content,
                     metadata,
                 }
             })
             .collect();
 
+        ids::assign_chunk_ids("pdf", &doc_hash, &mut chunks);
         Ok(chunks)
     }
 
     async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
         let file_metadata = tokio::fs::metadata(file_path).await?;
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        let info = tokio::task::spawn_blocking(move || read_document_info(&file_bytes)).await??;
+
+        let mut additional = HashMap::new();
+        additional.insert("page_count".to_string(), serde_json::json!(info.page_count));
         
         Ok(ContentMetadata {
             content_type: ContentType::Pdf,
             file_name: file_path.file_name().map(|n| n.to_string_lossy().to_string()),
             file_size: Some(file_metadata.len()),
-            created_at: None,
-            modified_at: None,
-            author: None,
-            title: None,
-            language: None,
-            additional: HashMap::new(),
+            created_at: info.created_at,
+            modified_at: info.modified_at,
+            author: info.author,
+            title: info.title,
+            language: info.language,
+            additional,
         })
     }
 
     async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
-        let service = EmbeddingService::global();
-        let service = service.lock().await;
+        let service = EmbeddingService::global().await?;
         
         let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-        let response = service.embed(texts).await?;
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
         
-        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<Value>> {
+        let path = file_path.to_owned();
+
+        tokio::task::spawn_blocking(move || -> anyhow::Result<Option<Value>> {
+            let document = Document::load(&path)?;
+            let Ok(info) = document.trailer.get(b"Info") else {
+                return Ok(None);
+            };
+
+            Ok(Some(pdf_object_to_json(&document, info, 0)))
+        })
+        .await?
     }
 }
\ No newline at end of file
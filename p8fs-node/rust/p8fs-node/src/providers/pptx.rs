@@ -0,0 +1,317 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::document::read_docx_properties;
+use crate::providers::limits::env_or;
+use crate::providers::{ids, outcome, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Chunks a PowerPoint deck into one chunk per slide, in slide order.
+pub struct PptxProvider {
+    include_notes: bool,
+}
+
+/// Text pulled out of one slide.
+#[derive(Debug, Default)]
+struct Slide {
+    number: usize,
+    title: Option<String>,
+    /// Paragraphs from every shape other than the title, in document order.
+    body: Vec<String>,
+    /// The speaker notes, if the slide has any.
+    notes: Option<String>,
+}
+
+impl PptxProvider {
+    /// Includes speaker notes unless `P8FS_PPTX_INCLUDE_NOTES` is `false`.
+    pub fn new() -> Self {
+        Self {
+            include_notes: env_or("P8FS_PPTX_INCLUDE_NOTES", true),
+        }
+    }
+
+    /// Whether speaker notes are added to each slide's chunk. `has_notes` is
+    /// set in the metadata either way.
+    pub fn include_notes(mut self, include_notes: bool) -> Self {
+        self.include_notes = include_notes;
+        self
+    }
+
+    pub(crate) fn chunk_bytes(&self, bytes: &[u8]) -> anyhow::Result<Vec<ContentChunk>> {
+        let doc_hash = ids::doc_hash(bytes);
+
+        let mut chunks: Vec<ContentChunk> = read_slides(bytes)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, slide)| {
+                let mut metadata = HashMap::new();
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("pptx"));
+                metadata.insert("slide_number".to_string(), serde_json::json!(slide.number));
+                metadata.insert("has_notes".to_string(), serde_json::json!(slide.notes.is_some()));
+                if let Some(title) = &slide.title {
+                    metadata.insert("slide_title".to_string(), serde_json::json!(title));
+                }
+
+                ContentChunk {
+                    id: String::new(),
+                    content: self.render_slide(&slide),
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("pptx", &doc_hash, &mut chunks);
+        Ok(chunks)
+    }
+
+    fn render_slide(&self, slide: &Slide) -> String {
+        let heading = match &slide.title {
+            Some(title) => title.clone(),
+            None => format!("Slide {}", slide.number),
+        };
+
+        let mut content = format!("## {}", heading);
+        for paragraph in &slide.body {
+            content.push_str("\n\n");
+            content.push_str(paragraph);
+        }
+
+        if let Some(notes) = slide.notes.as_ref().filter(|_| self.include_notes) {
+            content.push_str("\n\n### Notes\n\n");
+            content.push_str(notes);
+        }
+
+        content
+    }
+
+    fn metadata_from_bytes(&self, bytes: &[u8], file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        // Presentations use the same OPC property parts as DOCX
+        let properties = read_docx_properties(bytes)?;
+        let property = |part: &str, name: &str| {
+            properties[part][name]
+                .as_str()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        let mut additional = HashMap::new();
+        additional.insert("slide_count".to_string(), serde_json::json!(slide_parts(bytes)?.len()));
+        for (key, part, name) in [("subject", "core", "dc:subject"), ("company", "app", "Company")] {
+            if let Some(value) = property(part, name) {
+                additional.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
+
+        Ok(ContentMetadata {
+            content_type: ContentType::Presentation,
+            file_name,
+            file_size: Some(file_size),
+            created_at: property("core", "dcterms:created"),
+            modified_at: property("core", "dcterms:modified"),
+            author: property("core", "dc:creator"),
+            title: property("core", "dc:title"),
+            language: property("core", "dc:language"),
+            additional,
+        })
+    }
+}
+
+/// The `ppt/slides/slideN.xml` parts, ordered by `N`.
+fn slide_parts(bytes: &[u8]) -> anyhow::Result<Vec<(usize, String)>> {
+    let archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+    let mut parts: Vec<(usize, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let number = name.strip_prefix("ppt/slides/slide")?.strip_suffix(".xml")?.parse().ok()?;
+            Some((number, name.to_string()))
+        })
+        .collect();
+    parts.sort();
+
+    Ok(parts)
+}
+
+/// Reads the text and speaker notes of every slide in the deck.
+fn read_slides(bytes: &[u8]) -> anyhow::Result<Vec<Slide>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut slides = Vec::new();
+
+    for (number, part) in slide_parts(bytes)? {
+        let mut slide = Slide {
+            number,
+            ..Slide::default()
+        };
+
+        for (placeholder, paragraph) in shape_paragraphs(&read_part(&mut archive, &part)?.unwrap_or_default())? {
+            match placeholder.as_deref() {
+                Some("title" | "ctrTitle") if slide.title.is_none() => slide.title = Some(paragraph),
+                Some("title" | "ctrTitle") => {
+                    if let Some(title) = slide.title.as_mut() {
+                        title.push(' ');
+                        title.push_str(&paragraph);
+                    }
+                }
+                // Footers repeat on every slide and carry no content
+                Some("sldNum" | "dt" | "ftr") => {}
+                _ => slide.body.push(paragraph),
+            }
+        }
+
+        let rels_part = part.replacen("ppt/slides/", "ppt/slides/_rels/", 1) + ".rels";
+        if let Some(notes_part) = notes_target(&read_part(&mut archive, &rels_part)?.unwrap_or_default())? {
+            let notes_xml = read_part(&mut archive, &notes_part)?.unwrap_or_default();
+            let notes: Vec<String> = shape_paragraphs(&notes_xml)?
+                .into_iter()
+                .filter(|(placeholder, _)| placeholder.as_deref() == Some("body"))
+                .map(|(_, paragraph)| paragraph)
+                .collect();
+            if !notes.is_empty() {
+                slide.notes = Some(notes.join("\n\n"));
+            }
+        }
+
+        slides.push(slide);
+    }
+
+    Ok(slides)
+}
+
+pub(crate) fn read_part(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> anyhow::Result<Option<String>> {
+    let mut xml = String::new();
+    match archive.by_name(name) {
+        Ok(mut file) => file.read_to_string(&mut xml)?,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(Some(xml))
+}
+
+/// The notes slide a slide's relationships point to, as a part name.
+fn notes_target(rels_xml: &str) -> anyhow::Result<Option<String>> {
+    let mut reader = Reader::from_str(rels_xml);
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) if element.local_name().as_ref() == b"Relationship" => {
+                let is_notes = attribute(&element, b"Type")?.is_some_and(|kind| kind.ends_with("/notesSlide"));
+                if let Some(target) = attribute(&element, b"Target")?.filter(|_| is_notes) {
+                    // Relative targets are resolved against ppt/slides/
+                    return Ok(Some(match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("ppt/{}", target.trim_start_matches("../")),
+                    }));
+                }
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Every non-empty paragraph in a slide or notes part, with the placeholder
+/// type (`title`, `body`, ...) of the shape it belongs to. Line breaks within
+/// a paragraph are kept.
+fn shape_paragraphs(xml: &str) -> anyhow::Result<Vec<(Option<String>, String)>> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut paragraphs = Vec::new();
+    let mut placeholder: Option<String> = None;
+    let mut paragraph: Option<String> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"sp" | b"graphicFrame" => placeholder = None,
+                b"ph" => placeholder = Some(attribute(&element, b"type")?.unwrap_or_else(|| "body".to_string())),
+                b"p" => paragraph = Some(String::new()),
+                b"t" => in_text = true,
+                _ => {}
+            },
+            Event::Empty(element) => match element.local_name().as_ref() {
+                // A placeholder without a type is a body placeholder
+                b"ph" => placeholder = Some(attribute(&element, b"type")?.unwrap_or_else(|| "body".to_string())),
+                b"br" => {
+                    if let Some(paragraph) = paragraph.as_mut() {
+                        paragraph.push('\n');
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_text => {
+                if let Some(paragraph) = paragraph.as_mut() {
+                    paragraph.push_str(&text.unescape()?);
+                }
+            }
+            Event::End(element) => match element.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => {
+                    if let Some(text) = paragraph.take().filter(|text| !text.trim().is_empty()) {
+                        paragraphs.push((placeholder.clone(), text.trim().to_string()));
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(paragraphs)
+}
+
+pub(crate) fn attribute(element: &BytesStart, name: &[u8]) -> anyhow::Result<Option<String>> {
+    match element.try_get_attribute(name)? {
+        Some(attribute) => Ok(Some(attribute.unescape_value()?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+#[async_trait]
+impl ContentProvider for PptxProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Presentation, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let include_notes = self.include_notes;
+
+        tokio::task::spawn_blocking(move || PptxProvider { include_notes }.chunk_bytes(&file_bytes)).await?
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        self.metadata_from_bytes(&file_bytes, file_name, file_bytes.len() as u64)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<Value>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        let properties = tokio::task::spawn_blocking(move || read_docx_properties(&file_bytes)).await??;
+
+        Ok(Some(properties))
+    }
+}
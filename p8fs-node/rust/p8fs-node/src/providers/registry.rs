@@ -1,5 +1,5 @@
-use crate::models::ContentType;
-use crate::providers::{ContentProvider, audio::AudioProvider, document::DocumentProvider, json::JsonProvider, markdown::MarkdownProvider, pdf::PdfProvider};
+use crate::models::{ContentType, SupportedType};
+use crate::providers::{ContentProvider, archive::ArchiveProvider, audio::AudioProvider, code::{CodeProvider, Language}, document::DocumentProvider, fixed_width::FixedWidthProvider, image::ImageProvider, json::JsonProvider, markdown::MarkdownProvider, odt::OdtProvider, pdf::PdfProvider, pptx::PptxProvider, rtf::RtfProvider, sql::SqlProvider, subtitle::SubtitleProvider, toml::TomlProvider, xlsx::XlsxProvider, yaml::YamlProvider};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -10,31 +10,286 @@ mod tests;
 
 pub type ProviderFactory = Arc<dyn ContentProvider>;
 
-static REGISTRY: Lazy<HashMap<ContentType, ProviderFactory>> = Lazy::new(|| {
-    let mut registry = HashMap::new();
-    
-    registry.insert(ContentType::Pdf, Arc::new(PdfProvider::new()) as ProviderFactory);
-    registry.insert(ContentType::Audio, Arc::new(AudioProvider::new()) as ProviderFactory);
-    registry.insert(ContentType::Document, Arc::new(DocumentProvider::new()) as ProviderFactory);
-    registry.insert(ContentType::StructuredData, Arc::new(JsonProvider::new()) as ProviderFactory);
-    registry.insert(ContentType::Markdown, Arc::new(MarkdownProvider::new()) as ProviderFactory);
-    
-    registry
-});
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::with_defaults);
+
+/// Where an extension or MIME type resolves to. `provider` overrides the one
+/// registered for `content_type`, for types such as `Code` whose files are
+/// handled differently depending on the extension.
+#[derive(Clone)]
+struct Route {
+    content_type: ContentType,
+    provider: Option<ProviderFactory>,
+}
+
+/// Maps content types, file extensions and MIME types to providers. The
+/// server uses the default-populated `Registry::global()`; build another with
+/// `Registry::new()` or `Registry::with_defaults()` to add or override
+/// providers, e.g. a mock in tests.
+#[derive(Clone, Default)]
+pub struct Registry {
+    providers: HashMap<ContentType, ProviderFactory>,
+    // In registration order, which `supported_types` lists them in
+    extensions: Vec<(String, Route)>,
+    mime_types: HashMap<String, Route>,
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every built-in provider with its extensions and MIME types.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(ContentType::Pdf, &["pdf"], Arc::new(PdfProvider::new()));
+        registry.register_mime_types(ContentType::Pdf, &["application/pdf"]);
+        registry.register(ContentType::Audio, &["wav"], Arc::new(AudioProvider::new()));
+        registry.register_mime_types(ContentType::Audio, &["audio/wav", "audio/x-wav", "audio/wave", "audio/vnd.wave"]);
+        registry.register(ContentType::Document, &["docx"], Arc::new(DocumentProvider::new()));
+        registry.register_mime_types(
+            ContentType::Document,
+            &["application/vnd.openxmlformats-officedocument.wordprocessingml.document"],
+        );
+        // RTF and ODT report as documents but have providers of their own
+        registry.register_route(
+            ContentType::Document,
+            &["rtf"],
+            &["application/rtf", "text/rtf"],
+            Arc::new(RtfProvider::new()),
+        );
+        registry.register_route(
+            ContentType::Document,
+            &["odt"],
+            &["application/vnd.oasis.opendocument.text"],
+            Arc::new(OdtProvider::new()),
+        );
+        registry.register(ContentType::StructuredData, &["json"], Arc::new(JsonProvider::new()));
+        registry.register_mime_types(ContentType::StructuredData, &["application/json", "text/json"]);
+        registry.register_route(
+            ContentType::StructuredData,
+            &["toml"],
+            &["application/toml"],
+            Arc::new(TomlProvider::new()),
+        );
+        registry.register(ContentType::Markdown, &["md", "markdown"], Arc::new(MarkdownProvider::new()));
+        registry.register_mime_types(ContentType::Markdown, &["text/markdown", "text/x-markdown"]);
+        // Forcing the type gets the generic provider, which reads the language
+        // from the extension; SQL and each known language are routed to their own
+        registry.register(ContentType::Code, &[], Arc::new(CodeProvider::generic()));
+        registry.register_route(
+            ContentType::Code,
+            &["sql", "ddl"],
+            &["application/sql", "text/x-sql"],
+            Arc::new(SqlProvider::new()),
+        );
+        for language in Language::ALL {
+            registry.register_route(
+                ContentType::Code,
+                language.extensions(),
+                language.mime_types(),
+                Arc::new(CodeProvider::new(language)),
+            );
+        }
+        registry.register(ContentType::Yaml, &["yaml", "yml"], Arc::new(YamlProvider::new()));
+        registry.register_mime_types(
+            ContentType::Yaml,
+            &["application/yaml", "application/x-yaml", "text/yaml", "text/x-yaml"],
+        );
+        registry.register(ContentType::Presentation, &["pptx"], Arc::new(PptxProvider::new()));
+        registry.register_mime_types(
+            ContentType::Presentation,
+            &["application/vnd.openxmlformats-officedocument.presentationml.presentation"],
+        );
+        registry.register(ContentType::Spreadsheet, &["xlsx"], Arc::new(XlsxProvider::new()));
+        registry.register_mime_types(
+            ContentType::Spreadsheet,
+            &["application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"],
+        );
+        registry.register(ContentType::Image, &["png", "jpg", "jpeg", "gif", "webp"], Arc::new(ImageProvider::new()));
+        registry.register_mime_types(ContentType::Image, &["image/png", "image/jpeg", "image/gif", "image/webp"]);
+        registry.register(ContentType::Archive, &["zip"], Arc::new(ArchiveProvider::new()));
+        registry.register_mime_types(ContentType::Archive, &["application/zip", "application/x-zip-compressed"]);
+        registry.register(ContentType::Subtitle, &["srt", "vtt"], Arc::new(SubtitleProvider::new()));
+        registry.register_mime_types(ContentType::Subtitle, &["application/x-subrip", "text/vtt"]);
+        // Only reachable by forcing the type; without a column spec it chunks plain text
+        registry.register(ContentType::FixedWidth, &[], Arc::new(FixedWidthProvider::new()));
+
+        registry
+    }
+
+    pub fn global() -> &'static Registry {
+        &REGISTRY
+    }
+
+    /// Makes `provider` the provider for `content_type` and routes
+    /// `extensions` to it, replacing any earlier registration of either.
+    pub fn register(&mut self, content_type: ContentType, extensions: &[&str], provider: ProviderFactory) {
+        self.providers.insert(content_type.clone(), provider);
+        self.route_extensions(extensions, Route { content_type, provider: None });
+    }
+
+    /// Routes `mime_types` to the provider registered for `content_type`.
+    pub fn register_mime_types(&mut self, content_type: ContentType, mime_types: &[&str]) {
+        let route = Route { content_type, provider: None };
+        for mime in mime_types {
+            self.mime_types.insert(mime.to_lowercase(), route.clone());
+        }
+    }
+
+    /// Routes `extensions` and `mime_types` to `provider` while reporting
+    /// them as `content_type`, leaving that type's own provider in place.
+    pub fn register_route(
+        &mut self,
+        content_type: ContentType,
+        extensions: &[&str],
+        mime_types: &[&str],
+        provider: ProviderFactory,
+    ) {
+        let route = Route { content_type, provider: Some(provider) };
+        self.route_extensions(extensions, route.clone());
+        for mime in mime_types {
+            self.mime_types.insert(mime.to_lowercase(), route.clone());
+        }
+    }
+
+    fn route_extensions(&mut self, extensions: &[&str], route: Route) {
+        for extension in extensions {
+            let extension = extension.to_lowercase();
+            self.extensions.retain(|(existing, _)| *existing != extension);
+            self.extensions.push((extension, route.clone()));
+        }
+    }
+
+    fn resolve(&self, route: &Route) -> Option<(ContentType, ProviderFactory)> {
+        let provider = route.provider.clone().or_else(|| self.get(&route.content_type))?;
+        Some((route.content_type.clone(), provider))
+    }
+
+    pub fn get(&self, content_type: &ContentType) -> Option<ProviderFactory> {
+        self.providers.get(content_type).cloned()
+    }
+
+    pub fn get_by_extension(&self, extension: &str) -> Option<(ContentType, ProviderFactory)> {
+        let extension = extension.to_lowercase();
+        let (_, route) = self.extensions.iter().find(|(registered, _)| *registered == extension)?;
+        self.resolve(route)
+    }
+
+    /// Resolves a provider from a declared MIME type such as a multipart
+    /// part's `Content-Type`. Parameters (`; charset=...`) are ignored, and an
+    /// unregistered type with a structured syntax suffix such as
+    /// `application/ld+json` falls back to `application/json`.
+    pub fn get_by_mime(&self, mime: &str) -> Option<(ContentType, ProviderFactory)> {
+        let essence = mime.split(';').next().unwrap_or_default().trim().to_lowercase();
+        let route = self.mime_types.get(&essence).or_else(|| {
+            let (_, suffix) = essence.rsplit_once('+')?;
+            self.mime_types.get(&format!("application/{}", suffix))
+        })?;
+        self.resolve(route)
+    }
+
+    /// Picks a provider by sniffing the leading bytes of a file, for uploads
+    /// whose extension is missing or wrong.
+    pub fn detect(&self, bytes: &[u8]) -> Option<(ContentType, ProviderFactory)> {
+        if bytes.starts_with(b"{\\rtf") {
+            return self.get_by_extension("rtf");
+        }
+        if is_odt(bytes) {
+            return self.get_by_extension("odt");
+        }
+
+        let content_type = if bytes.starts_with(b"%PDF") {
+            ContentType::Pdf
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+            ContentType::Audio
+        } else if is_image(bytes) {
+            ContentType::Image
+        } else if bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes).starts_with(b"WEBVTT") {
+            ContentType::Subtitle
+        } else if bytes.starts_with(b"PK\x03\x04") {
+            // DOCX, PPTX and XLSX are told apart by their main part; any
+            // other zip is an archive of files
+            if zip_contains(bytes, "ppt/presentation.xml") {
+                ContentType::Presentation
+            } else if zip_contains(bytes, "xl/workbook.xml") {
+                ContentType::Spreadsheet
+            } else if zip_contains(bytes, "word/document.xml") {
+                ContentType::Document
+            } else {
+                ContentType::Archive
+            }
+        } else {
+            let text = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+            match text.iter().find(|b| !b.is_ascii_whitespace()) {
+                Some(b'{') | Some(b'[') => ContentType::StructuredData,
+                _ => return None,
+            }
+        };
+
+        self.get(&content_type).map(|provider| (content_type, provider))
+    }
+
+    /// The registered content types, sorted by name, each with the extensions
+    /// that resolve to it.
+    pub fn supported_types(&self) -> Vec<SupportedType> {
+        let mut types: Vec<SupportedType> = self
+            .providers
+            .keys()
+            .map(|content_type| SupportedType {
+                content_type: content_type.clone(),
+                extensions: self
+                    .extensions
+                    .iter()
+                    .filter(|(_, route)| &route.content_type == content_type)
+                    .map(|(extension, _)| extension.clone())
+                    .collect(),
+            })
+            .collect();
+        types.sort_by_key(|supported| format!("{:?}", supported.content_type));
+
+        types
+    }
+}
 
 pub fn get_provider(content_type: &ContentType) -> Option<ProviderFactory> {
-    REGISTRY.get(content_type).cloned()
+    Registry::global().get(content_type)
+}
+
+pub fn supported_types() -> Vec<SupportedType> {
+    Registry::global().supported_types()
 }
 
 pub fn get_provider_by_extension(extension: &str) -> Option<(ContentType, ProviderFactory)> {
-    let content_type = match extension.to_lowercase().as_str() {
-        "pdf" => ContentType::Pdf,
-        "wav" => ContentType::Audio,
-        "docx" => ContentType::Document,
-        "json" => ContentType::StructuredData,
-        "md" | "markdown" => ContentType::Markdown,
-        _ => return None,
-    };
-    
-    get_provider(&content_type).map(|provider| (content_type, provider))
-}
\ No newline at end of file
+    Registry::global().get_by_extension(extension)
+}
+
+pub fn get_provider_by_mime(mime: &str) -> Option<(ContentType, ProviderFactory)> {
+    Registry::global().get_by_mime(mime)
+}
+
+pub fn detect_provider(bytes: &[u8]) -> Option<(ContentType, ProviderFactory)> {
+    Registry::global().detect(bytes)
+}
+
+/// PNG, JPEG, GIF or WebP signatures.
+fn is_image(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\x89PNG\r\n\x1a\n")
+        || bytes.starts_with(b"\xFF\xD8\xFF")
+        || bytes.starts_with(b"GIF87a")
+        || bytes.starts_with(b"GIF89a")
+        || (bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP")
+}
+
+/// OpenDocument packages start with an uncompressed `mimetype` entry, so
+/// the type is readable at a fixed offset.
+fn is_odt(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04")
+        && bytes.get(30..38) == Some(b"mimetype".as_slice())
+        && bytes[38..].starts_with(b"application/vnd.oasis.opendocument.text")
+}
+
+fn zip_contains(bytes: &[u8], name: &str) -> bool {
+    zip::ZipArchive::new(std::io::Cursor::new(bytes)).is_ok_and(|archive| archive.index_for_name(name).is_some())
+}
@@ -1,7 +1,44 @@
 #[cfg(test)]
 mod tests {
-    use super::super::registry::*;
+    use super::super::*;
     use crate::models::ContentType;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_supported_types_cover_every_extension() {
+        let types = supported_types();
+
+        for (extension, _) in &Registry::global().extensions {
+            let (content_type, _) = get_provider_by_extension(extension).unwrap();
+            let supported = types.iter().find(|t| t.content_type == content_type).unwrap();
+            assert!(supported.extensions.contains(extension), "{}", extension);
+        }
+
+        let fixed_width = types.iter().find(|t| t.content_type == ContentType::FixedWidth).unwrap();
+        assert!(fixed_width.extensions.is_empty());
+    }
+
+    #[test]
+    fn test_custom_registry_resolves_registered_provider() {
+        let mut registry = Registry::new();
+        let provider: ProviderFactory = Arc::new(crate::providers::markdown::MarkdownProvider::new());
+        registry.register(ContentType::Text, &["TXT", "text"], provider.clone());
+        registry.register_mime_types(ContentType::Text, &["text/plain"]);
+
+        let (content_type, found) = registry.get_by_extension("txt").unwrap();
+        assert_eq!(content_type, ContentType::Text);
+        assert!(Arc::ptr_eq(&found, &provider));
+        assert!(Arc::ptr_eq(&registry.get_by_mime("text/plain; charset=utf-8").unwrap().1, &provider));
+        assert!(registry.get_by_extension("pdf").is_none());
+        assert_eq!(registry.supported_types()[0].extensions, vec!["txt", "text"]);
+
+        // Overriding a default leaves the global registry alone
+        let mut registry = Registry::with_defaults();
+        registry.register(ContentType::Pdf, &["pdf"], provider.clone());
+        assert!(Arc::ptr_eq(&registry.get_by_extension("pdf").unwrap().1, &provider));
+        assert!(!Arc::ptr_eq(&get_provider_by_extension("pdf").unwrap().1, &provider));
+        assert!(registry.get_by_extension("docx").is_some());
+    }
 
     #[test]
     fn test_get_provider_by_content_type() {
@@ -38,6 +75,22 @@ mod tests {
             ("md", Some(ContentType::Markdown)),
             ("markdown", Some(ContentType::Markdown)),
             ("MD", Some(ContentType::Markdown)),
+            ("sql", Some(ContentType::Code)),
+            ("ddl", Some(ContentType::Code)),
+            ("yaml", Some(ContentType::Yaml)),
+            ("YML", Some(ContentType::Yaml)),
+            ("pptx", Some(ContentType::Presentation)),
+            ("XLSX", Some(ContentType::Spreadsheet)),
+            ("png", Some(ContentType::Image)),
+            ("JPG", Some(ContentType::Image)),
+            ("jpeg", Some(ContentType::Image)),
+            ("gif", Some(ContentType::Image)),
+            ("webp", Some(ContentType::Image)),
+            ("zip", Some(ContentType::Archive)),
+            ("rs", Some(ContentType::Code)),
+            ("py", Some(ContentType::Code)),
+            ("TS", Some(ContentType::Code)),
+            ("cpp", Some(ContentType::Code)),
             ("txt", None), // Unsupported extension
             ("xyz", None), // Non-existent extension
             ("", None),    // Empty extension
@@ -62,7 +115,7 @@ mod tests {
 
     #[test]
     fn test_provider_consistency() {
-        let extensions = vec!["pdf", "wav", "docx", "json", "md"];
+        let extensions = vec!["pdf", "wav", "docx", "json", "md", "yaml"];
         
         for extension in extensions {
             let (content_type, provider1) = get_provider_by_extension(extension).unwrap();
@@ -73,4 +126,93 @@ mod tests {
                 "Providers for {} should be the same instance", extension);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_provider_by_mime() {
+        let test_cases = vec![
+            ("application/pdf", Some(ContentType::Pdf)),
+            ("audio/wav", Some(ContentType::Audio)),
+            ("audio/x-wav", Some(ContentType::Audio)),
+            ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", Some(ContentType::Document)),
+            ("application/json", Some(ContentType::StructuredData)),
+            ("application/json; charset=utf-8", Some(ContentType::StructuredData)),
+            ("application/ld+json", Some(ContentType::StructuredData)),
+            ("text/markdown", Some(ContentType::Markdown)),
+            ("TEXT/MARKDOWN", Some(ContentType::Markdown)),
+            ("application/sql", Some(ContentType::Code)),
+            ("application/yaml", Some(ContentType::Yaml)),
+            ("application/vnd.k8s+yaml", Some(ContentType::Yaml)),
+            ("application/vnd.openxmlformats-officedocument.presentationml.presentation", Some(ContentType::Presentation)),
+            ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", Some(ContentType::Spreadsheet)),
+            ("image/png", Some(ContentType::Image)),
+            ("image/jpeg", Some(ContentType::Image)),
+            ("application/zip", Some(ContentType::Archive)),
+            ("text/x-python", Some(ContentType::Code)),
+            ("application/javascript", Some(ContentType::Code)),
+            ("application/octet-stream", None),
+            ("", None),
+        ];
+
+        for (mime, expected) in test_cases {
+            let result = get_provider_by_mime(mime).map(|(content_type, _)| content_type);
+            assert_eq!(result, expected, "MIME type {:?}", mime);
+        }
+    }
+
+    #[test]
+    fn test_detect_provider_from_bytes() {
+        let test_cases: Vec<(&[u8], Option<ContentType>)> = vec![
+            (b"%PDF-1.7\n%\xE2\xE3\xCF\xD3", Some(ContentType::Pdf)),
+            (b"RIFF\x24\x08\x00\x00WAVEfmt ", Some(ContentType::Audio)),
+            (b"PK\x03\x04\x14\x00\x06\x00", Some(ContentType::Archive)), // no readable OOXML part
+            (b"{\"key\": \"value\"}", Some(ContentType::StructuredData)),
+            (b"  \n[1, 2, 3]", Some(ContentType::StructuredData)),
+            (b"\xEF\xBB\xBF{}", Some(ContentType::StructuredData)),
+            (b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR", Some(ContentType::Image)),
+            (b"\xFF\xD8\xFF\xE1\x00\x10Exif", Some(ContentType::Image)),
+            (b"GIF89a\x01\x00\x01\x00", Some(ContentType::Image)),
+            (b"RIFF\x24\x08\x00\x00WEBPVP8 ", Some(ContentType::Image)),
+            (b"RIFF\x24\x08\x00\x00AVI LIST", None), // RIFF but not WAVE
+            (b"RIFF", None),
+            (b"# Just a heading", None),
+            (b"", None),
+        ];
+
+        for (bytes, expected) in test_cases {
+            let result = detect_provider(bytes).map(|(content_type, _)| content_type);
+            assert_eq!(result, expected, "Bytes {:?}", String::from_utf8_lossy(bytes));
+        }
+    }
+
+    fn build_zip(names: &[&str]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            for name in names {
+                zip.start_file(*name, options).unwrap();
+                zip.write_all(b"content").unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_detect_provider_tells_zip_formats_apart() {
+        let test_cases = vec![
+            (vec!["[Content_Types].xml", "word/document.xml"], ContentType::Document),
+            (vec!["[Content_Types].xml", "ppt/presentation.xml"], ContentType::Presentation),
+            (vec!["[Content_Types].xml", "xl/workbook.xml"], ContentType::Spreadsheet),
+            // An extensionless upload of an ordinary zip is an archive, not a document
+            (vec!["report.pdf", "notes/readme.md"], ContentType::Archive),
+        ];
+
+        for (names, expected) in test_cases {
+            let result = detect_provider(&build_zip(&names)).map(|(content_type, _)| content_type);
+            assert_eq!(result, Some(expected), "Zip with {:?}", names);
+        }
+    }
+}
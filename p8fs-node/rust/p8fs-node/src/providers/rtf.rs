@@ -0,0 +1,411 @@
+use crate::models::{ChunkStrategy, ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::document::DocumentProvider;
+use crate::providers::language::detect_language;
+use crate::providers::{ids, outcome, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use encoding_rs::{Encoding, WINDOWS_1252};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Destinations whose text is not part of the document body.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "listtable", "listoverridetable", "revtbl", "rsidtbl", "generator",
+    "pict", "object", "themedata", "colorschememapping", "latentstyles", "datastore", "xmlnstbl", "header",
+    "headerl", "headerr", "headerf", "footer", "footerl", "footerr", "footerf", "fldinst",
+];
+
+/// `\info` fields read into the metadata, with the key each is stored under.
+const INFO_FIELDS: &[(&str, &str)] = &[
+    ("title", "title"),
+    ("author", "author"),
+    ("subject", "subject"),
+    ("keywords", "keywords"),
+    ("doccomm", "description"),
+    ("company", "company"),
+];
+
+/// Extracts the text of RTF documents: control words and groups are
+/// stripped, `\par` becomes a paragraph break and `\uN` and `\'hh` escapes
+/// are decoded. Chunked like DOCX text, with the same strategy.
+pub struct RtfProvider {
+    chunker: DocumentProvider,
+}
+
+impl RtfProvider {
+    pub fn new() -> Self {
+        Self::with_strategy(ChunkStrategy::Fixed)
+    }
+
+    pub fn with_strategy(strategy: ChunkStrategy) -> Self {
+        Self { chunker: DocumentProvider::with_strategy(strategy) }
+    }
+
+    pub(crate) async fn chunk_document(&self, document: &RtfDocument, doc_hash: &str) -> anyhow::Result<Vec<ContentChunk>> {
+        let mut chunks: Vec<ContentChunk> = self
+            .chunker
+            .chunk_document(&document.text)
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(i, (range, content))| {
+                let mut metadata = HashMap::new();
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("rtf"));
+                metadata.insert("char_start".to_string(), serde_json::json!(range.start));
+                metadata.insert("char_end".to_string(), serde_json::json!(range.end));
+
+                ContentChunk {
+                    id: String::new(),
+                    content: content.trim().to_string(),
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("rtf", doc_hash, &mut chunks);
+        Ok(chunks)
+    }
+
+    fn metadata_from(&self, document: &RtfDocument, file_name: Option<String>, file_size: u64) -> ContentMetadata {
+        let mut additional = HashMap::new();
+        for (key, value) in &document.info {
+            if !matches!(*key, "title" | "author") {
+                additional.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
+
+        ContentMetadata {
+            content_type: ContentType::Document,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: document.info.get("author").cloned(),
+            title: document.info.get("title").cloned(),
+            language: detect_language(&document.text),
+            additional,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentProvider for RtfProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Document, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let bytes = tokio::fs::read(file_path).await?;
+        let document = parse(&bytes)?;
+        self.chunk_document(&document, &ids::doc_hash(&bytes)).await
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let bytes = tokio::fs::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        Ok(self.metadata_from(&parse(&bytes)?, file_name, bytes.len() as u64))
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let document = match parse(bytes) {
+            Ok(document) => document,
+            Err(err) => {
+                let metadata = outcome::basic_metadata(ContentType::Document, None, Some(bytes.len() as u64));
+                return outcome::failed(err, metadata).map(Some);
+            }
+        };
+
+        let metadata = self.metadata_from(&document, None, bytes.len() as u64);
+        match self.chunk_document(&document, &ids::doc_hash(bytes)).await {
+            Ok(chunks) => Ok(Some(ContentProcessingResult {
+                success: true,
+                status: ProcessingStatus::Processed,
+                chunks,
+                metadata,
+                error: None,
+            })),
+            Err(err) => outcome::failed(err, metadata).map(Some),
+        }
+    }
+
+    fn with_chunk_strategy(&self, strategy: ChunkStrategy) -> Option<Arc<dyn ContentProvider>> {
+        Some(Arc::new(RtfProvider::with_strategy(strategy)))
+    }
+}
+
+/// The body text and `\info` fields of an RTF document.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RtfDocument {
+    /// Paragraphs separated by blank lines.
+    pub text: String,
+    /// `\info` fields by the keys in `INFO_FIELDS`.
+    pub info: HashMap<&'static str, String>,
+}
+
+/// Where the text of the current group goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Destination {
+    Body,
+    /// The `\info` group itself, whose own text is ignored.
+    Info,
+    /// One `\info` field, stored under this key.
+    InfoField(&'static str),
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    destination: Destination,
+    /// Fallback characters that follow each `\uN`, set by `\ucN`.
+    fallback_chars: usize,
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    encoding: &'static Encoding,
+    group: Group,
+    stack: Vec<Group>,
+    /// `\'hh` bytes not yet decoded, so multi-byte code pages decode whole.
+    pending: Vec<u8>,
+    /// The high half of a surrogate pair written as two `\uN`.
+    high_surrogate: Option<u16>,
+    /// Fallback characters still to skip after a `\uN`.
+    skip: usize,
+    document: RtfDocument,
+}
+
+/// Reads an RTF document. Anything not starting with `{\rtf` is rejected.
+pub fn parse(bytes: &[u8]) -> anyhow::Result<RtfDocument> {
+    if !bytes.starts_with(b"{\\rtf") {
+        anyhow::bail!("Not an RTF document: missing {{\\rtf header");
+    }
+
+    let mut parser = Parser {
+        bytes,
+        pos: 0,
+        encoding: WINDOWS_1252,
+        group: Group { destination: Destination::Body, fallback_chars: 1 },
+        stack: Vec::new(),
+        pending: Vec::new(),
+        high_surrogate: None,
+        skip: 0,
+        document: RtfDocument::default(),
+    };
+    parser.run();
+    parser.flush();
+
+    let mut document = parser.document;
+    document.text = tidy(&document.text);
+    for value in document.info.values_mut() {
+        *value = value.trim().to_string();
+    }
+    document.info.retain(|_, value| !value.is_empty());
+    Ok(document)
+}
+
+impl Parser<'_> {
+    fn run(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            self.pos += 1;
+            match byte {
+                b'{' => {
+                    self.flush();
+                    self.stack.push(self.group);
+                    self.skip = 0;
+                }
+                b'}' => {
+                    self.flush();
+                    self.group = self.stack.pop().unwrap_or(self.group);
+                    self.skip = 0;
+                }
+                b'\\' => self.control(),
+                // Line breaks in the source are not part of the text
+                b'\r' | b'\n' => {}
+                _ if self.skip > 0 => self.skip -= 1,
+                // Raw 8-bit text is in the document's code page, like `\'hh`
+                _ if !byte.is_ascii() => self.pending.push(byte),
+                _ => {
+                    self.flush();
+                    self.push_char(byte as char);
+                }
+            }
+        }
+    }
+
+    /// Handles what follows a backslash: a control symbol or a control word
+    /// with its optional numeric parameter.
+    fn control(&mut self) {
+        let Some(&next) = self.bytes.get(self.pos) else { return };
+
+        if !next.is_ascii_alphabetic() {
+            self.pos += 1;
+            match next {
+                b'\'' => {
+                    let hex = self.bytes.get(self.pos..self.pos + 2).and_then(|hex| std::str::from_utf8(hex).ok());
+                    if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                        self.pos += 2;
+                        if self.skip > 0 {
+                            self.skip -= 1;
+                        } else {
+                            self.pending.push(byte);
+                        }
+                    }
+                }
+                b'\\' | b'{' | b'}' => self.literal(next as char),
+                // An escaped line break is a paragraph break
+                b'\r' | b'\n' => self.literal_str("\n\n"),
+                b'~' => self.literal('\u{a0}'),
+                b'_' => self.literal('\u{2011}'),
+                b'*' => self.group.destination = Destination::Skipped,
+                _ => {}
+            }
+            return;
+        }
+
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_alphabetic) {
+            self.pos += 1;
+        }
+        let word = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or_default().to_string();
+
+        let number_start = self.pos;
+        if self.bytes.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        let parameter: Option<i32> = std::str::from_utf8(&self.bytes[number_start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok());
+        // A single space ends the control word and is not part of the text
+        if self.bytes.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+
+        self.control_word(&word, parameter);
+    }
+
+    fn control_word(&mut self, word: &str, parameter: Option<i32>) {
+        if word != "u" {
+            self.skip = 0;
+        }
+
+        match word {
+            "par" | "sect" => self.literal_str("\n\n"),
+            "line" | "row" => self.literal('\n'),
+            "tab" | "cell" => self.literal('\t'),
+            "emdash" => self.literal('—'),
+            "endash" => self.literal('–'),
+            "bullet" => self.literal('•'),
+            "lquote" => self.literal('‘'),
+            "rquote" => self.literal('’'),
+            "ldblquote" => self.literal('“'),
+            "rdblquote" => self.literal('”'),
+            "u" => {
+                if let Some(code) = parameter {
+                    self.flush();
+                    // Negative values are code units above 0x7FFF
+                    self.push_code_unit(code.rem_euclid(0x10000) as u16);
+                    self.skip = self.group.fallback_chars;
+                }
+            }
+            "uc" => self.group.fallback_chars = parameter.unwrap_or(1).max(0) as usize,
+            "ansicpg" => {
+                let label = format!("windows-{}", parameter.unwrap_or_default());
+                self.encoding = Encoding::for_label(label.as_bytes()).unwrap_or(WINDOWS_1252);
+            }
+            "bin" => {
+                let length = parameter.unwrap_or_default().max(0) as usize;
+                self.pos = (self.pos + length).min(self.bytes.len());
+            }
+            "info" => self.group.destination = Destination::Info,
+            _ if SKIPPED_DESTINATIONS.contains(&word) => self.group.destination = Destination::Skipped,
+            _ if self.group.destination == Destination::Info => {
+                if let Some((_, key)) = INFO_FIELDS.iter().find(|(field, _)| *field == word) {
+                    self.group.destination = Destination::InfoField(key);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn literal(&mut self, c: char) {
+        self.flush();
+        self.push_char(c);
+    }
+
+    fn literal_str(&mut self, text: &str) {
+        self.flush();
+        for c in text.chars() {
+            self.push_char(c);
+        }
+    }
+
+    fn push_code_unit(&mut self, unit: u16) {
+        match (self.high_surrogate.take(), unit) {
+            (None, 0xD800..=0xDBFF) => self.high_surrogate = Some(unit),
+            (Some(high), 0xDC00..=0xDFFF) => {
+                let code = 0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(unit) - 0xDC00);
+                self.push_char(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            (_, unit) => self.push_char(char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER)),
+        }
+    }
+
+    /// Decodes any pending `\'hh` bytes into the current destination.
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let bytes = std::mem::take(&mut self.pending);
+        let (text, _) = self.encoding.decode_without_bom_handling(&bytes);
+        for c in text.chars() {
+            self.push_char(c);
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        match self.group.destination {
+            Destination::Body => self.document.text.push(c),
+            Destination::InfoField(key) => self.document.info.entry(key).or_default().push(c),
+            Destination::Info | Destination::Skipped => {}
+        }
+    }
+}
+
+/// Trims each line and collapses runs of blank lines into one.
+fn tidy(text: &str) -> String {
+    let mut tidied = String::new();
+    let mut blank_lines = 0;
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !tidied.is_empty() {
+            tidied.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        tidied.push_str(line);
+        blank_lines = 0;
+    }
+
+    tidied
+}
@@ -0,0 +1,429 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::{ids, outcome, text, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const IDENTIFIER: &str = r#"(?:[\w$]+|"[^"]+"|`[^`]+`|\[[^\]]+\])"#;
+
+static CREATE_OBJECT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?is)^CREATE\s+(?:OR\s+REPLACE\s+)?(?:(?:GLOBAL|LOCAL)\s+)?(?:(?:TEMP|TEMPORARY|UNLOGGED)\s+)?(TABLE|VIEW|FUNCTION)\s+(?:IF\s+NOT\s+EXISTS\s+)?({id}(?:\s*\.\s*{id})*)",
+        id = IDENTIFIER
+    ))
+    .unwrap()
+});
+
+static INSERT_INTO: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"(?is)^INSERT\s+INTO\s+({id}(?:\s*\.\s*{id})*)", id = IDENTIFIER)).unwrap()
+});
+
+/// Keywords that start a table-level constraint rather than a column definition.
+const TABLE_CONSTRAINTS: &[&str] = &["CONSTRAINT", "PRIMARY", "FOREIGN", "UNIQUE", "CHECK", "KEY", "INDEX", "EXCLUDE"];
+
+/// Keywords that end a column's type and start its constraints.
+const COLUMN_CONSTRAINTS: &[&str] = &[
+    "NOT", "NULL", "PRIMARY", "REFERENCES", "DEFAULT", "UNIQUE", "CHECK", "CONSTRAINT", "GENERATED",
+    "COLLATE", "AUTO_INCREMENT", "AUTOINCREMENT", "IDENTITY", "COMMENT",
+];
+
+/// A column of a `CREATE TABLE` statement.
+pub(crate) struct SqlColumn {
+    pub name: String,
+    pub data_type: String,
+    pub constraints: String,
+}
+
+/// A schema object found in a SQL file.
+pub(crate) enum SqlObject {
+    Table {
+        name: String,
+        columns: Vec<SqlColumn>,
+        constraints: Vec<String>,
+        statement: String,
+    },
+    View {
+        name: String,
+        statement: String,
+    },
+    Function {
+        name: String,
+        statement: String,
+    },
+}
+
+pub struct SqlProvider;
+
+impl SqlProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Splits a SQL script into statements on `;`, ignoring semicolons inside
+    /// quotes, comments and dollar-quoted bodies. Comments are dropped.
+    pub(crate) fn split_statements(&self, sql: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        let mut current = String::new();
+        let mut chars = sql.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '-' if sql[i..].starts_with("--") => {
+                    while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                }
+                '/' if sql[i..].starts_with("/*") => {
+                    chars.next();
+                    let mut previous = ' ';
+                    for (_, next) in chars.by_ref() {
+                        if previous == '*' && next == '/' {
+                            break;
+                        }
+                        previous = next;
+                    }
+                    current.push(' ');
+                }
+                '\'' | '"' | '`' => {
+                    current.push(c);
+                    for (_, next) in chars.by_ref() {
+                        current.push(next);
+                        if next == c {
+                            break;
+                        }
+                    }
+                }
+                '$' => {
+                    let tag_len = sql[i + 1..]
+                        .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                        .filter(|&end| sql[i + 1 + end..].starts_with('$'));
+                    match tag_len {
+                        Some(len) => {
+                            let tag = &sql[i..i + len + 2];
+                            let body_start = i + tag.len();
+                            let body_end = sql[body_start..]
+                                .find(tag)
+                                .map(|end| body_start + end + tag.len())
+                                .unwrap_or(sql.len());
+                            current.push_str(&sql[i..body_end]);
+                            while chars.next_if(|&(j, _)| j < body_end).is_some() {}
+                        }
+                        None => current.push(c),
+                    }
+                }
+                ';' => {
+                    if !current.trim().is_empty() {
+                        statements.push(current.trim().to_string());
+                    }
+                    current.clear();
+                }
+                _ => current.push(c),
+            }
+        }
+
+        if !current.trim().is_empty() {
+            statements.push(current.trim().to_string());
+        }
+
+        statements
+    }
+
+    /// Extracts tables, views and functions, plus the number of `INSERT`
+    /// statements per table.
+    pub(crate) fn parse_objects(&self, sql: &str) -> (Vec<SqlObject>, BTreeMap<String, usize>) {
+        let mut objects = Vec::new();
+        let mut inserts = BTreeMap::new();
+
+        for statement in self.split_statements(sql) {
+            if let Some(captures) = CREATE_OBJECT.captures(&statement) {
+                let name = normalize_name(&captures[2]);
+                let object = match captures[1].to_uppercase().as_str() {
+                    "TABLE" => {
+                        let (columns, constraints) = parse_table_body(&statement[captures[0].len()..]);
+                        SqlObject::Table { name, columns, constraints, statement }
+                    }
+                    "VIEW" => SqlObject::View { name, statement },
+                    _ => SqlObject::Function { name, statement },
+                };
+                objects.push(object);
+            } else if let Some(captures) = INSERT_INTO.captures(&statement) {
+                *inserts.entry(normalize_name(&captures[1])).or_insert(0) += 1;
+            }
+        }
+
+        (objects, inserts)
+    }
+
+    pub(crate) fn chunk_str(&self, content: &str) -> Vec<ContentChunk> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let (objects, inserts) = self.parse_objects(content);
+
+        let mut chunks: Vec<ContentChunk> = objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| {
+                let (object_type, object_name) = match object {
+                    SqlObject::Table { name, .. } => ("table", name),
+                    SqlObject::View { name, .. } => ("view", name),
+                    SqlObject::Function { name, .. } => ("function", name),
+                };
+
+                let mut metadata = HashMap::new();
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("sql"));
+                metadata.insert("object_type".to_string(), serde_json::json!(object_type));
+                metadata.insert("object_name".to_string(), serde_json::json!(object_name));
+                if let SqlObject::Table { columns, .. } = object {
+                    let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+                    metadata.insert("columns".to_string(), serde_json::json!(names));
+                }
+
+                ContentChunk {
+                    id: String::new(),
+                    content: render_object(object),
+                    metadata,
+                }
+            })
+            .collect();
+
+        // Row data isn't useful to embed; summarize it instead
+        if !inserts.is_empty() {
+            let i = chunks.len();
+            let mut content = "## Data\n\n| Table | INSERT statements |\n|-------|-------------------|\n".to_string();
+            for (table, count) in &inserts {
+                content.push_str(&format!("| {} | {} |\n", escape_cell(table), count));
+            }
+
+            let mut metadata = HashMap::new();
+            metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+            metadata.insert("source".to_string(), serde_json::json!("sql"));
+            metadata.insert("object_type".to_string(), serde_json::json!("insert_summary"));
+
+            chunks.push(ContentChunk {
+                id: String::new(),
+                content: content.trim_end().to_string(),
+                metadata,
+            });
+        }
+
+        ids::assign_chunk_ids("sql", &doc_hash, &mut chunks);
+        chunks
+    }
+
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> ContentMetadata {
+        let (objects, _) = self.parse_objects(content);
+
+        let (mut tables, mut views, mut functions) = (0, 0, 0);
+        for object in &objects {
+            match object {
+                SqlObject::Table { .. } => tables += 1,
+                SqlObject::View { .. } => views += 1,
+                SqlObject::Function { .. } => functions += 1,
+            }
+        }
+
+        let mut additional = HashMap::new();
+        additional.insert("table_count".to_string(), serde_json::json!(tables));
+        additional.insert("view_count".to_string(), serde_json::json!(views));
+        additional.insert("function_count".to_string(), serde_json::json!(functions));
+
+        ContentMetadata {
+            content_type: ContentType::Code,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional,
+        }
+    }
+}
+
+/// Strips identifier quoting and whitespace around `.` in a qualified name.
+fn normalize_name(name: &str) -> String {
+    name.split('.')
+        .map(|part| part.trim().trim_matches(|c| matches!(c, '"' | '`' | '[' | ']')))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Splits on commas that are not nested inside parentheses or quotes.
+fn split_top_level(body: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+
+    for c in body.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+
+    parts
+}
+
+/// Parses the parenthesized definition list following `CREATE TABLE name`.
+fn parse_table_body(rest: &str) -> (Vec<SqlColumn>, Vec<String>) {
+    let Some(open) = rest.find('(') else {
+        // e.g. CREATE TABLE ... AS SELECT
+        return (Vec::new(), Vec::new());
+    };
+    if !rest[..open].trim().is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut depth = 0;
+    let mut close = rest.len();
+    for (i, c) in rest[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = open + i;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut columns = Vec::new();
+    let mut constraints = Vec::new();
+
+    for definition in split_top_level(&rest[open + 1..close]) {
+        let definition = definition.split_whitespace().collect::<Vec<_>>().join(" ");
+        let first = definition.split(' ').next().unwrap_or_default().to_uppercase();
+
+        if TABLE_CONSTRAINTS.contains(&first.as_str()) {
+            constraints.push(definition);
+            continue;
+        }
+
+        // Quoted names may contain spaces
+        let name_end = match definition.chars().next() {
+            Some(quote @ ('"' | '`')) => definition[1..].find(quote).map(|end| end + 2),
+            Some('[') => definition.find(']').map(|end| end + 1),
+            _ => definition.find(' '),
+        }
+        .unwrap_or(definition.len());
+        let (name, remainder) = (&definition[..name_end], definition[name_end..].trim());
+
+        let words: Vec<&str> = remainder.split(' ').filter(|w| !w.is_empty()).collect();
+        let type_len = words
+            .iter()
+            .position(|w| COLUMN_CONSTRAINTS.contains(&w.to_uppercase().as_str()))
+            .unwrap_or(words.len());
+
+        columns.push(SqlColumn {
+            name: normalize_name(name),
+            data_type: words[..type_len].join(" "),
+            constraints: words[type_len..].join(" "),
+        });
+    }
+
+    (columns, constraints)
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn render_object(object: &SqlObject) -> String {
+    match object {
+        SqlObject::Table { name, columns, constraints, statement } => {
+            let mut markdown = format!("## Table `{}`\n\n", name);
+            if columns.is_empty() {
+                markdown.push_str(&format!("```sql\n{}\n```", statement));
+                return markdown;
+            }
+
+            markdown.push_str("| Column | Type | Constraints |\n|--------|------|-------------|\n");
+            for column in columns {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    escape_cell(&column.name),
+                    escape_cell(&column.data_type),
+                    escape_cell(&column.constraints)
+                ));
+            }
+
+            if !constraints.is_empty() {
+                markdown.push_str("\n**Constraints:**\n\n");
+                for constraint in constraints {
+                    markdown.push_str(&format!("- `{}`\n", constraint));
+                }
+            }
+
+            markdown.trim_end().to_string()
+        }
+        SqlObject::View { name, statement } => format!("## View `{}`\n\n```sql\n{}\n```", name, statement),
+        SqlObject::Function { name, statement } => format!("## Function `{}`\n\n```sql\n{}\n```", name, statement),
+    }
+}
+
+#[async_trait]
+impl ContentProvider for SqlProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Code, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        Ok(self.chunk_str(&content.text))
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.metadata_from_str(&content.text, file_name, file_metadata.len());
+        content.record(&mut metadata);
+        Ok(metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let mut metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64);
+        content.record(&mut metadata);
+
+        Ok(Some(ContentProcessingResult {
+            success: true,
+            status: ProcessingStatus::Processed,
+            chunks: self.chunk_str(&content.text),
+            metadata,
+            error: None,
+        }))
+    }
+}
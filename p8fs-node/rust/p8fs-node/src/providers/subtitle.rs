@@ -0,0 +1,258 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType};
+use crate::providers::limits::env_or;
+use crate::providers::{ids, outcome, text, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Default span of a chunk, in seconds.
+const DEFAULT_CHUNK_SECS: f64 = 60.0;
+
+/// One timed caption, with its times in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Chunks SubRip (`.srt`) and WebVTT (`.vtt`) subtitles. Consecutive cues
+/// are grouped until a chunk would span more than `chunk_secs`, and each
+/// chunk records the `start_time` and `end_time` of its cues in seconds so
+/// a search hit can link to that point of the video.
+pub struct SubtitleProvider {
+    chunk_secs: f64,
+}
+
+impl SubtitleProvider {
+    /// Groups cues into chunks of `P8FS_SUBTITLE_CHUNK_SECS` seconds (default 60).
+    pub fn new() -> Self {
+        Self::with_chunk_secs(env_or("P8FS_SUBTITLE_CHUNK_SECS", DEFAULT_CHUNK_SECS))
+    }
+
+    pub fn with_chunk_secs(chunk_secs: f64) -> Self {
+        Self {
+            chunk_secs: if chunk_secs > 0.0 { chunk_secs } else { DEFAULT_CHUNK_SECS },
+        }
+    }
+
+    /// The cues of an SRT or VTT file, in file order. The WebVTT header and
+    /// `NOTE`, `STYLE` and `REGION` blocks are skipped, as are blocks with no
+    /// valid timing line and cues with no text left once tags are stripped.
+    pub(crate) fn parse(&self, content: &str) -> anyhow::Result<Vec<Cue>> {
+        let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+
+        let cues: Vec<Cue> = content
+            .split("\n\n")
+            .filter_map(|block| {
+                let lines: Vec<&str> = block.lines().filter(|line| !line.trim().is_empty()).collect();
+                let first = lines.first()?.trim_start();
+                if ["WEBVTT", "NOTE", "STYLE", "REGION"].iter().any(|keyword| first.starts_with(keyword)) {
+                    return None;
+                }
+
+                // The timing line may follow an SRT index or a VTT cue identifier
+                let timing = lines.iter().position(|line| line.contains("-->"))?;
+                let (start_ms, end_ms) = parse_timing(lines[timing])?;
+                let text: Vec<String> = lines[timing + 1..]
+                    .iter()
+                    .map(|line| strip_tags(line))
+                    .filter(|line| !line.is_empty())
+                    .collect();
+
+                (!text.is_empty()).then(|| Cue {
+                    start_ms,
+                    end_ms,
+                    text: text.join(" "),
+                })
+            })
+            .collect();
+
+        if cues.is_empty() && !content.trim().is_empty() && !content.trim_start().starts_with("WEBVTT") {
+            anyhow::bail!("No subtitle cues found");
+        }
+        Ok(cues)
+    }
+
+    pub(crate) fn chunk_str(&self, content: &str) -> anyhow::Result<Vec<ContentChunk>> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let cues = self.parse(content)?;
+        let max_ms = (self.chunk_secs * 1000.0) as u64;
+
+        let mut groups: Vec<Vec<&Cue>> = Vec::new();
+        for cue in &cues {
+            match groups.last_mut() {
+                Some(group) if cue.end_ms.saturating_sub(group[0].start_ms) <= max_ms => group.push(cue),
+                _ => groups.push(vec![cue]),
+            }
+        }
+
+        let mut chunks: Vec<ContentChunk> = groups
+            .into_iter()
+            .enumerate()
+            .map(|(i, group)| {
+                let start_ms = group.iter().map(|cue| cue.start_ms).min().unwrap_or(0);
+                let end_ms = group.iter().map(|cue| cue.end_ms).max().unwrap_or(0);
+
+                let mut metadata = HashMap::new();
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("subtitle"));
+                metadata.insert("start_time".to_string(), serde_json::json!(seconds(start_ms)));
+                metadata.insert("end_time".to_string(), serde_json::json!(seconds(end_ms)));
+                metadata.insert("cue_count".to_string(), serde_json::json!(group.len()));
+
+                ContentChunk {
+                    id: String::new(),
+                    content: group.iter().map(|cue| cue.text.as_str()).collect::<Vec<_>>().join("\n"),
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("subtitle", &doc_hash, &mut chunks);
+        Ok(chunks)
+    }
+
+    /// Records the `format` (`srt` or `vtt`), `cue_count` and `duration` in
+    /// seconds, the end of the last cue.
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        let cues = self.parse(content)?;
+        let format = if content.trim_start_matches('\u{feff}').trim_start().starts_with("WEBVTT") {
+            "vtt"
+        } else {
+            "srt"
+        };
+
+        let mut additional = HashMap::new();
+        additional.insert("format".to_string(), serde_json::json!(format));
+        additional.insert("cue_count".to_string(), serde_json::json!(cues.len()));
+        additional.insert(
+            "duration".to_string(),
+            serde_json::json!(seconds(cues.iter().map(|cue| cue.end_ms).max().unwrap_or(0))),
+        );
+
+        Ok(ContentMetadata {
+            content_type: ContentType::Subtitle,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional,
+        })
+    }
+}
+
+fn seconds(ms: u64) -> f64 {
+    ms as f64 / 1000.0
+}
+
+/// Parses a timing line such as `00:01:02,500 --> 00:01:04,000` (SRT) or
+/// `01:02.500 --> 01:04.000 align:start` (VTT, where hours are optional and
+/// cue settings may follow).
+fn parse_timing(line: &str) -> Option<(u64, u64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// Parses `hh:mm:ss,mmm`, `hh:mm:ss.mmm` or `mm:ss.mmm` into milliseconds.
+pub(crate) fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (clock, fraction) = match timestamp.split_once([',', '.']) {
+        Some((clock, fraction)) => (clock, fraction),
+        None => (timestamp, ""),
+    };
+
+    let fields: Vec<u64> = clock.split(':').map(|field| field.parse().ok()).collect::<Option<_>>()?;
+    let (hours, minutes, secs) = match fields[..] {
+        [hours, minutes, secs] => (hours, minutes, secs),
+        [minutes, secs] => (0, minutes, secs),
+        _ => return None,
+    };
+    if minutes >= 60 || secs >= 60 || fraction.len() > 3 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // `5` after the separator is 500 ms, not 5
+    let millis = format!("{:0<3}", fraction).parse::<u64>().ok()?;
+    Some(((hours * 60 + minutes) * 60 + secs) * 1000 + millis)
+}
+
+/// Removes formatting from a caption line: HTML-style tags such as `<i>`,
+/// `<font color="red">`, VTT `<v Speaker>` and inline timestamps, SSA
+/// overrides such as `{\an8}`, and the entities VTT escapes text with.
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+
+    for c in line.chars() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, c) => text.push(c),
+            (Some(end), c) if c == end => closing = None,
+            (Some(_), _) => {}
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[async_trait]
+impl ContentProvider for SubtitleProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Subtitle, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        self.chunk_str(&content.text)
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.metadata_from_str(&content.text, file_name, file_metadata.len())?;
+        content.record(&mut metadata);
+        Ok(metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64).map(|mut metadata| {
+            content.record(&mut metadata);
+            metadata
+        });
+        let chunks = self.chunk_str(&content.text);
+
+        outcome::build(chunks, metadata, || {
+            let mut metadata = outcome::basic_metadata(ContentType::Subtitle, None, Some(bytes.len() as u64));
+            content.record(&mut metadata);
+            metadata
+        })
+        .map(Some)
+    }
+}
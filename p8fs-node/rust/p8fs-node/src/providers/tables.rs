@@ -0,0 +1,230 @@
+//! Markdown table rendering shared by the table-producing providers.
+//!
+//! A table with dozens of columns makes chunks too wide to embed usefully, so
+//! tables over `WideTables::max_columns` are cut into parts: column groups
+//! that each repeat the first (key) column, or one key-value list per row.
+
+use crate::providers::limits::env_or;
+use once_cell::sync::Lazy;
+use std::str::FromStr;
+
+static WIDE_TABLES: Lazy<WideTables> = Lazy::new(WideTables::from_env);
+
+/// How a table wider than the limit is cut up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WideTableMode {
+    /// Column groups, each a table that repeats the first column.
+    Split,
+    /// One `- **column**: value` list per row.
+    Transpose,
+}
+
+impl FromStr for WideTableMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "split" => Ok(Self::Split),
+            "transpose" => Ok(Self::Transpose),
+            other => Err(format!("Unknown wide table mode: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WideTables {
+    /// Most columns a table part may have.
+    pub max_columns: usize,
+    pub mode: WideTableMode,
+}
+
+/// One piece of a table: its markdown and, for a table that had to be cut,
+/// its 1-based part number and the table's part count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TablePart {
+    pub content: String,
+    pub part: Option<(usize, usize)>,
+}
+
+impl WideTables {
+    /// Reads `P8FS_TABLE_MAX_COLUMNS` and `P8FS_WIDE_TABLE_MODE`, keeping the
+    /// default for any that are unset or invalid.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            max_columns: env_or("P8FS_TABLE_MAX_COLUMNS", defaults.max_columns).max(2),
+            mode: env_or("P8FS_WIDE_TABLE_MODE", defaults.mode),
+        }
+    }
+
+    /// The settings read from the environment on first use.
+    pub fn global() -> WideTables {
+        *WIDE_TABLES
+    }
+
+    /// Renders `header` and `rows` (unescaped cell text) as one or more
+    /// markdown pieces. Rows shorter than the header are padded.
+    pub(crate) fn render(&self, header: &[String], rows: &[Vec<String>]) -> Vec<TablePart> {
+        if header.len() <= self.max_columns {
+            return vec![TablePart {
+                content: render_table(header, rows),
+                part: None,
+            }];
+        }
+
+        let pieces: Vec<String> = match self.mode {
+            WideTableMode::Split => {
+                // Every group repeats column 0 so its rows can still be told apart
+                let rest: Vec<usize> = (1..header.len()).collect();
+                rest.chunks(self.max_columns - 1)
+                    .map(|group| {
+                        let columns: Vec<usize> = std::iter::once(0).chain(group.iter().copied()).collect();
+                        let pick = |cells: &[String]| -> Vec<String> {
+                            columns.iter().map(|&i| cells.get(i).cloned().unwrap_or_default()).collect()
+                        };
+                        let rows: Vec<Vec<String>> = rows.iter().map(|row| pick(row)).collect();
+                        render_table(&pick(header), &rows)
+                    })
+                    .collect()
+            }
+            WideTableMode::Transpose => rows
+                .iter()
+                .map(|row| {
+                    let lines: Vec<String> = header
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| format!("- **{}**: {}", name, row.get(i).map_or("", String::as_str)))
+                        .collect();
+                    lines.join("\n")
+                })
+                .collect(),
+        };
+
+        let count = pieces.len();
+        pieces
+            .into_iter()
+            .enumerate()
+            .map(|(i, content)| TablePart {
+                content,
+                part: Some((i + 1, count)),
+            })
+            .collect()
+    }
+
+    /// Cuts markdown text into pieces at every table wider than the limit.
+    /// Text around such tables is kept as pieces of its own, without a
+    /// `part`; text with no wide tables comes back as a single piece.
+    pub(crate) fn split_markdown(&self, markdown: &str) -> Vec<TablePart> {
+        let lines: Vec<&str> = markdown.lines().collect();
+        let mut pieces = Vec::new();
+        let mut text: Vec<&str> = Vec::new();
+        let mut in_fence = false;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+
+            let table_end = (!in_fence && is_table_start(&lines[i..]))
+                .then(|| i + lines[i..].iter().take_while(|line| line.trim_start().starts_with('|')).count());
+            let wide = table_end.map(|end| (end, parse_row(line))).filter(|(_, header)| header.len() > self.max_columns);
+
+            match wide {
+                Some((end, header)) => {
+                    push_text(&mut pieces, &text);
+                    text.clear();
+                    let rows: Vec<Vec<String>> = lines[i + 2..end].iter().map(|line| parse_row(line)).collect();
+                    pieces.extend(self.render(&header, &rows));
+                    i = end;
+                }
+                None => {
+                    text.push(line);
+                    i += 1;
+                }
+            }
+        }
+        push_text(&mut pieces, &text);
+
+        if pieces.iter().all(|piece| piece.part.is_none()) {
+            return vec![TablePart {
+                content: markdown.to_string(),
+                part: None,
+            }];
+        }
+        pieces
+    }
+}
+
+impl Default for WideTables {
+    fn default() -> Self {
+        Self {
+            max_columns: 20,
+            mode: WideTableMode::Split,
+        }
+    }
+}
+
+fn push_text(pieces: &mut Vec<TablePart>, lines: &[&str]) {
+    let text = lines.join("\n");
+    if !text.trim().is_empty() {
+        pieces.push(TablePart {
+            content: text.trim().to_string(),
+            part: None,
+        });
+    }
+}
+
+/// A pipe table starts with a row followed by a `| --- |` delimiter row.
+fn is_table_start(lines: &[&str]) -> bool {
+    let [header, delimiter, ..] = lines else {
+        return false;
+    };
+    let cells = parse_row(delimiter);
+
+    header.trim_start().starts_with('|')
+        && !cells.is_empty()
+        && cells
+            .iter()
+            .all(|cell| cell.contains('-') && cell.chars().all(|c| matches!(c, '-' | ':' | ' ')))
+}
+
+/// The cells of a `| a | b |` row, trimmed and with `\|` unescaped.
+fn parse_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').filter(|rest| !rest.ends_with('\\')).unwrap_or(line);
+
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cells.last_mut().unwrap().push('|');
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+
+    cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// A markdown pipe table with `header` as its first row. Pipes in cells are
+/// escaped and newlines flattened.
+pub(crate) fn render_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let width = header.len();
+    let row = |cells: &[String]| {
+        let cells: Vec<String> = (0..width)
+            .map(|i| cells.get(i).map_or(String::new(), |cell| cell.replace('|', "\\|").replace('\n', " ")))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![row(header), format!("|{}", " --- |".repeat(width))];
+    lines.extend(rows.iter().map(|cells| row(cells)));
+    lines.join("\n")
+}
@@ -8,7 +8,94 @@ mod tests {
 
     mod json_provider_tests {
         use super::*;
-        use super::super::json::JsonProvider;
+        use crate::providers::json::JsonProvider;
+        use crate::providers::limits::{LimitExceeded, ResourceLimits};
+
+        #[test]
+        fn test_json_depth_limit_truncates() {
+            let provider = JsonProvider::with_limits(ResourceLimits { max_depth: 8, ..ResourceLimits::default() });
+            let deep = format!(r#"{{"kind": "Deep", "value": {}1{}}}"#, "[".repeat(10), "]".repeat(10));
+
+            let chunks = provider.chunk_str(&deep).unwrap();
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(chunks[0].metadata["truncated_depth"], true);
+            assert!(chunks[0].content.contains("nested too deep"));
+            assert!(!chunks[0].content.contains(": 1"));
+
+            let shallow = format!("{}1{}", "[".repeat(8), "]".repeat(8));
+            let chunks = provider.chunk_str(&shallow).unwrap();
+            assert!(chunks.iter().all(|chunk| !chunk.metadata.contains_key("truncated_depth")));
+        }
+
+        #[test]
+        fn test_json_extreme_nesting_does_not_overflow() {
+            let provider = JsonProvider::with_limits(ResourceLimits::default());
+            let deep = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+
+            let chunks = provider.chunk_str(&deep).unwrap();
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(chunks[0].metadata["truncated_depth"], true);
+
+            // Still has to be well-formed past the cut-off point
+            let unbalanced = format!("{}{}", "[".repeat(10_000), "]".repeat(9_999));
+            assert!(provider.chunk_str(&unbalanced).is_err());
+        }
+
+        #[test]
+        fn test_json_entry_and_size_limits_abort() {
+            let provider = JsonProvider::with_limits(ResourceLimits { max_entries: 3, ..ResourceLimits::default() });
+            let err = provider.chunk_str("[1, 2, 3]").unwrap_err();
+            assert_eq!(err.downcast_ref::<LimitExceeded>(), Some(&LimitExceeded::Entries(3)));
+
+            let provider = JsonProvider::with_limits(ResourceLimits { max_total_bytes: 4, ..ResourceLimits::default() });
+            let err = provider.chunk_str("[1, 2]").unwrap_err();
+            assert_eq!(err.downcast_ref::<LimitExceeded>(), Some(&LimitExceeded::TotalBytes(4)));
+        }
+
+        #[test]
+        fn test_json_keys_keep_source_order() {
+            let provider = JsonProvider::with_limits(ResourceLimits::default());
+            let chunks = provider.chunk_str(r#"{"kind": "Record", "z": 1, "m": 2, "a": 3}"#).unwrap();
+
+            let content = &chunks[0].content;
+            let (z, m, a) = (content.find("**z**").unwrap(), content.find("**m**").unwrap(), content.find("**a**").unwrap());
+            assert!(z < m && m < a, "{}", content);
+        }
+
+        #[test]
+        fn test_json_large_array_chunks_in_order() {
+            let provider = JsonProvider::with_limits(ResourceLimits::default());
+            let records: Vec<serde_json::Value> = (0..10_000)
+                .map(|i| serde_json::json!({ "kind": "Record", "id": i, "tags": { "even": i % 2 == 0 } }))
+                .collect();
+            let content = serde_json::to_string(&serde_json::json!({ "records": records })).unwrap();
+
+            let chunks = provider.chunk_str(&content).unwrap();
+
+            assert_eq!(chunks.len(), 10_000);
+            for (i, chunk) in chunks.iter().enumerate() {
+                assert_eq!(chunk.metadata["path"], format!("records[{}]", i));
+                assert_eq!(chunk.metadata["chunk_index"], i);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_json_process_bytes_matches_file() {
+            let provider = JsonProvider::new();
+            let test_content = r#"{"kind": "Config", "name": "bytes"}"#;
+            let test_path = "/tmp/test_json_process_bytes.json";
+
+            fs::write(test_path, test_content).await.unwrap();
+            let from_file = provider.process_content(Path::new(test_path)).await.unwrap();
+            fs::remove_file(test_path).await.ok();
+
+            let from_bytes = provider.process_bytes(test_content.as_bytes()).await.unwrap().unwrap();
+
+            let ids = |result: &ContentProcessingResult| result.chunks.iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+            assert_eq!(ids(&from_bytes), ids(&from_file));
+            assert_eq!(from_bytes.chunks[0].content, from_file.chunks[0].content);
+            assert_eq!(from_bytes.metadata.file_size, from_file.metadata.file_size);
+        }
 
         #[tokio::test]
         async fn test_json_provider_simple() {
@@ -29,6 +116,27 @@ mod tests {
             fs::remove_file(test_path).await.ok();
         }
 
+        #[tokio::test]
+        async fn test_json_provider_reports_invalid_json_in_result() {
+            let provider = JsonProvider::new();
+            let test_path = "/tmp/test_json_invalid.json";
+
+            fs::write(test_path, "{not json").await.unwrap();
+            let result = provider.process_content(Path::new(test_path)).await.unwrap();
+            fs::remove_file(test_path).await.ok();
+
+            assert!(!result.success);
+            assert_eq!(result.status, ProcessingStatus::Failed);
+            assert!(result.error.as_deref().is_some_and(|error| !error.is_empty()));
+            assert!(result.chunks.is_empty());
+            assert_eq!(result.metadata.content_type, ContentType::StructuredData);
+            assert_eq!(result.metadata.file_size, Some(9));
+
+            let from_bytes = provider.process_bytes(b"{not json").await.unwrap().unwrap();
+            assert!(!from_bytes.success);
+            assert_eq!(from_bytes.error, result.error);
+        }
+
         #[tokio::test]
         async fn test_json_provider_with_kind() {
             let provider = JsonProvider::new();
@@ -50,6 +158,101 @@ mod tests {
             fs::remove_file(test_path).await.ok();
         }
 
+        #[test]
+        fn test_json_list_chunks_each_item_once() {
+            let provider = JsonProvider::new();
+            let pod = |name: &str| serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "Pod",
+                "metadata": { "name": name },
+                "spec": { "containers": [{ "name": "app", "image": "nginx" }] }
+            });
+            let list = serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "List",
+                "metadata": { "resourceVersion": "" },
+                "items": [pod("web-0"), pod("web-1"), pod("web-2")]
+            });
+
+            let chunks = provider.chunk_str(&list.to_string()).unwrap();
+
+            assert_eq!(chunks.len(), 3);
+            for (i, chunk) in chunks.iter().enumerate() {
+                assert_eq!(chunk.metadata["kind"], "Pod");
+                assert_eq!(chunk.metadata["parent_kind"], "List");
+                assert_eq!(chunk.metadata["path"], format!("items[{}]", i));
+                assert!(chunk.content.contains(&format!("web-{}", i)));
+            }
+        }
+
+        #[test]
+        fn test_json_group_by_kind() {
+            let provider = JsonProvider::new().group_by_kind(true);
+            let document = serde_json::json!([
+                { "kind": "Service", "metadata": { "name": "web" } },
+                { "kind": "Deployment", "metadata": { "name": "web" } },
+                { "kind": "Service", "metadata": { "name": "db" } },
+                { "note": "unmanaged" },
+                { "kind": "Deployment", "metadata": { "name": "db" } }
+            ]);
+
+            let chunks = provider.chunk_str(&document.to_string()).unwrap();
+
+            let groups: Vec<_> = chunks
+                .iter()
+                .map(|c| (c.metadata["kind_group"].clone(), c.metadata["group_position"].clone()))
+                .collect();
+            assert_eq!(groups, vec![
+                (serde_json::json!("Service"), serde_json::json!(0)),
+                (serde_json::json!("Service"), serde_json::json!(1)),
+                (serde_json::json!("Deployment"), serde_json::json!(0)),
+                (serde_json::json!("Deployment"), serde_json::json!(1)),
+                (serde_json::Value::Null, serde_json::json!(0)),
+            ]);
+            assert_eq!(chunks[0].metadata["path"], "[0]");
+            assert_eq!(chunks[1].metadata["path"], "[2]");
+            assert!(chunks.iter().take(4).all(|c| c.metadata["group_size"] == 2));
+            assert!(chunks.iter().enumerate().all(|(i, c)| c.metadata["chunk_index"] == i));
+        }
+
+        #[test]
+        fn test_json_kind_summaries() {
+            let provider = JsonProvider::new().group_by_kind(true).kind_summaries(true);
+            let document = serde_json::json!({
+                "a": { "kind": "Service" },
+                "b": { "kind": "Deployment" },
+                "c": { "kind": "Service" }
+            });
+
+            let chunks = provider.chunk_str(&document.to_string()).unwrap();
+
+            assert_eq!(chunks.len(), 5);
+            assert_eq!(chunks[0].metadata["summary"], true);
+            assert_eq!(chunks[0].content, "## Service (2)\n- a\n- c");
+            assert_eq!(chunks[3].metadata["summary"], true);
+            assert_eq!(chunks[3].metadata["kind_group"], "Deployment");
+        }
+
+        #[test]
+        fn test_json_to_markdown_scalars() {
+            let provider = JsonProvider::new();
+            let value = serde_json::json!({
+                "description": "hello",
+                "columns": "a | b",
+                "count": 3,
+                "enabled": true,
+                "owner": null
+            });
+
+            let markdown = provider.json_to_markdown(&value, 0);
+            assert!(markdown.contains("- **description**: hello\n"));
+            assert!(markdown.contains("- **columns**: a \\| b"));
+            assert!(markdown.contains("- **count**: 3"));
+            assert!(markdown.contains("- **enabled**: true"));
+            assert!(markdown.contains("- **owner**: null"));
+            assert!(!markdown.contains('"'));
+        }
+
         #[test]
         fn test_json_to_markdown() {
             let provider = JsonProvider::new();
@@ -70,7 +273,7 @@ mod tests {
 
     mod markdown_provider_tests {
         use super::*;
-        use super::super::markdown::MarkdownProvider;
+        use crate::providers::markdown::MarkdownProvider;
 
         #[tokio::test]
         async fn test_markdown_provider_sections() {
@@ -123,89 +326,2178 @@ Nested content."#;
             assert_eq!(sections[1].0, "Section");
             assert_eq!(sections[1].2, 2); // heading level
         }
+
+        #[tokio::test]
+        async fn test_markdown_task_list_states() {
+            let provider = MarkdownProvider::new();
+            let test_content = "# Release\n\n- [x] Tag the build\n- [ ] Publish notes\n- Plain item\n";
+            let test_path = "/tmp/test_markdown_tasks.md";
+
+            fs::write(test_path, test_content).await.unwrap();
+
+            let chunks = provider.to_markdown_chunks(Path::new(test_path)).await.unwrap();
+            fs::remove_file(test_path).await.ok();
+
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(
+                chunks[0].metadata["tasks"],
+                serde_json::json!([
+                    { "text": "Tag the build", "checked": true },
+                    { "text": "Publish notes", "checked": false },
+                ])
+            );
+            assert!(chunks[0].content.contains("- [x] Tag the build\n- [ ] Publish notes\n- Plain item"));
+        }
+
+        #[test]
+        fn test_markdown_heading_inside_task_item() {
+            let provider = MarkdownProvider::new();
+            provider.extract_sections("- [ ] task\n  # Heading\n");
+
+            let sections = provider.extract_sections("# Plan\n\n- [ ] Draft the outline\n  # Notes\n  Keep it short.\n");
+
+            assert_eq!(sections.len(), 2);
+            assert_eq!(sections[0].3, vec![("Draft the outline".to_string(), false)]);
+            assert_eq!(sections[1].0, "Notes");
+            assert!(sections[1].3.is_empty());
+        }
+
+        #[test]
+        fn test_markdown_heading_appears_once() {
+            let provider = MarkdownProvider::new();
+            let markdown = "# Intro\n\nWelcome.\n\n## Using `p8fs` today\n\nRun it.";
+
+            let chunks = provider.chunk_str(markdown);
+
+            assert_eq!(chunks.len(), 2);
+            assert_eq!(chunks[0].content, "# Intro\n\nWelcome.");
+            assert_eq!(chunks[1].content.matches("Using p8fs today").count(), 1);
+            assert_eq!(chunks[1].metadata["section_title"], "Using p8fs today");
+            assert_eq!(chunks[1].content, "## Using p8fs today\n\nRun it.");
+        }
+
+        #[test]
+        fn test_markdown_lists_tables_and_quotes() {
+            let provider = MarkdownProvider::new();
+            let markdown = "# Setup\n\n- Install\n- Configure\n- Run\n\n| Name | Port |\n|:-----|-----:|\n| api | 80 |\n| a\\|b | 81 |\n\n> Back up first.\n>\n> Then upgrade.\n\nDone.";
+
+            let sections = provider.extract_sections(markdown);
+
+            assert_eq!(sections.len(), 1);
+            let content = &sections[0].1;
+            assert!(content.contains("- Install\n- Configure\n- Run\n"));
+            assert!(content.contains("| Name | Port |\n| :--- | ---: |\n| api | 80 |\n| a\\|b | 81 |\n"));
+            assert!(content.contains("> Back up first.\n>\n> Then upgrade.\n\nDone."));
+        }
+
+        #[test]
+        fn test_markdown_definition_list_pairs() {
+            let provider = MarkdownProvider::new();
+            let markdown = "# Glossary\n\nChunk\n: A slice of a document\n: Sized for embedding\n\nA normal\nparagraph.";
+
+            let sections = provider.extract_sections(markdown);
+
+            assert_eq!(sections.len(), 1);
+            assert!(sections[0].1.contains("Chunk: A slice of a document\nChunk: Sized for embedding"));
+            assert!(sections[0].1.contains("A normal paragraph."));
+            assert!(sections[0].3.is_empty());
+        }
+
+        /// A section with a 50-column, 3-row table between two paragraphs.
+        fn wide_table_markdown() -> String {
+            let header: Vec<String> = (1..=50).map(|i| format!("c{}", i)).collect();
+            let rows: Vec<String> = (1..=3)
+                .map(|row| {
+                    let cells: Vec<String> = (1..=50).map(|i| format!("r{}v{}", row, i)).collect();
+                    format!("| {} |", cells.join(" | "))
+                })
+                .collect();
+
+            format!(
+                "# Metrics\n\nBefore the table.\n\n| {} |\n|{}\n{}\n\nAfter the table.",
+                header.join(" | "),
+                " --- |".repeat(50),
+                rows.join("\n")
+            )
+        }
+
+        #[test]
+        fn test_markdown_wide_table_is_split_by_columns() {
+            use crate::providers::tables::{WideTableMode, WideTables};
+
+            let provider = MarkdownProvider::new().wide_tables(WideTables {
+                max_columns: 20,
+                mode: WideTableMode::Split,
+            });
+            let chunks = provider.chunk_str(&wide_table_markdown());
+
+            // Text before, three column groups (c1 plus 19 others each), text after
+            assert_eq!(chunks.len(), 5);
+            assert_eq!(chunks[0].content, "# Metrics\n\nBefore the table.");
+            assert_eq!(chunks[4].content, "# Metrics\n\nAfter the table.");
+
+            let parts = &chunks[1..4];
+            for (i, chunk) in parts.iter().enumerate() {
+                assert_eq!(chunk.metadata["table_part"], i + 1);
+                assert_eq!(chunk.metadata["table_parts"], 3);
+                assert_eq!(chunk.metadata["section_title"], "Metrics");
+
+                let lines: Vec<&str> = chunk.content.lines().collect();
+                assert_eq!(lines.len(), 2 + 2 + 3, "heading, blank, header, delimiter, rows");
+                let columns = lines[2].matches(" | ").count() + 1;
+                assert!(columns <= 20, "part {} has {} columns", i + 1, columns);
+                assert!(lines[2].starts_with("| c1 |"));
+                assert!(lines[4].starts_with("| r1v1 |"));
+            }
+            assert!(parts[0].content.contains("| c19 |") && parts[0].content.ends_with("r3v20 |"));
+            assert!(parts[2].content.contains("| c50 |") && parts[2].content.ends_with("r3v50 |"));
+            assert!(!chunks[0].metadata.contains_key("table_part"));
+        }
+
+        #[test]
+        fn test_markdown_wide_table_transposed_per_row() {
+            use crate::providers::tables::{WideTableMode, WideTables};
+
+            let provider = MarkdownProvider::new().wide_tables(WideTables {
+                max_columns: 20,
+                mode: WideTableMode::Transpose,
+            });
+            let chunks = provider.chunk_str(&wide_table_markdown());
+
+            assert_eq!(chunks.len(), 5);
+            assert!(chunks[2].content.starts_with("# Metrics\n\n- **c1**: r2v1\n- **c2**: r2v2\n"));
+            assert!(chunks[2].content.ends_with("- **c50**: r2v50"));
+            assert_eq!(chunks[2].metadata["table_part"], 2);
+
+            // Narrow tables are left alone
+            let narrow = provider.chunk_str("# Setup\n\n| Name | Port |\n| --- | --- |\n| api | 80 |");
+            assert_eq!(narrow.len(), 1);
+            assert!(!narrow[0].metadata.contains_key("table_part"));
+        }
     }
 
-    mod pdf_provider_tests {
-        use super::*;
-        use super::super::pdf::PdfProvider;
+    mod document_provider_tests {
+        use crate::providers::document::{read_docx_properties, DocumentProvider};
+        use crate::providers::ContentProvider;
+        use docx_rs::{Docx, Paragraph, Run, Table, TableCell, TableRow, VMergeType};
+        use std::io::Write;
+
+        fn cell(text: &str) -> TableCell {
+            TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(text)))
+        }
+
+        #[tokio::test]
+        async fn test_docx_table_cells_are_extracted() {
+            let table = Table::new(vec![
+                TableRow::new(vec![cell("Party"), cell("Amount")]),
+                TableRow::new(vec![cell("Acme Corp"), cell("$12,500")]),
+                TableRow::new(vec![cell("Globex"), cell("$3,000")]),
+            ]);
+            let docx = Docx::new()
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Payment schedule")))
+                .add_table(table);
+
+            let file = tempfile::Builder::new().suffix(".docx").tempfile().unwrap();
+            docx.build().pack(file.reopen().unwrap()).unwrap();
+
+            let chunks = DocumentProvider::new().to_markdown_chunks(file.path()).await.unwrap();
+            let content = &chunks[0].content;
+
+            assert!(content.contains("Payment schedule"));
+            assert!(content.contains("| Party     | Amount  |"));
+            assert!(content.contains("| --------- | ------- |"));
+            assert!(content.contains("| Acme Corp | $12,500 |"));
+            assert!(content.contains("| Globex    | $3,000  |"));
+            assert!(!content.contains("Table data extracted"));
+        }
+
+        #[tokio::test]
+        async fn test_docx_chunk_offsets_overlap_by_configured_amount() {
+            let docx = Docx::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("word ".repeat(600))));
+            let file = tempfile::Builder::new().suffix(".docx").tempfile().unwrap();
+            docx.build().pack(file.reopen().unwrap()).unwrap();
+
+            let chunks = DocumentProvider::new().to_markdown_chunks(file.path()).await.unwrap();
+            let offsets: Vec<(u64, u64)> = chunks
+                .iter()
+                .map(|c| (c.metadata["char_start"].as_u64().unwrap(), c.metadata["char_end"].as_u64().unwrap()))
+                .collect();
+
+            assert!(offsets.len() > 2);
+            assert_eq!(offsets[0], (0, 1000));
+            for pair in offsets.windows(2) {
+                assert_eq!(pair[0].1 - pair[1].0, 200);
+            }
+        }
 
         #[test]
-        fn test_pdf_chunk_text() {
-            let provider = PdfProvider::new();
-            let text = "a".repeat(2500); // Long text
-            
-            let chunks = provider.chunk_text(&text, 1000, 200);
-            
-            assert!(chunks.len() > 2);
-            assert_eq!(chunks[0].len(), 1000);
-            
-            // Check overlap
-            let overlap_start = &chunks[0][800..];
-            let next_start = &chunks[1][..200];
-            assert_eq!(overlap_start, next_start);
+        fn test_render_table_merged_cells() {
+            let table = Table::new(vec![
+                TableRow::new(vec![cell("Region"), cell("Q1 and Q2").grid_span(2)]),
+                TableRow::new(vec![cell("North").vertical_merge(VMergeType::Restart), cell("10"), cell("a|b")]),
+                TableRow::new(vec![TableCell::new().vertical_merge(VMergeType::Continue), cell("30")]),
+            ]);
+
+            let rendered = DocumentProvider::new().render_table(&table);
+
+            assert_eq!(
+                rendered,
+                "| Region | Q1 and Q2 |      |\n\
+                 | ------ | --------- | ---- |\n\
+                 | North  | 10        | a\\|b |\n\
+                 | North  | 30        |      |"
+            );
+        }
+
+        /// Builds a minimal package holding only the given core and app properties parts.
+        fn docx_package(core: &str, app: &str) -> Vec<u8> {
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            {
+                let mut zip = zip::ZipWriter::new(&mut buffer);
+                let options = zip::write::SimpleFileOptions::default();
+                zip.start_file("docProps/core.xml", options).unwrap();
+                zip.write_all(core.as_bytes()).unwrap();
+                zip.start_file("docProps/app.xml", options).unwrap();
+                zip.write_all(app.as_bytes()).unwrap();
+                zip.finish().unwrap();
+            }
+            buffer.into_inner()
+        }
+
+        #[test]
+        fn test_read_docx_properties() {
+            let package = docx_package(r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <dc:title>Design &amp; Notes</dc:title>
+  <dc:creator>Jane Doe</dc:creator>
+  <cp:keywords/>
+</cp:coreProperties>"#, r#"<?xml version="1.0" encoding="UTF-8"?>
+<Properties xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">
+  <Application>Microsoft Office Word</Application>
+  <Pages>3</Pages>
+  <TitlesOfParts><vt:vector size="1" baseType="lpstr"><vt:lpstr>Design</vt:lpstr></vt:vector></TitlesOfParts>
+</Properties>"#);
+
+            let properties = read_docx_properties(&package).unwrap();
+
+            assert_eq!(properties["core"]["dc:title"], "Design & Notes");
+            assert_eq!(properties["core"]["dc:creator"], "Jane Doe");
+            assert_eq!(properties["core"]["cp:keywords"], "");
+            assert_eq!(properties["app"]["Application"], "Microsoft Office Word");
+            assert_eq!(properties["app"]["Pages"], "3");
+            assert_eq!(properties["app"]["TitlesOfParts"], serde_json::json!(["Design"]));
+        }
+
+        #[tokio::test]
+        async fn test_docx_core_properties_fill_metadata() {
+            let package = docx_package(r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <dc:title>Supply Agreement</dc:title>
+  <dc:creator>Jane Doe</dc:creator>
+  <dc:language>en-GB</dc:language>
+  <cp:keywords>contract, supply</cp:keywords>
+  <cp:revision>7</cp:revision>
+  <cp:category/>
+  <dcterms:created xsi:type="dcterms:W3CDTF">2024-03-01T09:30:00Z</dcterms:created>
+  <dcterms:modified xsi:type="dcterms:W3CDTF">2024-04-12T16:05:00Z</dcterms:modified>
+</cp:coreProperties>"#, r#"<?xml version="1.0" encoding="UTF-8"?>
+<Properties><Company>Acme Corp</Company></Properties>"#);
+
+            let file = tempfile::Builder::new().suffix(".docx").tempfile().unwrap();
+            std::fs::write(file.path(), package).unwrap();
+
+            let metadata = DocumentProvider::new().to_metadata(file.path()).await.unwrap();
+
+            assert_eq!(metadata.title.as_deref(), Some("Supply Agreement"));
+            assert_eq!(metadata.author.as_deref(), Some("Jane Doe"));
+            assert_eq!(metadata.language.as_deref(), Some("en-GB"));
+            assert_eq!(metadata.created_at.as_deref(), Some("2024-03-01T09:30:00Z"));
+            assert_eq!(metadata.modified_at.as_deref(), Some("2024-04-12T16:05:00Z"));
+            assert_eq!(metadata.additional["keywords"], "contract, supply");
+            assert_eq!(metadata.additional["revision"], "7");
+            assert_eq!(metadata.additional["company"], "Acme Corp");
+            assert!(!metadata.additional.contains_key("category"));
         }
     }
 
-    mod audio_provider_tests {
-        use super::*;
-        use super::super::audio::AudioProvider;
+    mod fixed_width_provider_tests {
+        use crate::models::FixedWidthColumn;
+        use crate::providers::fixed_width::FixedWidthProvider;
+
+        fn columns() -> Vec<FixedWidthColumn> {
+            [("id", 6), ("name", 12), ("city", 10), ("balance", 9)]
+                .into_iter()
+                .map(|(name, width)| FixedWidthColumn { name: name.to_string(), width })
+                .collect()
+        }
+
+        const EXPORT: &str = "\
+000042Jane Doe    London      1250.00
+000043Li Wei      Shenzhen   -300.50
+
+000044Ana|Maria   Lisbon
+";
 
         #[test]
-        fn test_audio_segment_calculation() {
-            let provider = AudioProvider::new();
-            let samples = vec![0i16; 44100 * 60]; // 60 seconds at 44.1kHz
-            
-            let segments = provider.segment_audio(&samples, 44100, 30.0);
-            
-            assert_eq!(segments.len(), 2); // Two 30-second segments
-            assert_eq!(segments[0].0, 0);
-            assert_eq!(segments[0].1, 44100 * 30);
-            assert_eq!(segments[1].0, 44100 * 30);
-            assert_eq!(segments[1].1, 44100 * 60);
+        fn test_fixed_width_fields_are_extracted() {
+            let provider = FixedWidthProvider::with_columns(columns());
+
+            assert_eq!(provider.parse_line("000042Jane Doe    London      1250.00"), vec!["000042", "Jane Doe", "London", "1250.00"]);
+            // Short lines leave the trailing fields empty
+            assert_eq!(provider.parse_line("000044Ana"), vec!["000044", "Ana", "", ""]);
+        }
+
+        #[test]
+        fn test_fixed_width_renders_markdown_table() {
+            let chunks = FixedWidthProvider::with_columns(columns()).chunk_str(EXPORT);
+
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(
+                chunks[0].content,
+                "| id | name | city | balance |\n\
+                 | --- | --- | --- | --- |\n\
+                 | 000042 | Jane Doe | London | 1250.00 |\n\
+                 | 000043 | Li Wei | Shenzhen | -300.50 |\n\
+                 | 000044 | Ana\\|Maria | Lisbon |  |"
+            );
+            assert_eq!(chunks[0].metadata["line_range"], serde_json::json!([1, 4]));
+            assert_eq!(chunks[0].metadata["columns"], serde_json::json!(["id", "name", "city", "balance"]));
+        }
+
+        #[test]
+        fn test_fixed_width_without_spec_is_plain_text() {
+            let chunks = FixedWidthProvider::new().chunk_str(EXPORT);
+
+            assert!(chunks[0].content.starts_with("```text\n000042Jane Doe    London"));
+            assert!(!chunks[0].metadata.contains_key("columns"));
         }
     }
 
-    #[async_trait]
-    impl ContentProvider for MockProvider {
-        async fn process_content(&self, _file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
-            Ok(ContentProcessingResult {
-                success: true,
-                chunks: vec![],
-                metadata: ContentMetadata {
-                    content_type: ContentType::Unknown,
-                    file_name: None,
-                    file_size: None,
-                    created_at: None,
-                    modified_at: None,
-                    author: None,
-                    title: None,
-                    language: None,
-                    additional: HashMap::new(),
-                },
-                error: None,
-            })
+    mod pptx_provider_tests {
+        use crate::providers::pptx::PptxProvider;
+        use crate::providers::registry::detect_provider;
+        use crate::models::ContentType;
+        use std::io::Write;
+
+        const NS: &str = r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main""#;
+
+        /// A shape holding `paragraphs`, as a placeholder of `placeholder` type if given.
+        fn shape(placeholder: Option<&str>, paragraphs: &[&str]) -> String {
+            let ph = match placeholder {
+                Some(kind) => format!(r#"<p:ph type="{}"/>"#, kind),
+                None => String::new(),
+            };
+            let paragraphs: String = paragraphs
+                .iter()
+                .map(|text| format!("<a:p><a:r><a:t>{}</a:t></a:r></a:p>", text))
+                .collect();
+            format!(
+                "<p:sp><p:nvSpPr><p:cNvPr id=\"1\" name=\"Shape\"/><p:cNvSpPr/><p:nvPr>{}</p:nvPr></p:nvSpPr><p:txBody><a:bodyPr/>{}</p:txBody></p:sp>",
+                ph, paragraphs
+            )
         }
 
-        async fn to_markdown_chunks(&self, _file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
-            Ok(vec![])
+        fn slide(shapes: &[String]) -> String {
+            format!("<p:sld {}><p:cSld><p:spTree>{}</p:spTree></p:cSld></p:sld>", NS, shapes.concat())
         }
 
-        async fn to_metadata(&self, _file_path: &Path) -> anyhow::Result<ContentMetadata> {
-            Ok(ContentMetadata {
-                content_type: ContentType::Unknown,
-                file_name: None,
-                file_size: None,
-                created_at: None,
-                modified_at: None,
-                author: None,
-                title: None,
-                language: None,
-                additional: HashMap::new(),
-            })
+        /// Slides 1, 2 and 10 (so name order differs from slide order); slide 2 has notes.
+        fn fixture_deck() -> Vec<u8> {
+            let notes = format!(
+                "<p:notes {}><p:cSld><p:spTree>{}{}</p:spTree></p:cSld></p:notes>",
+                NS,
+                shape(Some("body"), &["Mention the Q3 numbers.", "Pause for questions."]),
+                shape(Some("sldNum"), &["2"]),
+            );
+            let parts = [
+                ("ppt/presentation.xml", format!("<p:presentation {}/>", NS)),
+                ("ppt/slides/slide1.xml", slide(&[shape(Some("ctrTitle"), &["Quarterly Review"]), shape(Some("subTitle"), &["Finance team"])])),
+                (
+                    "ppt/slides/slide2.xml",
+                    slide(&[
+                        shape(Some("title"), &["Revenue &amp; Costs"]),
+                        shape(None, &["Revenue grew 12%", "Costs fell 3%"]),
+                        shape(Some("sldNum"), &["2"]),
+                    ]),
+                ),
+                (
+                    "ppt/slides/_rels/slide2.xml.rels",
+                    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout2.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide" Target="../notesSlides/notesSlide1.xml"/></Relationships>"#.to_string(),
+                ),
+                ("ppt/notesSlides/notesSlide1.xml", notes),
+                ("ppt/slides/slide10.xml", slide(&[shape(None, &["Thank you"])])),
+                (
+                    "docProps/core.xml",
+                    r#"<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Q3 Review</dc:title><dc:creator>Jane Doe</dc:creator></cp:coreProperties>"#.to_string(),
+                ),
+            ];
+
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            {
+                let mut zip = zip::ZipWriter::new(&mut buffer);
+                let options = zip::write::SimpleFileOptions::default();
+                for (name, xml) in parts {
+                    zip.start_file(name, options).unwrap();
+                    zip.write_all(xml.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+            buffer.into_inner()
         }
 
-        async fn to_embeddings(&self, _chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
-            Ok(vec![])
+        #[test]
+        fn test_pptx_one_chunk_per_slide_with_notes() {
+            let chunks = PptxProvider::new().include_notes(true).chunk_bytes(&fixture_deck()).unwrap();
+
+            assert_eq!(chunks.len(), 3);
+            let numbers: Vec<_> = chunks.iter().map(|chunk| chunk.metadata["slide_number"].clone()).collect();
+            assert_eq!(numbers, vec![1, 2, 10]);
+
+            assert_eq!(chunks[0].metadata["slide_title"], "Quarterly Review");
+            assert_eq!(chunks[0].content, "## Quarterly Review\n\nFinance team");
+            assert_eq!(chunks[0].metadata["has_notes"], false);
+
+            assert_eq!(chunks[1].metadata["slide_title"], "Revenue & Costs");
+            assert_eq!(chunks[1].metadata["has_notes"], true);
+            assert_eq!(
+                chunks[1].content,
+                "## Revenue & Costs\n\nRevenue grew 12%\n\nCosts fell 3%\n\n### Notes\n\nMention the Q3 numbers.\n\nPause for questions."
+            );
+
+            assert!(!chunks[2].metadata.contains_key("slide_title"));
+            assert_eq!(chunks[2].content, "## Slide 10\n\nThank you");
+        }
+
+        #[test]
+        fn test_pptx_notes_can_be_left_out() {
+            let chunks = PptxProvider::new().include_notes(false).chunk_bytes(&fixture_deck()).unwrap();
+
+            assert_eq!(chunks[1].metadata["has_notes"], true);
+            assert!(!chunks[1].content.contains("Q3 numbers"));
+        }
+
+        #[tokio::test]
+        async fn test_pptx_metadata_and_detection() {
+            use crate::providers::ContentProvider;
+
+            let deck = fixture_deck();
+            let file = tempfile::Builder::new().suffix(".pptx").tempfile().unwrap();
+            std::fs::write(file.path(), &deck).unwrap();
+
+            let metadata = PptxProvider::new().to_metadata(file.path()).await.unwrap();
+            assert_eq!(metadata.content_type, ContentType::Presentation);
+            assert_eq!(metadata.title.as_deref(), Some("Q3 Review"));
+            assert_eq!(metadata.author.as_deref(), Some("Jane Doe"));
+            assert_eq!(metadata.additional["slide_count"], 3);
+
+            assert_eq!(detect_provider(&deck).map(|(content_type, _)| content_type), Some(ContentType::Presentation));
+        }
+    }
+
+    mod xlsx_provider_tests {
+        use crate::models::ContentType;
+        use crate::providers::registry::detect_provider;
+        use crate::providers::xlsx::XlsxProvider;
+        use std::io::Write;
+
+        fn cell(reference: &str, value: &str) -> String {
+            match value.strip_prefix("date:") {
+                // Style 1 is a date format; the value is an Excel serial date
+                Some(serial) => format!(r#"<c r="{}" s="1"><v>{}</v></c>"#, reference, serial),
+                None if value.parse::<f64>().is_ok() => format!(r#"<c r="{}"><v>{}</v></c>"#, reference, value),
+                None => format!(r#"<c r="{}" t="inlineStr"><is><t>{}</t></is></c>"#, reference, value),
+            }
+        }
+
+        fn worksheet(rows: &[&[&str]]) -> String {
+            let rows: String = rows
+                .iter()
+                .enumerate()
+                .map(|(i, cells)| {
+                    let cells: String = cells
+                        .iter()
+                        .enumerate()
+                        .map(|(column, value)| cell(&format!("{}{}", (b'A' + column as u8) as char, i + 1), value))
+                        .collect();
+                    format!(r#"<row r="{}">{}</row>"#, i + 1, cells)
+                })
+                .collect();
+            format!(
+                r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{}</sheetData></worksheet>"#,
+                rows
+            )
+        }
+
+        /// A workbook with a "Sales" sheet that has a header row, an empty
+        /// sheet, and a "Targets" sheet of bare numbers.
+        fn fixture_workbook() -> Vec<u8> {
+            let sheets = ["Sales", "Empty", "Targets"];
+            let workbook_sheets: String = sheets
+                .iter()
+                .enumerate()
+                .map(|(i, name)| format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, name, i + 1, i + 1))
+                .collect();
+            let relationships: String = (1..=sheets.len())
+                .map(|i| format!(r#"<Relationship Id="rId{i}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{i}.xml"/>"#))
+                .collect();
+
+            let parts = [
+                (
+                    "[Content_Types].xml",
+                    r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="xml" ContentType="application/xml"/></Types>"#.to_string(),
+                ),
+                (
+                    "_rels/.rels",
+                    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+                ),
+                (
+                    "xl/workbook.xml",
+                    format!(r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{}</sheets></workbook>"#, workbook_sheets),
+                ),
+                (
+                    "xl/_rels/workbook.xml.rels",
+                    format!(r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#, relationships),
+                ),
+                (
+                    "xl/styles.xml",
+                    r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><cellXfs count="2"><xf numFmtId="0"/><xf numFmtId="14" applyNumberFormat="1"/></cellXfs></styleSheet>"#.to_string(),
+                ),
+                (
+                    "xl/worksheets/sheet1.xml",
+                    worksheet(&[
+                        &["Region", "Revenue", "Closed"],
+                        &["North", "1250", "date:45352"],
+                        &["South", "980.5", "date:45353"],
+                        &["East|West", "42", ""],
+                    ]),
+                ),
+                ("xl/worksheets/sheet2.xml", worksheet(&[])),
+                ("xl/worksheets/sheet3.xml", worksheet(&[&["100", "200"]])),
+            ];
+
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            {
+                let mut zip = zip::ZipWriter::new(&mut buffer);
+                let options = zip::write::SimpleFileOptions::default();
+                for (name, xml) in parts {
+                    zip.start_file(name, options).unwrap();
+                    zip.write_all(xml.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+            buffer.into_inner()
+        }
+
+        #[test]
+        fn test_xlsx_chunks_each_sheet_in_row_windows() {
+            let chunks = XlsxProvider::with_rows_per_chunk(2).chunk_bytes(&fixture_workbook()).unwrap();
+
+            let sheets: Vec<_> = chunks.iter().map(|chunk| chunk.metadata["sheet_name"].clone()).collect();
+            assert_eq!(sheets, vec!["Sales", "Sales", "Targets"]);
+
+            assert_eq!(
+                chunks[0].content,
+                "## Sales\n\n\
+                 | Region | Revenue | Closed |\n\
+                 | --- | --- | --- |\n\
+                 | North | 1250 | 2024-03-01 |\n\
+                 | South | 980.5 | 2024-03-02 |"
+            );
+            assert_eq!(chunks[0].metadata["row_range"], serde_json::json!([2, 3]));
+            assert_eq!(chunks[0].metadata["header_row"], 1);
+
+            // The header is repeated in the sheet's later chunks
+            assert_eq!(
+                chunks[1].content,
+                "## Sales\n\n| Region | Revenue | Closed |\n| --- | --- | --- |\n| East\\|West | 42 |  |"
+            );
+            assert_eq!(chunks[1].metadata["row_range"], serde_json::json!([4, 4]));
+
+            // No header row, so columns are labelled by letter
+            assert_eq!(chunks[2].content, "## Targets\n\n| A | B |\n| --- | --- |\n| 100 | 200 |");
+            assert_eq!(chunks[2].metadata["header_row"], serde_json::Value::Null);
+            assert_eq!(chunks[2].metadata["sheet_index"], 2);
+        }
+
+        #[tokio::test]
+        async fn test_xlsx_metadata_lists_sheets() {
+            use crate::providers::ContentProvider;
+
+            let workbook = fixture_workbook();
+            let file = tempfile::Builder::new().suffix(".xlsx").tempfile().unwrap();
+            std::fs::write(file.path(), &workbook).unwrap();
+
+            let metadata = XlsxProvider::new().to_metadata(file.path()).await.unwrap();
+            assert_eq!(metadata.content_type, ContentType::Spreadsheet);
+            assert_eq!(metadata.additional["sheet_names"], serde_json::json!(["Sales", "Empty", "Targets"]));
+            assert_eq!(metadata.additional["empty_sheets"], serde_json::json!(["Empty"]));
+
+            assert_eq!(detect_provider(&workbook).map(|(content_type, _)| content_type), Some(ContentType::Spreadsheet));
+        }
+    }
+
+    mod image_provider_tests {
+        use crate::models::ContentType;
+        use crate::providers::image::ImageProvider;
+        use crate::providers::ContentProvider;
+
+        const ASCII: u16 = 2;
+        const LONG: u16 = 4;
+        const RATIONAL: u16 = 5;
+
+        /// `(tag, type, count, value)` with the value already encoded.
+        type Entry = (u16, u16, u32, Vec<u8>);
+
+        fn ascii(tag: u16, text: &str) -> Entry {
+            let mut value = text.as_bytes().to_vec();
+            value.push(0);
+            (tag, ASCII, value.len() as u32, value)
+        }
+
+        fn rationals(tag: u16, parts: &[(u32, u32)]) -> Entry {
+            let value = parts.iter().flat_map(|(num, denom)| [num.to_le_bytes(), denom.to_le_bytes()].concat()).collect();
+            (tag, RATIONAL, parts.len() as u32, value)
+        }
+
+        fn ifd_len(entries: &[Entry]) -> usize {
+            2 + 12 * entries.len() + 4 + entries.iter().map(|(_, _, _, value)| if value.len() > 4 { value.len() } else { 0 }).sum::<usize>()
+        }
+
+        /// Appends an IFD to a little-endian TIFF buffer, with values that do
+        /// not fit in an entry stored right after it.
+        fn write_ifd(tiff: &mut Vec<u8>, entries: &[Entry]) {
+            let mut data_offset = tiff.len() + 2 + 12 * entries.len() + 4;
+            let mut data: Vec<u8> = Vec::new();
+
+            tiff.extend((entries.len() as u16).to_le_bytes());
+            for (tag, kind, count, value) in entries {
+                tiff.extend(tag.to_le_bytes());
+                tiff.extend(kind.to_le_bytes());
+                tiff.extend(count.to_le_bytes());
+                if value.len() <= 4 {
+                    let mut inline = value.clone();
+                    inline.resize(4, 0);
+                    tiff.extend(inline);
+                } else {
+                    tiff.extend((data_offset as u32).to_le_bytes());
+                    data.extend(value);
+                    data_offset += value.len();
+                }
+            }
+            tiff.extend(0u32.to_le_bytes());
+            tiff.extend(data);
+        }
+
+        /// A 640x480 JPEG whose EXIF names the camera, the capture time and a
+        /// position in San Francisco. It has no scan data, which is all the
+        /// metadata path needs.
+        fn jpeg_with_exif() -> Vec<u8> {
+            let exif_ifd = vec![ascii(0x9003, "2024:03:01 14:30:00")];
+            let gps_ifd = vec![
+                ascii(0x0001, "N"),
+                rationals(0x0002, &[(37, 1), (46, 1), (2964, 100)]),
+                ascii(0x0003, "W"),
+                rationals(0x0004, &[(122, 1), (25, 1), (984, 100)]),
+            ];
+            let mut primary = vec![ascii(0x010F, "Canon"), ascii(0x0110, "Canon EOS R5"), (0x8769, LONG, 1, vec![]), (0x8825, LONG, 1, vec![])];
+
+            let exif_offset = 8 + ifd_len(&primary);
+            let gps_offset = exif_offset + ifd_len(&exif_ifd);
+            primary[2].3 = (exif_offset as u32).to_le_bytes().to_vec();
+            primary[3].3 = (gps_offset as u32).to_le_bytes().to_vec();
+
+            let mut tiff = b"II*\x00\x08\x00\x00\x00".to_vec();
+            write_ifd(&mut tiff, &primary);
+            write_ifd(&mut tiff, &exif_ifd);
+            write_ifd(&mut tiff, &gps_ifd);
+
+            let mut jpeg = b"\xFF\xD8\xFF\xE1".to_vec();
+            jpeg.extend(((tiff.len() + 8) as u16).to_be_bytes());
+            jpeg.extend(b"Exif\x00\x00");
+            jpeg.extend(tiff);
+            // Baseline frame header: 8-bit, 480 rows, 640 columns, 3 components
+            jpeg.extend(b"\xFF\xC0\x00\x11\x08\x01\xE0\x02\x80\x03\x01\x22\x00\x02\x11\x01\x03\x11\x01");
+            jpeg.extend(b"\xFF\xD9");
+            jpeg
+        }
+
+        /// A 3x2 PNG header with no EXIF.
+        fn bare_png() -> Vec<u8> {
+            let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+            png.extend(3u32.to_be_bytes());
+            png.extend(2u32.to_be_bytes());
+            png.extend(b"\x08\x02\x00\x00\x00\x00\x00\x00\x00");
+            png.extend(b"\x00\x00\x00\x00IEND\xAE\x42\x60\x82");
+            png
+        }
+
+        async fn write_temp(bytes: &[u8], suffix: &str) -> tempfile::NamedTempFile {
+            let file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+            tokio::fs::write(file.path(), bytes).await.unwrap();
+            file
+        }
+
+        #[tokio::test]
+        async fn test_image_metadata_from_exif() {
+            let file = write_temp(&jpeg_with_exif(), ".jpg").await;
+
+            let metadata = ImageProvider::new().to_metadata(file.path()).await.unwrap();
+
+            assert_eq!(metadata.content_type, ContentType::Image);
+            assert_eq!(metadata.additional["width"], 640);
+            assert_eq!(metadata.additional["height"], 480);
+            assert_eq!(metadata.additional["format"], "jpeg");
+            assert_eq!(metadata.additional["camera_make"], "Canon");
+            assert_eq!(metadata.additional["camera_model"], "Canon EOS R5");
+            assert_eq!(metadata.created_at.as_deref(), Some("2024-03-01T14:30:00"));
+
+            let latitude = metadata.additional["gps"]["latitude"].as_f64().unwrap();
+            let longitude = metadata.additional["gps"]["longitude"].as_f64().unwrap();
+            assert!((latitude - 37.7749).abs() < 1e-6, "latitude {}", latitude);
+            assert!((longitude + 122.4194).abs() < 1e-6, "longitude {}", longitude);
+
+            let raw = ImageProvider::new().raw_metadata(file.path()).await.unwrap().unwrap();
+            assert_eq!(raw["Make"], "\"Canon\"");
+        }
+
+        #[cfg(not(feature = "ocr"))]
+        #[tokio::test]
+        async fn test_image_without_ocr_has_metadata_and_no_chunks() {
+            let file = write_temp(&bare_png(), ".png").await;
+
+            let result = ImageProvider::new().process_content(file.path()).await.unwrap();
+
+            assert!(result.success);
+            assert!(result.chunks.is_empty());
+            assert_eq!(result.metadata.additional["width"], 3);
+            assert_eq!(result.metadata.additional["height"], 2);
+            assert_eq!(result.metadata.additional["format"], "png");
+            assert!(result.metadata.created_at.is_none());
+            assert!(!result.metadata.additional.contains_key("gps"));
+        }
+
+        #[tokio::test]
+        async fn test_image_rejects_unreadable_data() {
+            let file = write_temp(b"not an image", ".png").await;
+
+            assert!(ImageProvider::new().to_metadata(file.path()).await.is_err());
+        }
+    }
+
+    mod pdf_provider_tests {
+        use super::*;
+        use crate::providers::pdf::{pdf_date_to_iso, PageChunk, PageChunker, PdfProvider};
+        use pdf_extract::content::{Content, Operation};
+        use pdf_extract::{dictionary, Document, Object, Stream};
+
+        /// Builds a PDF with one page per entry in `pages`, each holding a single line of text.
+        pub(super) fn build_pdf(pages: &[&str]) -> Document {
+            let mut doc = Document::with_version("1.5");
+            let pages_id = doc.new_object_id();
+            let font_id = doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+            });
+
+            let mut kids = Vec::new();
+            for text in pages {
+                let content = Content {
+                    operations: vec![
+                        Operation::new("BT", vec![]),
+                        Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                        Operation::new("Td", vec![72.into(), 720.into()]),
+                        Operation::new("Tj", vec![Object::string_literal(*text)]),
+                        Operation::new("ET", vec![]),
+                    ],
+                };
+                let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+                let page_id = doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                });
+                kids.push(page_id.into());
+            }
+
+            doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }));
+            let catalog_id = doc.add_object(dictionary! {
+                "Type" => "Catalog",
+                "Pages" => pages_id,
+            });
+            doc.trailer.set("Root", catalog_id);
+            doc
+        }
+
+        pub(super) fn write_pdf_with_info(path: &Path) {
+            let mut doc = build_pdf(&["Hello PDF"]);
+            let info_id = doc.add_object(dictionary! {
+                "Title" => Object::string_literal("Quarterly Report"),
+                "Author" => Object::string_literal("Jane Doe"),
+                "Producer" => Object::string_literal("p8fs tests"),
+                "CreationDate" => Object::string_literal("D:20240101120000Z"),
+                "ModDate" => Object::string_literal("D:20240315093000+01'00'"),
+                "X-Archive-Id" => Object::string_literal("A-42"),
+            });
+            doc.trailer.set("Info", info_id);
+            doc.save(path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn test_pdf_chunks_carry_real_page_numbers() {
+            let provider = PdfProvider::new();
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("book.pdf");
+            let long_page = "word ".repeat(500);
+            build_pdf(&["First page text", &long_page, "Third page text"]).save(&test_path).unwrap();
+
+            let chunks = provider.to_markdown_chunks(&test_path).await.unwrap();
+
+            let pages: Vec<u64> = chunks.iter().map(|c| c.metadata["page_number"].as_u64().unwrap()).collect();
+            assert_eq!(pages.first(), Some(&1));
+            assert!(pages.iter().filter(|&&page| page == 2).count() > 1, "long page should span several chunks");
+            assert!(pages.windows(2).all(|w| w[0] <= w[1]));
+
+            assert!(chunks[0].content.contains("First page text"));
+            assert_eq!(chunks[0].metadata["pages"], serde_json::json!([1, 2]));
+            assert!(chunks.last().unwrap().content.contains("Third page text"));
+            assert_eq!(chunks.last().unwrap().metadata["pages"], serde_json::json!([2, 3]));
+            assert_eq!(chunks.last().unwrap().metadata["page_reference"], "Pages 2-3");
+        }
+
+        #[tokio::test]
+        async fn test_pdf_chunk_spanning_page_break_reports_both_pages() {
+            let provider = PdfProvider::new();
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("spanning.pdf");
+            let third = "third ".repeat(100);
+            let fourth = "fourth ".repeat(100);
+            build_pdf(&["cover", "intro", &third, &fourth]).save(&test_path).unwrap();
+
+            let chunks = provider.to_markdown_chunks(&test_path).await.unwrap();
+
+            let spanning = chunks
+                .iter()
+                .find(|c| c.content.contains("third") && c.content.contains("fourth"))
+                .expect("a chunk should cross the page 3/4 break");
+            let pages: Vec<u64> = spanning.metadata["pages"].as_array().unwrap().iter().map(|p| p.as_u64().unwrap()).collect();
+            assert!(pages.contains(&3) && pages.contains(&4), "pages were {:?}", pages);
+
+            let last = chunks.last().unwrap();
+            assert_eq!(last.metadata["pages"].as_array().unwrap().last().unwrap(), 4);
+        }
+
+        #[test]
+        fn test_page_chunker_tracks_pages_per_chunk() {
+            let mut chunker = PageChunker::new(10, 2);
+            chunker.push_page(1, "aaaaaaa");
+            chunker.push_page(2, "   ");
+            chunker.push_page(3, "bbbbbbbbbbbbbbb");
+            let chunks = chunker.finish();
+
+            assert_eq!(
+                chunks[0],
+                PageChunk { pages: vec![1, 3], char_start: 0, char_end: 10, text: "aaaaaaa\nbb".to_string() }
+            );
+            assert!(chunks[1..].iter().all(|chunk| chunk.pages == vec![3]));
+            assert!(chunks.last().unwrap().text.ends_with("b\n"));
+        }
+
+        #[test]
+        fn test_page_chunker_offsets_overlap_by_configured_amount() {
+            let mut chunker = PageChunker::new(100, 20);
+            chunker.push_page(1, &"a".repeat(130));
+            chunker.push_page(2, &"b".repeat(130));
+            let chunks = chunker.finish();
+
+            assert!(chunks.len() > 2);
+            assert_eq!(chunks[0].char_start, 0);
+            for pair in chunks.windows(2) {
+                assert_eq!(pair[0].char_end - pair[1].char_start, 20);
+            }
+            for chunk in &chunks {
+                assert_eq!(chunk.char_end - chunk.char_start, chunk.text.chars().count());
+            }
+            assert_eq!(chunks.last().unwrap().char_end, 262);
+        }
+
+        #[tokio::test]
+        async fn test_pdf_raw_metadata_info_dictionary() {
+            let provider = PdfProvider::new();
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("report.pdf");
+            write_pdf_with_info(&test_path);
+
+            let raw = provider.raw_metadata(&test_path).await.unwrap().unwrap();
+
+            for key in ["Title", "Author", "Producer", "CreationDate", "X-Archive-Id"] {
+                assert!(raw.get(key).is_some(), "missing info key {}", key);
+            }
+            assert_eq!(raw["Title"], "Quarterly Report");
+            assert_eq!(raw["X-Archive-Id"], "A-42");
+        }
+
+        #[tokio::test]
+        async fn test_pdf_metadata_from_info_dictionary() {
+            let provider = PdfProvider::new();
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("report.pdf");
+            write_pdf_with_info(&test_path);
+
+            let metadata = provider.to_metadata(&test_path).await.unwrap();
+
+            assert_eq!(metadata.title.as_deref(), Some("Quarterly Report"));
+            assert_eq!(metadata.author.as_deref(), Some("Jane Doe"));
+            assert_eq!(metadata.created_at.as_deref(), Some("2024-01-01T12:00:00Z"));
+            assert_eq!(metadata.modified_at.as_deref(), Some("2024-03-15T09:30:00+01:00"));
+            assert_eq!(metadata.additional["page_count"], 1);
+        }
+
+        #[tokio::test]
+        async fn test_pdf_metadata_without_info_dictionary() {
+            let provider = PdfProvider::new();
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("plain.pdf");
+            build_pdf(&["One", "Two"]).save(&test_path).unwrap();
+
+            let metadata = provider.to_metadata(&test_path).await.unwrap();
+
+            assert!(metadata.title.is_none());
+            assert!(metadata.author.is_none());
+            assert!(metadata.created_at.is_none());
+            assert!(metadata.modified_at.is_none());
+            assert_eq!(metadata.additional["page_count"], 2);
+        }
+
+        #[test]
+        fn test_pdf_date_to_iso() {
+            assert_eq!(pdf_date_to_iso("D:20240315093000+01'00'").as_deref(), Some("2024-03-15T09:30:00+01:00"));
+            assert_eq!(pdf_date_to_iso("D:19991231235959-05'30").as_deref(), Some("1999-12-31T23:59:59-05:30"));
+            assert_eq!(pdf_date_to_iso("D:2024").as_deref(), Some("2024-01-01T00:00:00"));
+            assert_eq!(pdf_date_to_iso("20240101120000Z").as_deref(), Some("2024-01-01T12:00:00Z"));
+            assert_eq!(pdf_date_to_iso("yesterday"), None);
+        }
+
+        #[test]
+        fn test_pdf_chunk_text() {
+            let text = "a".repeat(2500); // Long text
+            
+            let mut chunker = PageChunker::new(1000, 200);
+            chunker.push_page(1, &text);
+            let chunks: Vec<String> = chunker.finish().into_iter().map(|chunk| chunk.text).collect();
+            
+            assert!(chunks.len() > 2);
+            assert_eq!(chunks[0].len(), 1000);
+            
+            // Check overlap
+            let overlap_start = &chunks[0][800..];
+            let next_start = &chunks[1][..200];
+            assert_eq!(overlap_start, next_start);
+        }
+    }
+
+    mod chunk_id_tests {
+        use super::*;
+        use crate::providers::{audio::AudioProvider, document::DocumentProvider, json::JsonProvider, markdown::MarkdownProvider, pdf::PdfProvider};
+
+        /// Processes `bytes` under two different file names and returns both ID lists.
+        async fn ids_across_runs(provider: &dyn ContentProvider, extension: &str, bytes: &[u8]) -> (Vec<String>, Vec<String>) {
+            let dir = tempfile::tempdir().unwrap();
+            let mut runs = Vec::new();
+
+            for name in ["first", "second"] {
+                let path = dir.path().join(format!("{}.{}", name, extension));
+                fs::write(&path, bytes).await.unwrap();
+                let chunks = provider.to_markdown_chunks(&path).await.unwrap();
+                runs.push(chunks.into_iter().map(|c| c.id).collect::<Vec<_>>());
+            }
+
+            let second = runs.pop().unwrap();
+            (runs.pop().unwrap(), second)
+        }
+
+        async fn assert_canonical_ids(provider: &dyn ContentProvider, name: &str, extension: &str, bytes: &[u8], other: &[u8]) {
+            let (first, second) = ids_across_runs(provider, extension, bytes).await;
+            assert!(!first.is_empty());
+            assert_eq!(first, second, "{} IDs should not depend on file name or run", name);
+
+            let doc_hash = ids::doc_hash(bytes);
+            let prefix = format!("{}_{}_", name, doc_hash);
+            assert!(first.iter().all(|id| id.starts_with(&prefix)), "{} IDs should start with {}", name, prefix);
+
+            let (changed, _) = ids_across_runs(provider, extension, other).await;
+            assert!(changed.iter().all(|id| !first.contains(id)), "{} IDs should change with content", name);
+        }
+
+        #[test]
+        fn test_chunk_id_format() {
+            let doc_hash = ids::doc_hash(b"hello");
+            assert_eq!(doc_hash.len(), 16);
+            assert_eq!(doc_hash, ids::doc_hash(b"hello"));
+            assert_ne!(doc_hash, ids::doc_hash(b"hello!"));
+            assert_eq!(
+                ids::chunk_id("pdf", &doc_hash, "# Page"),
+                format!("pdf_{}_{}", doc_hash, ids::content_hash("# Page"))
+            );
+        }
+
+        #[test]
+        fn test_chunk_ids_follow_content() {
+            use crate::providers::code::{CodeProvider, Language};
+
+            let source = "one\ntwo\nthree\nfour\nfive\nsix";
+            let ids = |lines_per_chunk| -> Vec<String> {
+                CodeProvider::new(Language::Rust)
+                    .lines_per_chunk(lines_per_chunk)
+                    .chunk_str(source)
+                    .into_iter()
+                    .map(|chunk| chunk.id)
+                    .collect()
+            };
+
+            // Same content, same ID, on every run
+            assert_eq!(ids(2), ids(2));
+
+            // Chunking the same file differently never reuses an ID for other text
+            let (pairs, triples) = (ids(2), ids(3));
+            assert!(pairs.iter().all(|id| !triples.contains(id)));
+        }
+
+        #[test]
+        fn test_repeated_chunks_get_distinct_ids() {
+            let chunks = MarkdownProvider::new().chunk_str("## Notes\n\nSame\n\n## Notes\n\nSame\n\n## Notes\n\nSame");
+            assert_eq!(chunks.len(), 3);
+
+            let base = ids::chunk_id("markdown", &ids::doc_hash(b"## Notes\n\nSame\n\n## Notes\n\nSame\n\n## Notes\n\nSame"), &chunks[0].content);
+            let ids: Vec<_> = chunks.iter().map(|chunk| chunk.id.clone()).collect();
+            assert_eq!(ids, vec![base.clone(), format!("{}_1", base), format!("{}_2", base)]);
+            assert_eq!(chunks[2].metadata["chunk_index"], 2);
+        }
+
+        #[test]
+        fn test_hash_algorithms_stable_and_distinct() {
+            use crate::providers::hashing::Hasher;
+
+            let algorithms = [Hasher::Blake3, Hasher::Sha256, Hasher::Sha1];
+            let hashes: Vec<String> = algorithms.iter().map(|hasher| hasher.hex(b"abc")).collect();
+
+            assert_eq!(hashes[1], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+            assert_eq!(hashes[2], "a9993e364706816aba3e25717850c26c9cd0d89d");
+            for (hasher, hash) in algorithms.iter().zip(&hashes) {
+                assert_eq!(&hasher.hex(b"abc"), hash);
+                assert_eq!(ids::doc_hash_with(*hasher, b"abc"), hash[..16]);
+            }
+            assert_ne!(hashes[0], hashes[1]);
+            assert_ne!(hashes[0][..40], hashes[2]);
+            assert_ne!(hashes[1][..40], hashes[2]);
+
+            assert_eq!("SHA256".parse::<Hasher>().unwrap(), Hasher::Sha256);
+            assert!("md5".parse::<Hasher>().is_err());
+        }
+
+        #[tokio::test]
+        async fn test_json_ids_deterministic() {
+            assert_canonical_ids(&JsonProvider::new(), "json", "json", br#"{"a": {"b": 1}, "c": [1, 2]}"#, br#"{"a": {"b": 2}}"#).await;
+        }
+
+        #[tokio::test]
+        async fn test_markdown_ids_deterministic() {
+            assert_canonical_ids(&MarkdownProvider::new(), "markdown", "md", b"# One\n\nFirst\n\n## Two\n\nSecond", b"# One\n\nChanged").await;
+        }
+
+        #[tokio::test]
+        async fn test_pdf_ids_deterministic() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("source.pdf");
+            super::pdf_provider_tests::write_pdf_with_info(&path);
+            let bytes = fs::read(&path).await.unwrap();
+            let mut other = bytes.clone();
+            other.extend_from_slice(b"\n% trailing comment\n");
+
+            assert_canonical_ids(&PdfProvider::new(), "pdf", "pdf", &bytes, &other).await;
+        }
+
+        #[tokio::test]
+        async fn test_docx_ids_deterministic() {
+            fn docx_bytes(text: &str) -> Vec<u8> {
+                let mut buffer = std::io::Cursor::new(Vec::new());
+                docx_rs::Docx::new()
+                    .add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text(text)))
+                    .build()
+                    .pack(&mut buffer)
+                    .unwrap();
+                buffer.into_inner()
+            }
+
+            assert_canonical_ids(&DocumentProvider::new(), "docx", "docx", &docx_bytes("Hello docx"), &docx_bytes("Changed")).await;
+        }
+
+        #[tokio::test]
+        async fn test_audio_ids_deterministic() {
+            fn wav_bytes(seconds: u32) -> Vec<u8> {
+                let spec = hound::WavSpec {
+                    channels: 1,
+                    sample_rate: 8000,
+                    bits_per_sample: 16,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                let mut buffer = std::io::Cursor::new(Vec::new());
+                let mut writer = hound::WavWriter::new(&mut buffer, spec).unwrap();
+                for i in 0..8000 * seconds {
+                    writer.write_sample((i % 100) as i16).unwrap();
+                }
+                writer.finalize().unwrap();
+                buffer.into_inner()
+            }
+
+            assert_canonical_ids(&AudioProvider::new(), "audio", "wav", &wav_bytes(45), &wav_bytes(40)).await;
+        }
+    }
+
+    mod code_provider_tests {
+        use crate::providers::code::{CodeProvider, Language};
+
+        const RUST_SOURCE: &str = r#"use std::fmt;
+
+/// Parses a point from "x,y".
+pub fn parse(input: &str) -> Option<Point> {
+    let (x, y) = input.split_once(',')?;
+    Some(Point { x: x.parse().ok()?, y: y.parse().ok()? })
+}
+
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+fn main() {
+    println!("{}", parse("1,2").unwrap());
+}
+"#;
+
+        #[test]
+        fn test_rust_functions_become_separate_chunks() {
+            let chunks = CodeProvider::new(Language::Rust).chunk_str(RUST_SOURCE);
+
+            let symbols: Vec<_> = chunks.iter().map(|chunk| chunk.metadata.get("symbol_name").cloned()).collect();
+            assert_eq!(
+                symbols,
+                vec![
+                    None,
+                    Some(serde_json::json!("parse")),
+                    Some(serde_json::json!("Point")),
+                    Some(serde_json::json!("fmt::Display for Point")),
+                    Some(serde_json::json!("main")),
+                ]
+            );
+
+            // Doc comments and attributes travel with their definition
+            assert!(chunks[1].content.starts_with("```rust\n/// Parses a point"));
+            assert!(chunks[1].content.ends_with("}\n```"));
+            assert!(chunks[2].content.starts_with("```rust\n#[derive(Debug)]\npub struct Point {"));
+            // Methods stay inside their impl
+            assert!(chunks[3].content.contains("    fn fmt(&self"));
+
+            assert_eq!(chunks[0].content, "```rust\nuse std::fmt;\n```");
+            assert_eq!(chunks[1].metadata["line_range"], serde_json::json!([3, 7]));
+            assert!(chunks.iter().all(|chunk| chunk.metadata["language"] == "rust"));
+        }
+
+        #[test]
+        fn test_code_without_definitions_uses_line_windows() {
+            let script: String = (1..=25).map(|i| format!("print({})\n", i)).collect();
+
+            let chunks = CodeProvider::new(Language::Python).lines_per_chunk(10).chunk_str(&script);
+
+            assert_eq!(chunks.len(), 3);
+            assert_eq!(chunks[2].metadata["line_range"], serde_json::json!([21, 25]));
+            assert!(!chunks[0].metadata.contains_key("symbol_name"));
+        }
+
+        #[tokio::test]
+        async fn test_python_decorators_and_metadata() {
+            use crate::providers::ContentProvider;
+
+            let source = "import os\n\n@cache\ndef load(path):\n    return open(path).read()\n\nclass Store:\n    def get(self):\n        pass\n";
+            let provider = CodeProvider::new(Language::Python);
+
+            let chunks = provider.chunk_str(source);
+            assert_eq!(chunks.len(), 3);
+            assert!(chunks[1].content.starts_with("```python\n@cache\ndef load(path):"));
+            assert_eq!(chunks[2].metadata["symbol_name"], "Store");
+
+            let metadata = provider.process_bytes(source.as_bytes()).await.unwrap().unwrap().metadata;
+            assert_eq!(metadata.language.as_deref(), Some("python"));
+            assert_eq!(metadata.additional["symbols"], serde_json::json!(["load", "Store"]));
+        }
+
+        #[tokio::test]
+        async fn test_generic_provider_takes_language_from_extension() {
+            use crate::providers::ContentProvider;
+
+            let provider = CodeProvider::generic();
+            let source = "import os\n\ndef load(path):\n    return path\n";
+            assert!(provider.process_bytes(source.as_bytes()).await.unwrap().is_none());
+
+            let file = tempfile::Builder::new().suffix(".py").tempfile().unwrap();
+            std::fs::write(file.path(), source).unwrap();
+            let result = provider.process_content(file.path()).await.unwrap();
+            assert_eq!(result.metadata.language.as_deref(), Some("python"));
+            assert_eq!(result.chunks[1].metadata["symbol_name"], "load");
+            assert!(result.chunks[1].content.starts_with("```python\n"));
+
+            // An unknown language is cut into line windows, unlabelled
+            let file = tempfile::Builder::new().suffix(".kt").tempfile().unwrap();
+            std::fs::write(file.path(), "fun main() {\n    println(1)\n}\n").unwrap();
+            let result = provider.process_content(file.path()).await.unwrap();
+            assert_eq!(result.metadata.language, None);
+            assert_eq!(result.chunks.len(), 1);
+            assert!(result.chunks[0].content.starts_with("```\nfun main()"));
+            assert!(!result.chunks[0].metadata.contains_key("language"));
+        }
+    }
+
+    mod sql_provider_tests {
+        use super::*;
+        use crate::providers::sql::SqlProvider;
+
+        const SCHEMA: &str = r#"
+-- Accounts and their orders
+CREATE TABLE IF NOT EXISTS public.users (
+    id SERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    "display name" TEXT,
+    created_at TIMESTAMP DEFAULT now()
+);
+
+/* orders; one row per checkout */
+CREATE TABLE orders (
+    id BIGINT NOT NULL,
+    user_id INTEGER REFERENCES public.users(id),
+    total NUMERIC(10, 2),
+    PRIMARY KEY (id)
+);
+
+INSERT INTO orders VALUES (1, 1, 9.99);
+INSERT INTO orders VALUES (2, 1, 19.99);
+"#;
+
+        #[tokio::test]
+        async fn test_sql_provider_one_chunk_per_table() {
+            let provider = SqlProvider::new();
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("schema.sql");
+            fs::write(&test_path, SCHEMA).await.unwrap();
+
+            let chunks = provider.to_markdown_chunks(&test_path).await.unwrap();
+            let tables: Vec<_> = chunks.iter().filter(|c| c.metadata["object_type"] == "table").collect();
+
+            assert_eq!(tables.len(), 2);
+            assert_eq!(tables[0].metadata["object_name"], "public.users");
+            assert_eq!(tables[1].metadata["object_name"], "orders");
+            assert_eq!(tables[0].metadata["columns"], serde_json::json!(["id", "email", "display name", "created_at"]));
+
+            for column in ["id", "email", "display name", "created_at"] {
+                assert!(tables[0].content.contains(&format!("| {} |", column)), "missing column {}", column);
+            }
+            for column in ["id", "user_id", "total"] {
+                assert!(tables[1].content.contains(&format!("| {} |", column)), "missing column {}", column);
+            }
+            assert!(tables[0].content.contains("| VARCHAR(255) | NOT NULL UNIQUE |"));
+            assert!(tables[1].content.contains("| NUMERIC(10, 2) |"));
+            assert!(tables[1].content.contains("PRIMARY KEY (id)"));
+
+            let summary = chunks.last().unwrap();
+            assert_eq!(summary.metadata["object_type"], "insert_summary");
+            assert!(summary.content.contains("| orders | 2 |"));
+        }
+
+        #[test]
+        fn test_sql_views_and_functions() {
+            let provider = SqlProvider::new();
+            let sql = r#"
+CREATE OR REPLACE VIEW active_users AS SELECT * FROM users WHERE active;
+CREATE FUNCTION add_one(x integer) RETURNS integer AS $$
+BEGIN
+    RETURN x + 1;
+END;
+$$ LANGUAGE plpgsql;
+"#;
+
+            let statements = provider.split_statements(sql);
+            assert_eq!(statements.len(), 2, "semicolons inside $$ bodies should not split");
+
+            let (objects, inserts) = provider.parse_objects(sql);
+            assert_eq!(objects.len(), 2);
+            assert!(inserts.is_empty());
+            assert!(matches!(&objects[0], crate::providers::sql::SqlObject::View { name, .. } if name == "active_users"));
+            assert!(matches!(&objects[1], crate::providers::sql::SqlObject::Function { name, statement } if name == "add_one" && statement.contains("RETURN x + 1")));
+        }
+    }
+
+    mod yaml_provider_tests {
+        use super::*;
+        use crate::providers::yaml::YamlProvider;
+
+        #[tokio::test]
+        async fn test_yaml_multi_document_stream() {
+            let provider = YamlProvider::new();
+            let test_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app-config
+data:
+  LOG_LEVEL: debug
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: app
+spec:
+  replicas: 3
+"#;
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("manifests.yaml");
+            fs::write(&test_path, test_content).await.unwrap();
+
+            let result = provider.process_content(&test_path).await.unwrap();
+
+            let kinds: Vec<(&serde_json::Value, &serde_json::Value)> = result
+                .chunks
+                .iter()
+                .filter_map(|c| Some((c.metadata.get("kind")?, &c.metadata["document_index"])))
+                .collect();
+            assert_eq!(kinds, vec![
+                (&serde_json::json!("ConfigMap"), &serde_json::json!(0)),
+                (&serde_json::json!("Deployment"), &serde_json::json!(1)),
+            ]);
+
+            let config_map = result.chunks.iter().find(|c| c.content.starts_with("## ConfigMap")).unwrap();
+            assert!(config_map.content.contains("LOG_LEVEL"));
+            assert!(result.chunks.iter().all(|c| c.metadata["source"] == "yaml"));
+            assert!(result.chunks.iter().all(|c| c.id.starts_with("yaml_")));
+            assert_eq!(result.metadata.content_type, ContentType::Yaml);
+            assert_eq!(result.metadata.additional["document_count"], 2);
+        }
+
+        #[test]
+        fn test_yaml_group_by_kind_across_documents() {
+            let provider = YamlProvider::new().group_by_kind(true);
+            let test_content = "kind: Service\n---\nkind: Deployment\n---\nkind: Service\n";
+
+            let chunks = provider.chunk_str(test_content).unwrap();
+
+            let order: Vec<_> = chunks
+                .iter()
+                .map(|c| (c.metadata["kind"].clone(), c.metadata["document_index"].clone()))
+                .collect();
+            assert_eq!(order, vec![
+                (serde_json::json!("Service"), serde_json::json!(0)),
+                (serde_json::json!("Service"), serde_json::json!(2)),
+                (serde_json::json!("Deployment"), serde_json::json!(1)),
+            ]);
+        }
+
+        #[test]
+        fn test_yaml_tags_merges_and_non_string_keys() {
+            let provider = YamlProvider::new();
+            let documents = provider
+                .parse_documents("base: &base\n  region: eu\nsite:\n  <<: *base\n  bucket: !Ref Bucket\n  80: http\n---\n")
+                .unwrap();
+
+            assert_eq!(documents.len(), 1);
+            assert_eq!(documents[0]["site"], serde_json::json!({ "region": "eu", "bucket": "Bucket", "80": "http" }));
+        }
+    }
+
+    mod audio_provider_tests {
+        use crate::models::ContentMetadata;
+        use crate::providers::audio::AudioProvider;
+        use crate::providers::ContentProvider;
+        use hound::{SampleFormat, WavSpec, WavWriter};
+
+        fn spec(channels: u16, sample_rate: u32, bits_per_sample: u16, sample_format: SampleFormat) -> WavSpec {
+            WavSpec { channels, sample_rate, bits_per_sample, sample_format }
+        }
+
+        /// A silent WAV holding `frames` frames in the given format.
+        fn silent_wav(spec: WavSpec, frames: usize) -> Vec<u8> {
+            let mut cursor = std::io::Cursor::new(Vec::new());
+            let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+            for _ in 0..frames * spec.channels as usize {
+                match (spec.sample_format, spec.bits_per_sample) {
+                    (SampleFormat::Float, _) => writer.write_sample(0.0f32).unwrap(),
+                    (_, 8) => writer.write_sample(0i8).unwrap(),
+                    (_, 16) => writer.write_sample(0i16).unwrap(),
+                    _ => writer.write_sample(0i32).unwrap(),
+                }
+            }
+            writer.finalize().unwrap();
+            cursor.into_inner()
+        }
+
+        async fn wav_metadata(wav: Vec<u8>) -> ContentMetadata {
+            let file = tempfile::Builder::new().suffix(".wav").tempfile().unwrap();
+            std::fs::write(file.path(), wav).unwrap();
+            AudioProvider::new().to_metadata(file.path()).await.unwrap()
+        }
+
+        /// A one-second mono WAV with a `LIST`/`INFO` chunk holding `tags`.
+        fn tagged_wav(tags: &[(&[u8; 4], &str)]) -> Vec<u8> {
+            let mut wav = silent_wav(spec(1, 8000, 16, SampleFormat::Int), 8000);
+
+            let mut info = b"INFO".to_vec();
+            for (id, value) in tags {
+                let mut data = value.as_bytes().to_vec();
+                data.push(0);
+                info.extend_from_slice(*id);
+                info.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                info.extend_from_slice(&data);
+                if data.len() % 2 == 1 {
+                    info.push(0);
+                }
+            }
+            wav.extend_from_slice(b"LIST");
+            wav.extend_from_slice(&(info.len() as u32).to_le_bytes());
+            wav.extend_from_slice(&info);
+
+            let riff_size = (wav.len() - 8) as u32;
+            wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+            wav
+        }
+
+        #[tokio::test]
+        async fn test_audio_metadata_from_info_tags() {
+            let wav = tagged_wav(&[
+                (b"INAM", "Morning Standup"),
+                (b"IART", "Jane Doe"),
+                (b"ICRD", "2024-03-15"),
+                (b"IGNR", "Speech"),
+            ]);
+            let metadata = wav_metadata(wav).await;
+
+            assert_eq!(metadata.title.as_deref(), Some("Morning Standup"));
+            assert_eq!(metadata.author.as_deref(), Some("Jane Doe"));
+            assert_eq!(metadata.created_at.as_deref(), Some("2024-03-15"));
+            assert_eq!(metadata.additional["genre"], "Speech");
+            assert_eq!(metadata.additional["sample_rate"], 8000);
+            assert_eq!(metadata.additional["duration_seconds"], 1.0);
+        }
+
+        #[test]
+        fn test_audio_untagged_wav_has_no_tags() {
+            let provider = AudioProvider::new();
+            let wav = tagged_wav(&[]);
+
+            assert!(provider.read_info_tags(&wav).is_empty());
+            assert!(provider.read_info_tags(b"not a wav").is_empty());
+        }
+
+        #[test]
+        fn test_audio_segment_calculation() {
+            let provider = AudioProvider::new();
+            let frames = 44100 * 60; // 60 seconds at 44.1kHz
+            
+            let segments = provider.segment_audio(frames, 44100, 30.0);
+            
+            assert_eq!(segments.len(), 2); // Two 30-second segments
+            assert_eq!(segments[0].0, 0);
+            assert_eq!(segments[0].1, 44100 * 30);
+            assert_eq!(segments[1].0, 44100 * 30);
+            assert_eq!(segments[1].1, 44100 * 60);
+        }
+
+        #[tokio::test]
+        async fn test_audio_duration_mono_8khz() {
+            let metadata = wav_metadata(silent_wav(spec(1, 8000, 16, SampleFormat::Int), 8000 * 5 / 2)).await;
+
+            assert_eq!(metadata.additional["duration_seconds"], 2.5);
+            assert_eq!(metadata.additional["channels"], 1);
+        }
+
+        #[tokio::test]
+        async fn test_audio_duration_stereo_44khz() {
+            let metadata = wav_metadata(silent_wav(spec(2, 44100, 16, SampleFormat::Int), 44100 * 3)).await;
+
+            // Interleaved samples are twice the frame count; duration must not double or halve
+            assert_eq!(metadata.additional["duration_seconds"], 3.0);
+            assert_eq!(metadata.additional["channels"], 2);
+        }
+
+        #[tokio::test]
+        async fn test_audio_reads_8_24_and_float_samples() {
+            for (bits, format) in [(8, SampleFormat::Int), (24, SampleFormat::Int), (32, SampleFormat::Float)] {
+                let wav = silent_wav(spec(2, 16000, bits, format), 16000);
+
+                let file = tempfile::Builder::new().suffix(".wav").tempfile().unwrap();
+                std::fs::write(file.path(), wav).unwrap();
+                let chunks = AudioProvider::new().to_markdown_chunks(file.path()).await.unwrap();
+
+                assert_eq!(chunks.len(), 1, "{}-bit {:?}", bits, format);
+                assert_eq!(chunks[0].metadata["end_sample"], 16000);
+                assert_eq!(chunks[0].metadata["bits_per_sample"], bits);
+            }
+        }
+    }
+
+    mod archive_provider_tests {
+        use crate::models::ContentType;
+        use crate::providers::archive::ArchiveProvider;
+        use crate::providers::limits::{LimitExceeded, ResourceLimits};
+        use crate::providers::ContentProvider;
+        use std::io::Write;
+
+        fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            {
+                let mut zip = zip::ZipWriter::new(&mut buffer);
+                let options = zip::write::SimpleFileOptions::default();
+                for (name, data) in entries {
+                    zip.start_file(*name, options).unwrap();
+                    zip.write_all(data).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+            buffer.into_inner()
+        }
+
+        fn write_temp(bytes: &[u8]) -> tempfile::NamedTempFile {
+            let file = tempfile::Builder::new().suffix(".zip").tempfile().unwrap();
+            std::fs::write(file.path(), bytes).unwrap();
+            file
+        }
+
+        #[tokio::test]
+        async fn test_archive_runs_entries_through_their_providers() {
+            let inner = build_zip(&[("nested.md", b"# Nested")]);
+            let archive = build_zip(&[
+                ("docs/notes.md", b"# Notes\n\nFirst section.\n\n## More\n\nSecond section."),
+                ("data.json", br#"{"name": "p8fs", "tags": ["a", "b"]}"#),
+                ("blob.bin", &[0u8, 1, 2, 3]),
+                ("inner.zip", &inner),
+            ]);
+            let file = write_temp(&archive);
+
+            let result = ArchiveProvider::new().process_content(file.path()).await.unwrap();
+
+            assert_eq!(result.metadata.content_type, ContentType::Archive);
+            assert_eq!(result.metadata.additional["entries"], serde_json::json!(["data.json", "docs/notes.md"]));
+            assert_eq!(result.metadata.additional["skipped"], serde_json::json!(["blob.bin", "inner.zip"]));
+            assert!(!result.metadata.additional.contains_key("failed"));
+
+            let paths: Vec<_> = result.chunks.iter().map(|c| c.metadata["archive_path"].as_str().unwrap()).collect();
+            assert!(paths.contains(&"docs/notes.md"));
+            assert!(paths.contains(&"data.json"));
+            assert_eq!(result.chunks.iter().find(|c| c.metadata["archive_path"] == "data.json").unwrap().metadata["source"], "json");
+
+            for (i, chunk) in result.chunks.iter().enumerate() {
+                assert!(chunk.id.starts_with("archive_"), "{}", chunk.id);
+                assert_eq!(chunk.metadata["chunk_index"], i);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_archive_total_bytes_limit_aborts() {
+            // Highly compressible, so the archive itself stays tiny
+            let zeros = vec![0u8; 64 * 1024];
+            let archive = build_zip(&[("a.md", &zeros), ("b.md", &zeros)]);
+            let file = write_temp(&archive);
+            let provider = ArchiveProvider::with_limits(ResourceLimits { max_total_bytes: 100 * 1024, ..ResourceLimits::default() });
+
+            let err = provider.process_content(file.path()).await.unwrap_err();
+            assert_eq!(err.downcast_ref::<LimitExceeded>(), Some(&LimitExceeded::TotalBytes(100 * 1024)));
+        }
+
+        #[tokio::test]
+        async fn test_archive_collects_entries_in_path_order() {
+            // The large first entry takes longest, so finishing order differs from path order
+            let long = "Paragraph of filler text.\n\n".repeat(2000);
+            let archive = build_zip(&[
+                ("zeta.md", long.as_bytes()),
+                ("beta/notes.md", b"# Beta"),
+                ("alpha.json", br#"{"name": "alpha"}"#),
+                ("beta.md", b"# Beta file"),
+            ]);
+            let file = write_temp(&archive);
+
+            let result = ArchiveProvider::new().process_content(file.path()).await.unwrap();
+
+            let expected = ["alpha.json", "beta.md", "beta/notes.md", "zeta.md"];
+            assert_eq!(result.metadata.additional["entries"], serde_json::json!(expected));
+
+            let mut paths: Vec<&str> = result.chunks.iter().map(|c| c.metadata["archive_path"].as_str().unwrap()).collect();
+            paths.dedup();
+            assert_eq!(paths, expected);
+            for (i, chunk) in result.chunks.iter().enumerate() {
+                assert_eq!(chunk.metadata["chunk_index"], i);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_archive_shares_dispatch_permits_without_waiting() {
+            use std::sync::Arc;
+            use tokio::sync::Semaphore;
+
+            let archive = build_zip(&[("a.md", b"# A"), ("b.md", b"# B"), ("c.md", b"# C")]);
+            let file = write_temp(&archive);
+            let permits = Arc::new(Semaphore::new(2));
+            let provider = ArchiveProvider::new().with_dispatch_permits(permits.clone()).unwrap();
+
+            // Every permit taken, e.g. by the uploads of a batch: the entries run one at a time
+            let held = permits.clone().acquire_many_owned(2).await.unwrap();
+            let result = tokio::time::timeout(std::time::Duration::from_secs(10), provider.process_content(file.path()))
+                .await
+                .expect("archive should not wait for a permit")
+                .unwrap();
+            assert_eq!(result.metadata.additional["entries"], serde_json::json!(["a.md", "b.md", "c.md"]));
+            drop(held);
+
+            provider.process_content(file.path()).await.unwrap();
+            assert_eq!(permits.available_permits(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_archive_chunks_record_their_entry_content_type() {
+            let mut pdf = Vec::new();
+            super::pdf_provider_tests::build_pdf(&["Quarterly figures"]).save_to(&mut pdf).unwrap();
+            let archive = build_zip(&[("report.pdf", &pdf), ("notes.md", b"# Notes\n\nMeeting notes.")]);
+            let file = write_temp(&archive);
+
+            let result = ArchiveProvider::new().process_content(file.path()).await.unwrap();
+
+            let content_type = |path: &str| {
+                let chunk = result.chunks.iter().find(|c| c.metadata["archive_path"] == path).unwrap();
+                chunk.metadata["content_type"].clone()
+            };
+            assert_eq!(content_type("report.pdf"), serde_json::json!(ContentType::Pdf));
+            assert_eq!(content_type("notes.md"), serde_json::json!(ContentType::Markdown));
+
+            // Entries get a title like an upload of their own, from the file name if need be
+            let title = |path: &str| result.chunks.iter().find(|c| c.metadata["archive_path"] == path).unwrap().metadata["title"].clone();
+            assert_eq!(title("report.pdf"), "Report");
+            assert_eq!(title("notes.md"), "Notes");
+            assert_eq!(result.metadata.content_type, ContentType::Archive);
+        }
+    }
+
+    mod traced_provider_tests {
+        use crate::models::ContentType;
+        use crate::providers::markdown::MarkdownProvider;
+        use crate::providers::json::JsonProvider;
+        use crate::providers::traced::TracedProvider;
+        use crate::providers::{fill_default_metadata, title_from_filename, ContentProvider};
+        use std::sync::Arc;
+        use tracing_test::traced_test;
+
+        #[tokio::test]
+        #[traced_test]
+        async fn test_traced_provider_logs_in_a_span() {
+            let provider = TracedProvider::new(ContentType::Markdown, "notes.md".to_string(), Arc::new(MarkdownProvider::new()));
+
+            let result = provider.process_bytes(b"# Notes\n\nSome text.").await.unwrap().unwrap();
+
+            assert_eq!(result.chunks.len(), 1);
+            assert!(logs_contain("provider{stage=\"process_bytes\" content_type=Markdown file_name=notes.md}"));
+            assert!(logs_contain("Processed content file_size=19 chunk_count=1"));
+        }
+
+        #[test]
+        fn test_title_from_filename() {
+            assert_eq!(title_from_filename("my_cool_doc.pdf").as_deref(), Some("My Cool Doc"));
+            assert_eq!(title_from_filename("/tmp/q3-report final.docx").as_deref(), Some("Q3 Report Final"));
+            assert_eq!(title_from_filename("notes").as_deref(), Some("Notes"));
+            assert_eq!(title_from_filename("__.txt"), None);
+        }
+
+        #[tokio::test]
+        async fn test_default_metadata_falls_back_to_file_name_title() {
+            let mut metadata = JsonProvider::new().process_bytes(br#"{"a": 1}"#).await.unwrap().unwrap().metadata;
+            fill_default_metadata(&mut metadata, "my_cool_doc.json");
+            assert_eq!(metadata.file_name.as_deref(), Some("my_cool_doc.json"));
+            assert_eq!(metadata.title.as_deref(), Some("My Cool Doc"));
+
+            // A title found in the document itself is kept
+            let mut metadata = MarkdownProvider::new().process_bytes(b"# Release Notes\n\nText.").await.unwrap().unwrap().metadata;
+            fill_default_metadata(&mut metadata, "notes.md");
+            assert_eq!(metadata.title.as_deref(), Some("Release Notes"));
+        }
+    }
+
+    #[async_trait]
+    impl ContentProvider for MockProvider {
+        async fn process_content(&self, _file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+            Ok(ContentProcessingResult {
+                success: true,
+                status: ProcessingStatus::Processed,
+                chunks: vec![],
+                metadata: ContentMetadata {
+                    content_type: ContentType::Unknown,
+                    file_name: None,
+                    file_size: None,
+                    created_at: None,
+                    modified_at: None,
+                    author: None,
+                    title: None,
+                    language: None,
+                    additional: HashMap::new(),
+                },
+                error: None,
+            })
+        }
+
+        async fn to_markdown_chunks(&self, _file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+            Ok(vec![])
+        }
+
+        async fn to_metadata(&self, _file_path: &Path) -> anyhow::Result<ContentMetadata> {
+            Ok(ContentMetadata {
+                content_type: ContentType::Unknown,
+                file_name: None,
+                file_size: None,
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: None,
+                language: None,
+                additional: HashMap::new(),
+            })
+        }
+
+        async fn to_embeddings(&self, _chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+            Ok(vec![])
+        }
+    }
+
+    mod language_detection_tests {
+        use super::*;
+        use crate::providers::language::detect_language;
+        use crate::providers::markdown::MarkdownProvider;
+
+        const FRENCH: &str = "Le petit chat dort sur le canapé pendant que les enfants jouent dans le jardin. \
+            Nous irons au marché demain matin pour acheter du pain et des légumes frais.";
+        const ENGLISH: &str = "The quick brown fox jumps over the lazy dog while the children play in the garden. \
+            We will go to the market tomorrow morning to buy fresh bread and vegetables.";
+
+        #[test]
+        fn test_detect_language_french_and_english() {
+            assert_eq!(detect_language(FRENCH).as_deref(), Some("fra"));
+            assert_eq!(detect_language(ENGLISH).as_deref(), Some("eng"));
+            assert_eq!(detect_language("ok"), None);
+        }
+
+        #[tokio::test]
+        async fn test_markdown_metadata_language() {
+            let provider = MarkdownProvider::new();
+            let content = format!("# Notes\n\n{}", FRENCH);
+
+            let result = provider.process_bytes(content.as_bytes()).await.unwrap().unwrap();
+            assert_eq!(result.metadata.language.as_deref(), Some("fra"));
+        }
+    }
+
+    mod text_decoding_tests {
+        use super::*;
+        use crate::providers::json::JsonProvider;
+        use crate::providers::markdown::MarkdownProvider;
+        use crate::providers::text;
+
+        fn utf16le_with_bom(s: &str) -> Vec<u8> {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(s.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+            bytes
+        }
+
+        #[tokio::test]
+        async fn test_markdown_reads_utf16le_file_with_bom() {
+            let provider = MarkdownProvider::new();
+            let file = tempfile::Builder::new().suffix(".md").tempfile().unwrap();
+            fs::write(file.path(), utf16le_with_bom("# Café\n\nNaïve résumé.")).await.unwrap();
+
+            let result = provider.process_content(file.path()).await.unwrap();
+            assert_eq!(result.chunks.len(), 1);
+            assert!(result.chunks[0].content.contains("Naïve résumé."));
+            assert!(!result.chunks[0].content.contains('\u{FEFF}'));
+            assert_eq!(result.metadata.title.as_deref(), Some("Café"));
+            assert_eq!(result.metadata.additional.get("encoding").unwrap(), "UTF-16LE");
+        }
+
+        #[tokio::test]
+        async fn test_json_process_bytes_decodes_latin1() {
+            let provider = JsonProvider::new();
+            let bytes = b"{\"city\": \"S\xe3o Paulo\", \"note\": \"caf\xe9 cr\xe8me\"}";
+
+            let result = provider.process_bytes(bytes).await.unwrap().unwrap();
+            assert!(result.chunks[0].content.contains("São Paulo"));
+            assert_eq!(result.metadata.additional.get("encoding").unwrap(), "windows-1252");
+        }
+
+        #[test]
+        fn test_decode_keeps_plain_utf8() {
+            let decoded = text::decode("plain — text".as_bytes());
+            assert_eq!(decoded.text, "plain — text");
+            assert_eq!(decoded.encoding.name(), "UTF-8");
+
+            let decoded = text::decode(b"\xEF\xBB\xBFwith bom");
+            assert_eq!(decoded.text, "with bom");
+            assert_eq!(decoded.encoding.name(), "UTF-8");
+        }
+    }
+
+
+    mod rtf_provider_tests {
+        use super::*;
+        use crate::providers::registry;
+        use crate::providers::rtf::{parse, RtfProvider};
+
+        const SAMPLE: &str = r"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\fswiss Helvetica;}}{\colortbl;\red255\green0\blue0;}
+{\info{\title Quarterly Memo}{\author Dana Reyes}}
+{\*\generator Riched20 10.0;}\f0\fs24 Quarterly results were strong.\par
+Caf\'e9 sales grew by 12\'80.\par
+\b Next steps:\b0  hire two engineers.\par
+}";
+
+        #[test]
+        fn test_rtf_extracts_body_text() {
+            let document = parse(SAMPLE.as_bytes()).unwrap();
+
+            assert_eq!(
+                document.text,
+                "Quarterly results were strong.\n\nCafé sales grew by 12€.\n\nNext steps: hire two engineers."
+            );
+            assert_eq!(document.info.get("title").map(String::as_str), Some("Quarterly Memo"));
+            assert_eq!(document.info.get("author").map(String::as_str), Some("Dana Reyes"));
+        }
+
+        #[test]
+        fn test_rtf_decodes_unicode_escapes() {
+            // A negative \uN, a surrogate pair, and \uc2 with two fallback bytes
+            let document = parse(br"{\rtf1 \u-3913?x \u-10179?\u-8704?\uc2\u20320\'c4\'e3!}").unwrap();
+            assert_eq!(document.text, "\u{f0b7}x 😀你!");
+        }
+
+        #[tokio::test]
+        async fn test_rtf_provider_chunks_fixture() {
+            let file = tempfile::Builder::new().suffix(".rtf").tempfile().unwrap();
+            fs::write(file.path(), SAMPLE).await.unwrap();
+
+            let result = RtfProvider::new().process_content(file.path()).await.unwrap();
+
+            assert!(result.success);
+            assert_eq!(result.chunks.len(), 1);
+            assert!(result.chunks[0].id.starts_with("rtf_"));
+            assert!(result.chunks[0].content.contains("Café sales grew by 12€."));
+            assert!(!result.chunks[0].content.contains("Helvetica"));
+            assert_eq!(result.metadata.content_type, ContentType::Document);
+            assert_eq!(result.metadata.title.as_deref(), Some("Quarterly Memo"));
+            assert_eq!(result.metadata.author.as_deref(), Some("Dana Reyes"));
+        }
+
+        #[test]
+        fn test_rtf_routes_by_extension_and_content() {
+            let (content_type, _) = registry::get_provider_by_extension("rtf").unwrap();
+            assert_eq!(content_type, ContentType::Document);
+
+            let (content_type, provider) = registry::detect_provider(SAMPLE.as_bytes()).unwrap();
+            assert_eq!(content_type, ContentType::Document);
+            let result = futures::executor::block_on(provider.process_bytes(SAMPLE.as_bytes())).unwrap().unwrap();
+            assert!(result.chunks[0].id.starts_with("rtf_"));
+        }
+    }
+
+
+    mod odt_provider_tests {
+        use super::*;
+        use crate::providers::odt::OdtProvider;
+        use crate::providers::registry;
+        use std::io::Write;
+
+        const CONTENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+  <office:body><office:text>
+    <text:h text:outline-level="1">Introduction</text:h>
+    <text:p>Welcome to the<text:s/>handbook.<text:note><text:note-body><text:p>A footnote.</text:p></text:note-body></text:note></text:p>
+    <text:h text:outline-level="2">Getting Started</text:h>
+    <text:p>Install the tools.</text:p>
+    <text:list><text:list-item><text:p>Editor</text:p></text:list-item><text:list-item><text:p>Compiler</text:p></text:list-item></text:list>
+  </office:text></office:body>
+</office:document-content>"#;
+
+        const META: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-meta xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <office:meta>
+    <dc:title>Team Handbook</dc:title>
+    <meta:initial-creator>Ana Ruiz</meta:initial-creator>
+    <dc:creator>Sam Lee</dc:creator>
+    <meta:creation-date>2024-03-15T09:30:00</meta:creation-date>
+    <dc:date>2024-04-01T12:00:00</dc:date>
+    <meta:keyword>onboarding</meta:keyword>
+    <meta:keyword>tools</meta:keyword>
+    <meta:document-statistic meta:page-count="2" meta:word-count="11"/>
+  </office:meta>
+</office:document-meta>"#;
+
+        /// An ODT package with the `mimetype` entry first and uncompressed,
+        /// as the format requires.
+        fn build_odt() -> Vec<u8> {
+            build_odt_with(CONTENT)
+        }
+
+        fn build_odt_with(content: &str) -> Vec<u8> {
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            {
+                let mut zip = zip::ZipWriter::new(&mut buffer);
+                let stored = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+                zip.start_file("mimetype", stored).unwrap();
+                zip.write_all(b"application/vnd.oasis.opendocument.text").unwrap();
+                for (name, data) in [("content.xml", content), ("meta.xml", META)] {
+                    zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+                    zip.write_all(data.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+            buffer.into_inner()
+        }
+
+        #[tokio::test]
+        async fn test_odt_chunks_by_heading() {
+            let file = tempfile::Builder::new().suffix(".odt").tempfile().unwrap();
+            fs::write(file.path(), build_odt()).await.unwrap();
+
+            let result = OdtProvider::new().process_content(file.path()).await.unwrap();
+
+            assert!(result.success);
+            assert_eq!(result.chunks.len(), 2);
+            assert_eq!(result.chunks[0].content, "# Introduction\n\nWelcome to the handbook.");
+            assert_eq!(result.chunks[0].metadata["section_title"], "Introduction");
+            assert_eq!(result.chunks[1].content, "## Getting Started\n\nInstall the tools.\n\n- Editor\n- Compiler");
+            assert_eq!(result.chunks[1].metadata["heading_level"], 2);
+            assert!(result.chunks.iter().all(|c| c.id.starts_with("odt_") && c.metadata["source"] == "odt"));
+        }
+
+        #[test]
+        fn test_odt_huge_space_count_is_capped() {
+            let content = CONTENT.replace("<text:s/>", r#"<text:s text:c="1000000000000"/>"#);
+
+            let chunks = OdtProvider::new().chunk_bytes(&build_odt_with(&content)).unwrap();
+
+            assert!(chunks[0].content.contains("Welcome to the "));
+            assert!(chunks[0].content.len() < 200);
+        }
+
+        #[tokio::test]
+        async fn test_odt_metadata_from_meta_xml() {
+            let file = tempfile::Builder::new().suffix(".odt").tempfile().unwrap();
+            fs::write(file.path(), build_odt()).await.unwrap();
+
+            let metadata = OdtProvider::new().to_metadata(file.path()).await.unwrap();
+
+            assert_eq!(metadata.content_type, ContentType::Document);
+            assert_eq!(metadata.title.as_deref(), Some("Team Handbook"));
+            assert_eq!(metadata.author.as_deref(), Some("Ana Ruiz"));
+            assert_eq!(metadata.created_at.as_deref(), Some("2024-03-15T09:30:00"));
+            assert_eq!(metadata.modified_at.as_deref(), Some("2024-04-01T12:00:00"));
+            assert_eq!(metadata.additional["last_modified_by"], "Sam Lee");
+            assert_eq!(metadata.additional["keywords"], "onboarding, tools");
+            assert_eq!(metadata.additional["page_count"], 2);
+        }
+
+        #[tokio::test]
+        async fn test_odt_detected_from_content() {
+            let (content_type, _) = registry::get_provider_by_extension("odt").unwrap();
+            assert_eq!(content_type, ContentType::Document);
+
+            let (content_type, provider) = registry::detect_provider(&build_odt()).unwrap();
+            assert_eq!(content_type, ContentType::Document);
+            let file = tempfile::Builder::new().tempfile().unwrap();
+            fs::write(file.path(), build_odt()).await.unwrap();
+            let chunks = provider.to_markdown_chunks(file.path()).await.unwrap();
+            assert!(chunks[0].id.starts_with("odt_"));
+        }
+    }
+
+
+    mod gzip_tests {
+        use crate::providers::gzip::{decompress_into, inner_name};
+        use crate::providers::limits::{LimitExceeded, ResourceLimits};
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        fn gzip(bytes: &[u8]) -> Vec<u8> {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes).unwrap();
+            encoder.finish().unwrap()
+        }
+
+        #[test]
+        fn test_inner_name_strips_gz_suffix() {
+            assert_eq!(inner_name("records.json.gz"), Some("records.json"));
+            assert_eq!(inner_name("NOTES.MD.GZ"), Some("NOTES.MD"));
+            assert_eq!(inner_name("records.json"), None);
+            assert_eq!(inner_name(".gz"), None);
+        }
+
+        #[test]
+        fn test_decompress_reads_concatenated_members() {
+            let mut bytes = gzip(b"first ");
+            bytes.extend(gzip(b"second"));
+
+            let mut output = Vec::new();
+            let written = decompress_into(&bytes, &mut output, &ResourceLimits::default(), None).unwrap();
+            assert_eq!(written, 12);
+            assert_eq!(output, b"first second");
+        }
+
+        #[test]
+        fn test_decompress_stops_at_total_bytes_limit() {
+            // A megabyte of zeros compresses to about a kilobyte
+            let bomb = gzip(&vec![0u8; 1024 * 1024]);
+            let limits = ResourceLimits { max_total_bytes: 64 * 1024, ..ResourceLimits::default() };
+
+            let err = decompress_into(&bomb, &mut Vec::new(), &limits, None).unwrap_err();
+            assert_eq!(err.downcast_ref::<LimitExceeded>(), Some(&LimitExceeded::TotalBytes(64 * 1024)));
+        }
+
+        #[test]
+        fn test_decompress_stops_past_max_bytes() {
+            let bytes = gzip(&vec![b'a'; 4096]);
+            let mut output = Vec::new();
+
+            let written = decompress_into(&bytes, &mut output, &ResourceLimits::default(), Some(100)).unwrap();
+            assert_eq!(written, 101);
+            assert_eq!(output.len(), 101);
+        }
+
+        #[test]
+        fn test_decompress_rejects_corrupt_data() {
+            assert!(decompress_into(b"not gzip at all", &mut Vec::new(), &ResourceLimits::default(), None).is_err());
+        }
+    }
+
+
+    mod toml_provider_tests {
+        use super::*;
+        use crate::providers::toml::TomlProvider;
+
+        const CARGO_TOML: &str = r#"[package]
+name = "p8fs-node"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+
+[[bin]]
+name = "p8fs-node"
+path = "src/main.rs"
+
+[[bin]]
+name = "p8fs-cli"
+path = "src/cli.rs"
+"#;
+
+        #[tokio::test]
+        async fn test_toml_provider_renders_package_table() {
+            let dir = tempfile::tempdir().unwrap();
+            let test_path = dir.path().join("Cargo.toml");
+            fs::write(&test_path, CARGO_TOML).await.unwrap();
+
+            let result = TomlProvider::new().process_content(&test_path).await.unwrap();
+
+            let chunk = |path: &str| result.chunks.iter().find(|c| c.metadata["path"] == path).map(|c| c.content.as_str());
+            assert_eq!(chunk("package.name"), Some("p8fs-node"));
+            assert_eq!(chunk("package.version"), Some("0.1.0"));
+            assert_eq!(chunk("package.edition"), Some("2021"));
+            assert_eq!(chunk("bin[1].path"), Some("src/cli.rs"));
+
+            assert_eq!(result.metadata.content_type, ContentType::StructuredData);
+            assert_eq!(result.metadata.additional["tables"], serde_json::json!(["package", "dependencies"]));
+            assert!(result.chunks.iter().all(|c| c.metadata["source"] == "toml"));
+            assert!(result.chunks.iter().all(|c| c.id.starts_with("toml_")));
+        }
+
+        #[test]
+        fn test_toml_datetimes_and_invalid_input() {
+            let provider = TomlProvider::new();
+
+            let value = provider.parse("released = 1979-05-27T07:32:00Z\nratio = 0.5").unwrap();
+            assert_eq!(value, serde_json::json!({ "released": "1979-05-27T07:32:00Z", "ratio": 0.5 }));
+
+            assert!(provider.chunk_str("[package\nname = ").is_err());
+        }
+    }
+
+
+    mod subtitle_tests {
+        use super::*;
+        use crate::providers::subtitle::{parse_timestamp, SubtitleProvider};
+
+        const SRT: &str = "1\r\n00:00:01,000 --> 00:00:04,500\r\n<i>Welcome</i> to the\r\nshow.\r\n\r\n\
+2\r\n00:00:05,000 --> 00:00:09,000\r\n{\\an8}Today: <font color=\"red\">Rust</font>.\r\n\r\n\
+3\r\n00:00:12,250 --> 00:00:15,000\r\nLet's begin.\r\n";
+
+        #[test]
+        fn test_parse_timestamp_to_milliseconds() {
+            assert_eq!(parse_timestamp("01:02:03,456"), Some(3_723_456));
+            assert_eq!(parse_timestamp("01:02:03.456"), Some(3_723_456));
+            assert_eq!(parse_timestamp("02:03.5"), Some(123_500));
+            assert_eq!(parse_timestamp("00:61:00,000"), None);
+            assert_eq!(parse_timestamp("soon"), None);
+        }
+
+        #[test]
+        fn test_subtitle_provider_groups_cues_by_duration() {
+            let chunks = SubtitleProvider::with_chunk_secs(10.0).chunk_str(SRT).unwrap();
+
+            assert_eq!(chunks.len(), 2);
+            assert_eq!(chunks[0].content, "Welcome to the show.\nToday: Rust.");
+            assert_eq!(chunks[0].metadata["start_time"], 1.0);
+            assert_eq!(chunks[0].metadata["end_time"], 9.0);
+            assert_eq!(chunks[0].metadata["cue_count"], 2);
+            assert_eq!(chunks[1].content, "Let's begin.");
+            assert_eq!(chunks[1].metadata["start_time"], 12.25);
+            assert_eq!(chunks[1].metadata["end_time"], 15.0);
+            assert!(chunks.iter().all(|c| c.id.starts_with("subtitle_")));
+
+            let chunks = SubtitleProvider::with_chunk_secs(60.0).chunk_str(SRT).unwrap();
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(chunks[0].metadata["cue_count"], 3);
+        }
+
+        #[tokio::test]
+        async fn test_subtitle_provider_reads_webvtt() {
+            let vtt = "WEBVTT - Episode 1\n\nNOTE written by hand\n\nintro\n\
+01:02.000 --> 01:05.500 align:start position:10%\n<v Ann>Hello &amp; welcome</v>\n\n\
+01:00:00.000 --> 01:00:02.000\n<c.loud>Goodbye</c>\n";
+
+            let result = SubtitleProvider::with_chunk_secs(30.0)
+                .process_bytes(vtt.as_bytes())
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert!(result.success);
+            assert_eq!(result.metadata.content_type, ContentType::Subtitle);
+            assert_eq!(result.metadata.additional["format"], "vtt");
+            assert_eq!(result.metadata.additional["cue_count"], 2);
+            assert_eq!(result.metadata.additional["duration"], 3602.0);
+            assert_eq!(result.chunks.len(), 2);
+            assert_eq!(result.chunks[0].content, "Hello & welcome");
+            assert_eq!(result.chunks[0].metadata["start_time"], 62.0);
+            assert_eq!(result.chunks[1].content, "Goodbye");
+            assert_eq!(result.chunks[1].metadata["start_time"], 3600.0);
         }
     }
 
@@ -0,0 +1,52 @@
+//! Decoding for text-based uploads that are not necessarily UTF-8.
+//!
+//! A byte order mark wins when present (UTF-8, UTF-16LE or UTF-16BE). Without
+//! one, valid UTF-8 is taken as is and anything else is handed to `chardetng`,
+//! which picks the most likely legacy encoding (e.g. `windows-1252` for
+//! Latin-1 text).
+
+use crate::models::ContentMetadata;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::path::Path;
+
+/// Text decoded from an upload, along with the encoding it was read as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: &'static Encoding,
+}
+
+impl DecodedText {
+    /// Records the detected encoding under `additional.encoding`.
+    pub fn record(&self, metadata: &mut ContentMetadata) {
+        metadata
+            .additional
+            .insert("encoding".to_string(), serde_json::json!(self.encoding.name()));
+    }
+}
+
+/// Decodes `bytes`, detecting the encoding. The byte order mark is not part
+/// of the returned text; malformed sequences become U+FFFD.
+pub fn decode(bytes: &[u8]) -> DecodedText {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return DecodedText { text: text.into_owned(), encoding };
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return DecodedText { text: text.to_string(), encoding: UTF_8 };
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    DecodedText { text: text.into_owned(), encoding }
+}
+
+/// Reads and decodes the file at `path`.
+pub async fn read(path: &Path) -> anyhow::Result<DecodedText> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(decode(&bytes))
+}
@@ -0,0 +1,161 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType};
+use crate::providers::json::JsonProvider;
+use crate::providers::limits::ResourceLimits;
+use crate::providers::{ids, outcome, text, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Chunks TOML by converting it to JSON and running it through
+/// `JsonProvider`: tables chunk like nested objects and arrays of tables like
+/// arrays of objects, with paths such as `package.name` or `bin[0].name`.
+pub struct TomlProvider {
+    limits: ResourceLimits,
+    json: JsonProvider,
+}
+
+impl TomlProvider {
+    pub fn new() -> Self {
+        Self {
+            limits: ResourceLimits::global(),
+            json: JsonProvider::new(),
+        }
+    }
+
+    pub fn with_limits(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            json: JsonProvider::with_limits(limits),
+        }
+    }
+
+    pub(crate) fn parse(&self, content: &str) -> anyhow::Result<Value> {
+        self.limits.check_total_bytes(content.len() as u64)?;
+
+        let mut value = toml_to_json(toml::Value::Table(content.parse()?));
+        self.json.truncate_depth(&mut value);
+        self.json.check_limits(&value, &mut 0)?;
+        Ok(value)
+    }
+
+    pub(crate) fn chunk_str(&self, content: &str) -> anyhow::Result<Vec<ContentChunk>> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let value = self.parse(content)?;
+
+        let mut chunks: Vec<ContentChunk> = self
+            .json
+            .group_chunks(self.json.extract_chunks(&value, String::new()))
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_path, content, mut metadata))| {
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("toml"));
+
+                ContentChunk {
+                    id: String::new(),
+                    content,
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("toml", &doc_hash, &mut chunks);
+        Ok(chunks)
+    }
+
+    /// The top-level tables (`package`, `dependencies`, ...) are listed in
+    /// `additional["tables"]`.
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        let value = self.parse(content)?;
+        let tables: Vec<&String> = value
+            .as_object()
+            .map(|object| object.iter().filter(|(_, value)| value.is_object()).map(|(key, _)| key).collect())
+            .unwrap_or_default();
+
+        let mut additional = HashMap::new();
+        additional.insert("tables".to_string(), serde_json::json!(tables));
+
+        Ok(ContentMetadata {
+            content_type: ContentType::StructuredData,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional,
+        })
+    }
+}
+
+/// Converts a TOML value to JSON. Dates and times, which JSON has no type
+/// for, become their TOML text (e.g. `1979-05-27T07:32:00Z`).
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => serde_json::json!(i),
+        // NaN and infinities have no JSON number form
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(f.to_string())),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table.into_iter().map(|(key, value)| (key, toml_to_json(value))).collect(),
+        ),
+    }
+}
+
+#[async_trait]
+impl ContentProvider for TomlProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::StructuredData, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        self.chunk_str(&content.text)
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.metadata_from_str(&content.text, file_name, file_metadata.len())?;
+        content.record(&mut metadata);
+        Ok(metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64).map(|mut metadata| {
+            content.record(&mut metadata);
+            metadata
+        });
+        let chunks = self.chunk_str(&content.text);
+
+        outcome::build(chunks, metadata, || {
+            let mut metadata = outcome::basic_metadata(ContentType::StructuredData, None, Some(bytes.len() as u64));
+            content.record(&mut metadata);
+            metadata
+        })
+        .map(Some)
+    }
+}
@@ -0,0 +1,167 @@
+use crate::models::{ChunkStrategy, ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
+use crate::providers::registry::ProviderFactory;
+use crate::providers::ContentProvider;
+use crate::services::Metrics;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{Instrument, Span};
+
+/// Wraps the provider chosen for an upload so that every call runs in a
+/// `provider` span carrying the stage, `content_type` and the upload's
+/// `file_name`. Processing and embedding log their size, chunk count and
+/// elapsed time at info; the smaller stages log their timing at debug.
+pub struct TracedProvider {
+    content_type: ContentType,
+    file_name: String,
+    inner: ProviderFactory,
+}
+
+impl TracedProvider {
+    pub fn new(content_type: ContentType, file_name: String, inner: ProviderFactory) -> Self {
+        Self { content_type, file_name, inner }
+    }
+
+    fn record_processing(&self, started: Instant) {
+        Metrics::global().record_processing(&format!("{:?}", self.content_type).to_lowercase(), started.elapsed());
+    }
+
+    fn span(&self, stage: &'static str) -> Span {
+        tracing::info_span!("provider", stage, content_type = ?self.content_type, file_name = %self.file_name)
+    }
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+fn log_processed(result: &anyhow::Result<ContentProcessingResult>, file_size: Option<u64>, started: Instant) {
+    match result {
+        Ok(result) if !result.success => tracing::warn!(
+            file_size,
+            elapsed_ms = elapsed_ms(started),
+            error = result.error.as_deref().unwrap_or_default(),
+            "Processing failed"
+        ),
+        Ok(result) => tracing::info!(
+            file_size,
+            chunk_count = result.chunks.len(),
+            elapsed_ms = elapsed_ms(started),
+            "Processed content"
+        ),
+        Err(err) => tracing::warn!(file_size, elapsed_ms = elapsed_ms(started), error = %err, "Processing failed"),
+    }
+}
+
+#[async_trait]
+impl ContentProvider for TracedProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        async {
+            let started = Instant::now();
+            let result = self.inner.process_content(file_path).await;
+
+            let file_size = result.as_ref().ok().and_then(|result| result.metadata.file_size);
+            log_processed(&result, file_size, started);
+            self.record_processing(started);
+            result
+        }
+        .instrument(self.span("process_content"))
+        .await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        async {
+            let started = Instant::now();
+            let chunks = self.inner.to_markdown_chunks(file_path).await?;
+
+            tracing::info!(chunk_count = chunks.len(), elapsed_ms = elapsed_ms(started), "Chunked content");
+            Ok(chunks)
+        }
+        .instrument(self.span("to_markdown_chunks"))
+        .await
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        async {
+            let started = Instant::now();
+            let metadata = self.inner.to_metadata(file_path).await?;
+
+            tracing::debug!(elapsed_ms = elapsed_ms(started), "Read metadata");
+            Ok(metadata)
+        }
+        .instrument(self.span("to_metadata"))
+        .await
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        async {
+            let started = Instant::now();
+            let result = self.inner.to_embeddings(chunks).await;
+
+            match &result {
+                Ok(_) => tracing::info!(chunk_count = chunks.len(), elapsed_ms = elapsed_ms(started), "Embedded chunks"),
+                Err(err) => tracing::warn!(chunk_count = chunks.len(), elapsed_ms = elapsed_ms(started), error = %err, "Embedding failed"),
+            }
+            result
+        }
+        .instrument(self.span("to_embeddings"))
+        .await
+    }
+
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<serde_json::Value>> {
+        async {
+            let started = Instant::now();
+            let raw = self.inner.raw_metadata(file_path).await?;
+
+            tracing::debug!(elapsed_ms = elapsed_ms(started), "Read raw metadata");
+            Ok(raw)
+        }
+        .instrument(self.span("raw_metadata"))
+        .await
+    }
+
+    /// Logged like `process_content` when the provider works from memory;
+    /// a provider that does not gets no log line, as nothing was processed.
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        async {
+            let started = Instant::now();
+            let Some(result) = self.inner.process_bytes(bytes).await.transpose() else {
+                return Ok(None);
+            };
+
+            log_processed(&result, Some(bytes.len() as u64), started);
+            self.record_processing(started);
+            result.map(Some)
+        }
+        .instrument(self.span("process_bytes"))
+        .await
+    }
+
+    /// Forwarded to the inner provider so its own streaming is kept; an
+    /// error part way through is logged in the `stream_chunks` span.
+    fn stream_chunks<'a>(&'a self, file_path: &'a Path) -> BoxStream<'a, anyhow::Result<ContentChunk>> {
+        let span = self.span("stream_chunks");
+        self.inner
+            .stream_chunks(file_path)
+            .inspect(move |chunk| {
+                if let Err(err) = chunk {
+                    span.in_scope(|| tracing::warn!(error = %err, "Streaming chunks failed"));
+                }
+            })
+            .boxed()
+    }
+
+    /// The inner provider's rechunking variant, traced the same way.
+    fn with_chunk_strategy(&self, strategy: ChunkStrategy) -> Option<Arc<dyn ContentProvider>> {
+        let inner = self.inner.with_chunk_strategy(strategy)?;
+        Some(Arc::new(TracedProvider::new(self.content_type.clone(), self.file_name.clone(), inner)))
+    }
+
+    /// The inner provider's grouping variant, traced the same way.
+    fn with_kind_grouping(&self, kind_summaries: bool) -> Option<Arc<dyn ContentProvider>> {
+        let inner = self.inner.with_kind_grouping(kind_summaries)?;
+        Some(Arc::new(TracedProvider::new(self.content_type.clone(), self.file_name.clone(), inner)))
+    }
+}
@@ -0,0 +1,236 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::document::read_docx_properties;
+use crate::providers::limits::env_or;
+use crate::providers::tables::WideTables;
+use crate::providers::{ids, outcome, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use calamine::{Data, Reader, Xlsx};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Chunks an Excel workbook sheet by sheet, each chunk a markdown table of up
+/// to `rows_per_chunk` rows. A detected header row is repeated in every chunk
+/// of its sheet, and windows wider than the table limit are cut into parts.
+pub struct XlsxProvider {
+    rows_per_chunk: usize,
+    tables: WideTables,
+}
+
+/// The used cells of one sheet, rendered to text.
+struct Sheet {
+    name: String,
+    /// Sheet row number (1-based) of each row, with its cells.
+    rows: Vec<(usize, Vec<String>)>,
+}
+
+impl XlsxProvider {
+    /// Uses `P8FS_XLSX_ROWS_PER_CHUNK` rows per chunk (default 50).
+    pub fn new() -> Self {
+        Self::with_rows_per_chunk(env_or("P8FS_XLSX_ROWS_PER_CHUNK", 50))
+    }
+
+    pub fn with_rows_per_chunk(rows_per_chunk: usize) -> Self {
+        Self {
+            rows_per_chunk: rows_per_chunk.max(1),
+            tables: WideTables::global(),
+        }
+    }
+
+    /// How windows with more columns than `tables.max_columns` are cut up.
+    pub fn wide_tables(mut self, tables: WideTables) -> Self {
+        self.tables = tables;
+        self
+    }
+
+    pub(crate) fn chunk_bytes(&self, bytes: &[u8]) -> anyhow::Result<Vec<ContentChunk>> {
+        let doc_hash = ids::doc_hash(bytes);
+        let mut chunks = Vec::new();
+
+        for (sheet_index, sheet) in read_sheets(bytes)?.into_iter().enumerate() {
+            let header = detect_header(&sheet.rows);
+            let body = &sheet.rows[header.is_some() as usize..];
+
+            // Markdown tables need a header, so unlabeled sheets get column letters
+            let width = sheet.rows.iter().map(|(_, cells)| cells.len()).max().unwrap_or(0);
+            let labels = match header {
+                Some((_, cells)) => cells.clone(),
+                None => (0..width).map(column_name).collect(),
+            };
+
+            for window in body.chunks(self.rows_per_chunk) {
+                let first_row = window.first().map_or(0, |(number, _)| *number);
+                let last_row = window.last().map_or(0, |(number, _)| *number);
+                let rows: Vec<Vec<String>> = window.iter().map(|(_, cells)| cells.clone()).collect();
+
+                for table in self.tables.render(&labels, &rows) {
+                    let i = chunks.len();
+
+                    let mut metadata = HashMap::new();
+                    metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                    metadata.insert("source".to_string(), serde_json::json!("xlsx"));
+                    metadata.insert("sheet_name".to_string(), serde_json::json!(sheet.name));
+                    metadata.insert("sheet_index".to_string(), serde_json::json!(sheet_index));
+                    metadata.insert("row_range".to_string(), serde_json::json!([first_row, last_row]));
+                    metadata.insert("header_row".to_string(), serde_json::json!(header.map(|(number, _)| number)));
+                    if let Some((part, parts)) = table.part {
+                        metadata.insert("table_part".to_string(), serde_json::json!(part));
+                        metadata.insert("table_parts".to_string(), serde_json::json!(parts));
+                    }
+
+                    chunks.push(ContentChunk {
+                        id: String::new(),
+                        content: format!("## {}\n\n{}", sheet.name, table.content),
+                        metadata,
+                    });
+                }
+            }
+        }
+
+        ids::assign_chunk_ids("xlsx", &doc_hash, &mut chunks);
+        Ok(chunks)
+    }
+
+    fn metadata_from_bytes(&self, bytes: &[u8], file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        // Workbooks use the same OPC property parts as DOCX
+        let properties = read_docx_properties(bytes)?;
+        let property = |part: &str, name: &str| {
+            properties[part][name]
+                .as_str()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        let sheets = read_sheets(bytes)?;
+        let mut additional = HashMap::new();
+        additional.insert(
+            "sheet_names".to_string(),
+            serde_json::json!(sheets.iter().map(|sheet| &sheet.name).collect::<Vec<_>>()),
+        );
+        additional.insert(
+            "empty_sheets".to_string(),
+            serde_json::json!(sheets.iter().filter(|sheet| sheet.rows.is_empty()).map(|sheet| &sheet.name).collect::<Vec<_>>()),
+        );
+
+        Ok(ContentMetadata {
+            content_type: ContentType::Spreadsheet,
+            file_name,
+            file_size: Some(file_size),
+            created_at: property("core", "dcterms:created"),
+            modified_at: property("core", "dcterms:modified"),
+            author: property("core", "dc:creator"),
+            title: property("core", "dc:title"),
+            language: property("core", "dc:language"),
+            additional,
+        })
+    }
+}
+
+/// Every sheet in workbook order. Rows with no values are dropped, so an
+/// empty sheet has no rows.
+fn read_sheets(bytes: &[u8]) -> anyhow::Result<Vec<Sheet>> {
+    let mut workbook: Xlsx<_> = Xlsx::new(Cursor::new(bytes))?;
+    let mut sheets = Vec::new();
+
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)?;
+        let first_row = range.start().map_or(0, |(row, _)| row as usize);
+
+        let rows = range
+            .rows()
+            .enumerate()
+            .map(|(i, cells)| (first_row + i + 1, cells.iter().map(format_cell).collect::<Vec<_>>()))
+            .filter(|(_, cells)| cells.iter().any(|cell| !cell.is_empty()))
+            .collect();
+
+        sheets.push(Sheet { name, rows });
+    }
+
+    Ok(sheets)
+}
+
+/// Renders a cell the way it reads in Excel: whole numbers without a
+/// fractional part and dates as ISO 8601 (date only at midnight).
+pub(crate) fn format_cell(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.trim().to_string(),
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => f.to_string(),
+        Data::Bool(b) => b.to_string().to_uppercase(),
+        // The serial's whole part is the day and its fraction the time of day
+        Data::DateTime(date) => match date.as_datetime() {
+            Some(datetime) if date.as_f64().fract() == 0.0 => datetime.date().to_string(),
+            Some(datetime) => datetime.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => date.as_f64().to_string(),
+        },
+        Data::DateTimeIso(s) | Data::DurationIso(s) => s.clone(),
+        Data::Error(error) => format!("#{:?}", error),
+    }
+}
+
+/// The first row is taken as a header when every cell in it is non-empty
+/// text and at least one row follows it.
+fn detect_header(rows: &[(usize, Vec<String>)]) -> Option<&(usize, Vec<String>)> {
+    let (first, rest) = rows.split_first()?;
+    let is_header = !rest.is_empty()
+        && first.1.iter().all(|cell| !cell.is_empty() && cell.parse::<f64>().is_err());
+
+    is_header.then_some(first)
+}
+
+/// Excel's column letters: A, B, ..., Z, AA, AB, ...
+fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        name.push(b'A' + ((n - 1) % 26) as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+#[async_trait]
+impl ContentProvider for XlsxProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Spreadsheet, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let provider = Self::with_rows_per_chunk(self.rows_per_chunk).wide_tables(self.tables);
+
+        tokio::task::spawn_blocking(move || provider.chunk_bytes(&file_bytes)).await?
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        self.metadata_from_bytes(&file_bytes, file_name, file_bytes.len() as u64)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn raw_metadata(&self, file_path: &Path) -> anyhow::Result<Option<Value>> {
+        let file_bytes = tokio::fs::read(file_path).await?;
+
+        let properties = tokio::task::spawn_blocking(move || read_docx_properties(&file_bytes)).await??;
+
+        Ok(Some(properties))
+    }
+}
@@ -0,0 +1,215 @@
+use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType, InputType, ProcessingStatus};
+use crate::providers::json::JsonProvider;
+use crate::providers::limits::ResourceLimits;
+use crate::providers::{ids, outcome, text, ContentProvider};
+use crate::services::EmbeddingService;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Chunks YAML by converting each document to JSON and running it through
+/// `JsonProvider`, so `kind` objects are chunked the same way in both formats.
+pub struct YamlProvider {
+    limits: ResourceLimits,
+    json: JsonProvider,
+}
+
+impl YamlProvider {
+    pub fn new() -> Self {
+        Self {
+            limits: ResourceLimits::global(),
+            json: JsonProvider::new(),
+        }
+    }
+
+    pub fn with_limits(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            json: JsonProvider::with_limits(limits),
+        }
+    }
+
+    /// Groups chunks by `kind` across every document in the stream; see
+    /// `JsonProvider::group_by_kind`.
+    pub fn group_by_kind(mut self, group_by_kind: bool) -> Self {
+        self.json = self.json.group_by_kind(group_by_kind);
+        self
+    }
+
+    pub fn kind_summaries(mut self, kind_summaries: bool) -> Self {
+        self.json = self.json.kind_summaries(kind_summaries);
+        self
+    }
+
+    /// Parses every document in a `---` separated stream. Empty documents are skipped.
+    pub(crate) fn parse_documents(&self, content: &str) -> anyhow::Result<Vec<Value>> {
+        self.limits.check_total_bytes(content.len() as u64)?;
+
+        let mut documents = Vec::new();
+        let mut entries = 0;
+        for document in serde_yaml::Deserializer::from_str(content) {
+            let mut value = serde_yaml::Value::deserialize(document)?;
+            value.apply_merge()?;
+
+            let mut value = yaml_to_json(value);
+            if value.is_null() {
+                continue;
+            }
+
+            self.json.truncate_depth(&mut value);
+            self.json.check_limits(&value, &mut entries)?;
+            documents.push(value);
+        }
+
+        Ok(documents)
+    }
+
+    /// Chunks each document separately; `document_index` in the chunk metadata
+    /// groups chunks by the document they came from.
+    pub(crate) fn chunk_str(&self, content: &str) -> anyhow::Result<Vec<ContentChunk>> {
+        let doc_hash = ids::doc_hash(content.as_bytes());
+        let mut raw_chunks = Vec::new();
+
+        for (document_index, document) in self.parse_documents(content)?.iter().enumerate() {
+            for (path, content, mut metadata) in self.json.extract_chunks(document, String::new()) {
+                metadata.insert("document_index".to_string(), serde_json::json!(document_index));
+                raw_chunks.push((path, content, metadata));
+            }
+        }
+
+        let mut chunks: Vec<ContentChunk> = self
+            .json
+            .group_chunks(raw_chunks)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_path, content, mut metadata))| {
+                metadata.insert("chunk_index".to_string(), serde_json::json!(i));
+                metadata.insert("source".to_string(), serde_json::json!("yaml"));
+
+                ContentChunk {
+                    id: String::new(),
+                    content,
+                    metadata,
+                }
+            })
+            .collect();
+
+        ids::assign_chunk_ids("yaml", &doc_hash, &mut chunks);
+        Ok(chunks)
+    }
+
+    fn metadata_from_str(&self, content: &str, file_name: Option<String>, file_size: u64) -> anyhow::Result<ContentMetadata> {
+        let mut additional = HashMap::new();
+        additional.insert("document_count".to_string(), serde_json::json!(self.parse_documents(content)?.len()));
+
+        Ok(ContentMetadata {
+            content_type: ContentType::Yaml,
+            file_name,
+            file_size: Some(file_size),
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: None,
+            language: None,
+            additional,
+        })
+    }
+}
+
+/// Converts a YAML value to JSON. Non-string keys become their YAML text and
+/// tags (e.g. CloudFormation's `!Ref`) are dropped in favour of the tagged value.
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    use serde_yaml::Value as Yaml;
+
+    match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                serde_json::json!(i)
+            } else if let Some(u) = n.as_u64() {
+                serde_json::json!(u)
+            } else {
+                // NaN and infinities have no JSON number form
+                n.as_f64()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .unwrap_or_else(|| Value::String(n.to_string()))
+            }
+        }
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(items) => Value::Array(items.into_iter().map(yaml_to_json).collect()),
+        Yaml::Mapping(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (yaml_key(key), yaml_to_json(value)))
+                .collect(),
+        ),
+        Yaml::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+fn yaml_key(key: serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(s) => s,
+        other => serde_yaml::to_string(&other).unwrap_or_default().trim_end().to_string(),
+    }
+}
+
+#[async_trait]
+impl ContentProvider for YamlProvider {
+    async fn process_content(&self, file_path: &Path) -> anyhow::Result<ContentProcessingResult> {
+        let chunks = self.to_markdown_chunks(file_path).await;
+        let metadata = self.to_metadata(file_path).await;
+
+        outcome::build_for_file(ContentType::Yaml, file_path, chunks, metadata).await
+    }
+
+    async fn to_markdown_chunks(&self, file_path: &Path) -> anyhow::Result<Vec<ContentChunk>> {
+        let content = text::read(file_path).await?;
+        self.chunk_str(&content.text)
+    }
+
+    async fn to_metadata(&self, file_path: &Path) -> anyhow::Result<ContentMetadata> {
+        let file_metadata = tokio::fs::metadata(file_path).await?;
+        let content = text::read(file_path).await?;
+        let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string());
+
+        let mut metadata = self.metadata_from_str(&content.text, file_name, file_metadata.len())?;
+        content.record(&mut metadata);
+        Ok(metadata)
+    }
+
+    async fn to_embeddings(&self, chunks: &[ContentChunk]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let service = EmbeddingService::global().await?;
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let response = service.embed(texts, Some(InputType::Passage)).await?;
+
+        response.data.into_iter().map(|d| d.embedding.into_floats()).collect()
+    }
+
+    async fn process_bytes(&self, bytes: &[u8]) -> anyhow::Result<Option<ContentProcessingResult>> {
+        let content = text::decode(bytes);
+        let metadata = self.metadata_from_str(&content.text, None, bytes.len() as u64).map(|mut metadata| {
+            content.record(&mut metadata);
+            metadata
+        });
+        let chunks = self.chunk_str(&content.text);
+
+        outcome::build(chunks, metadata, || {
+            let mut metadata = outcome::basic_metadata(ContentType::Yaml, None, Some(bytes.len() as u64));
+            content.record(&mut metadata);
+            metadata
+        })
+        .map(Some)
+    }
+
+    fn with_kind_grouping(&self, kind_summaries: bool) -> Option<Arc<dyn ContentProvider>> {
+        Some(Arc::new(
+            YamlProvider::with_limits(self.limits).group_by_kind(true).kind_summaries(kind_summaries),
+        ))
+    }
+}
@@ -1,68 +1,336 @@
-use crate::models::EmbeddingResponse;
-use embed_anything::embeddings::embed::TextEmbedder;
-use once_cell::sync::OnceCell;
+use crate::models::{EmbeddingData, EmbeddingResponse, InputType};
+use crate::services::model_registry::ModelRegistry;
+use crate::services::{Metrics, RetryPolicy};
+use async_trait::async_trait;
+use embed_anything::embeddings::embed::{EmbeddingResult, TextEmbedder};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokenizers::Tokenizer;
 
-static EMBEDDING_SERVICE: OnceCell<Arc<Mutex<EmbeddingService>>> = OnceCell::new();
+/// A loaded model that turns a batch of texts into vectors.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<EmbeddingResult>>;
+
+    /// Number of tokens the model sees for `text`, as reported in `Usage`.
+    fn count_tokens(&self, text: &str) -> anyhow::Result<usize>;
+}
+
+/// A Hugging Face model together with the tokenizer it was trained with.
+pub struct HfEmbedder {
+    model: TextEmbedder,
+    tokenizer: Tokenizer,
+}
+
+impl HfEmbedder {
+    /// Loads the model and its `tokenizer.json` from the hub.
+    pub fn from_pretrained(model_name: &str) -> anyhow::Result<Self> {
+        let model = TextEmbedder::from_pretrained_hf(model_name, model_name, None, None, None)?;
+        let tokenizer = Tokenizer::from_pretrained(model_name, None)
+            .map_err(|e| anyhow::anyhow!("Failed to load tokenizer for {}: {}", model_name, e))?;
+
+        Ok(Self { model, tokenizer })
+    }
+
+    /// Loads the model from a local directory holding `config.json`,
+    /// `tokenizer.json` and `model.safetensors`, without network access. A
+    /// missing directory or file is an error; the hub is never tried instead.
+    pub fn from_local(dir: &Path) -> anyhow::Result<Self> {
+        if !dir.is_dir() {
+            anyhow::bail!("Local model directory {} does not exist", dir.display());
+        }
+        for file in LOCAL_MODEL_FILES {
+            if !dir.join(file).is_file() {
+                anyhow::bail!("Local model directory {} has no {}", dir.display(), file);
+            }
+        }
+
+        let repo_id = link_into_hub_cache(dir)?;
+        let model = TextEmbedder::from_pretrained_hf(&repo_id, &repo_id, Some(LOCAL_REVISION), None, None)?;
+        let tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(|e| anyhow::anyhow!("Failed to load tokenizer from {}: {}", dir.display(), e))?;
+
+        Ok(Self { model, tokenizer })
+    }
+}
+
+/// The files `HfEmbedder::from_local` needs in a model directory.
+const LOCAL_MODEL_FILES: [&str; 3] = ["config.json", "tokenizer.json", "model.safetensors"];
+
+/// The revision local models are filed under in the hub cache.
+const LOCAL_REVISION: &str = "local";
+
+/// `embed_anything` only loads models through the Hugging Face hub client,
+/// which serves files from its cache before trying the network. Links the
+/// model files into that cache (`$HF_HOME/hub`, or `~/.cache/huggingface/hub`)
+/// as revision `local` of the repo `local/<directory name>-<path hash>`, and
+/// returns the repo ID. The hash is of the canonical path, so two model
+/// directories with the same name never share, and overwrite, one repo.
+fn link_into_hub_cache(dir: &Path) -> anyhow::Result<String> {
+    let dir = dir.canonicalize()?;
+    let name = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Local model directory {} has no usable name", dir.display()))?;
+    let name = local_repo_name(&dir, name);
+
+    let hub = match env::var("HF_HOME") {
+        Ok(home) => PathBuf::from(home),
+        Err(_) => PathBuf::from(env::var("HOME")?).join(".cache").join("huggingface"),
+    }
+    .join("hub");
+    let repo = hub.join(format!("models--local--{}", name));
+    let snapshot = repo.join("snapshots").join(LOCAL_REVISION);
+
+    std::fs::create_dir_all(&snapshot)?;
+    std::fs::create_dir_all(repo.join("refs"))?;
+    std::fs::write(repo.join("refs").join(LOCAL_REVISION), LOCAL_REVISION)?;
+    for file in LOCAL_MODEL_FILES {
+        let link = snapshot.join(file);
+        if link.symlink_metadata().is_ok() {
+            std::fs::remove_file(&link)?;
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join(file), &link)?;
+        #[cfg(not(unix))]
+        std::fs::copy(dir.join(file), &link)?;
+    }
+
+    Ok(format!("local/{}", name))
+}
+
+/// `name` qualified by a short hash of the canonical `dir` it came from.
+pub(crate) fn local_repo_name(dir: &Path, name: &str) -> String {
+    let hash = blake3::hash(dir.to_string_lossy().as_bytes()).to_hex();
+    format!("{}-{}", name, &hash[..16])
+}
+
+#[async_trait]
+impl Embedder for HfEmbedder {
+    async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<EmbeddingResult>> {
+        self.model.embed(texts, None, None).await
+    }
+
+    fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+        count_tokens(&self.tokenizer, text)
+    }
+}
+
+/// Converts the model's output for input `index`. Only dense vectors can be
+/// returned to clients.
+fn embedding_data(index: usize, result: EmbeddingResult) -> anyhow::Result<EmbeddingData> {
+    match result {
+        EmbeddingResult::DenseVector(embedding) => Ok(EmbeddingData {
+            object: "embedding".to_string(),
+            embedding: embedding.into(),
+            index,
+        }),
+        _ => anyhow::bail!("Input {} produced a multi-vector embedding, expected a dense vector", index),
+    }
+}
+
+/// Length of `tokenizer`'s encoding of `text`, including any special tokens
+/// it adds around the input.
+pub fn count_tokens(tokenizer: &Tokenizer, text: &str) -> anyhow::Result<usize> {
+    let encoding = tokenizer.encode(text, true).map_err(|e| anyhow::anyhow!(e))?;
+    Ok(encoding.len())
+}
+
+/// Per-model prefixes from `EMBEDDING_PREFIXES`, a JSON object mapping model
+/// names to `{"query": ..., "passage": ...}`.
+static CONFIGURED_PREFIXES: Lazy<HashMap<String, InputPrefixes>> = Lazy::new(|| {
+    let Ok(json) = env::var("EMBEDDING_PREFIXES") else {
+        return HashMap::new();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring EMBEDDING_PREFIXES: {}", e);
+        HashMap::new()
+    })
+});
+
+/// The instructions a model trained for asymmetric retrieval expects in
+/// front of queries and of the passages searched. Empty for models that take
+/// raw text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct InputPrefixes {
+    pub query: String,
+    pub passage: String,
+}
+
+impl InputPrefixes {
+    /// The prefixes for `model_name`: its `EMBEDDING_PREFIXES` entry (by full
+    /// or short name) if it has one, else those of a known model family
+    /// (e5, English bge, nomic-embed), else none.
+    pub fn for_model(model_name: &str) -> Self {
+        let short_name = model_name.rsplit('/').next().unwrap_or(model_name);
+        let configured = CONFIGURED_PREFIXES
+            .iter()
+            .find(|(name, _)| *name == model_name || name.rsplit('/').next() == Some(short_name));
+        if let Some((_, prefixes)) = configured {
+            return prefixes.clone();
+        }
+
+        let family = short_name.to_lowercase();
+        let (query, passage) = if family.starts_with("bge-") && family.contains("-en") {
+            ("Represent this sentence for searching relevant passages: ", "")
+        } else if family.starts_with("nomic-embed") {
+            ("search_query: ", "search_document: ")
+        } else if family.starts_with("e5-") || family.contains("-e5-") {
+            ("query: ", "passage: ")
+        } else {
+            ("", "")
+        };
+        Self { query: query.to_string(), passage: passage.to_string() }
+    }
+
+    /// The prefix for inputs of `input_type`; none when it is not given.
+    pub fn for_input(&self, input_type: Option<InputType>) -> &str {
+        match input_type {
+            Some(InputType::Query) => &self.query,
+            Some(InputType::Passage) => &self.passage,
+            None => "",
+        }
+    }
+}
 
 pub struct EmbeddingService {
-    embedder: TextEmbedder,
+    embedder: Box<dyn Embedder>,
     model_name: String,
     dimensions: usize,
+    dimension_check: tokio::sync::OnceCell<Option<DimensionMismatch>>,
+    /// Applied to each call to the embedder.
+    retry: RetryPolicy,
+    prefixes: InputPrefixes,
+}
+
+/// `EMBEDDING_DIMENSIONS` disagrees with the length of the vectors the model
+/// actually produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DimensionMismatch {
+    pub configured: usize,
+    pub actual: usize,
+}
+
+impl DimensionMismatch {
+    pub fn check(configured: usize, actual: usize) -> Option<Self> {
+        (configured != actual).then_some(Self { configured, actual })
+    }
 }
 
 impl EmbeddingService {
-    pub fn new() -> anyhow::Result<Self> {
-        let model_name = env::var("EMBEDDING_MODEL")
-            .unwrap_or_else(|_| "sentence-transformers/all-MiniLM-L6-v2".to_string());
-        
-        let dimensions = env::var("EMBEDDING_DIMENSIONS")
-            .unwrap_or_else(|_| "384".to_string())
-            .parse::<usize>()
-            .unwrap_or(384);
+    /// Loads the model files in `dir` (see `HfEmbedder::from_local`), served
+    /// under `model_name`.
+    pub fn with_local_model(model_name: &str, dir: &Path, dimensions: usize) -> anyhow::Result<Self> {
+        let embedder = HfEmbedder::from_local(dir)
+            .map_err(|e| anyhow::anyhow!("Failed to load EMBEDDING_MODEL_PATH for {}: {}", model_name, e))?;
+        let short_model_name = model_name.rsplit('/').next().unwrap_or(model_name);
 
-        let embedder = TextEmbedder::from_pretrained_hf(&model_name, &model_name, None, None, None)?;
+        Ok(Self::from_embedder(short_model_name, dimensions, Box::new(embedder)))
+    }
+
+    /// Loads `model_name` from the Hugging Face hub, expecting it to produce
+    /// vectors of `dimensions` values.
+    pub fn with_model(model_name: &str, dimensions: usize) -> anyhow::Result<Self> {
+        let embedder = HfEmbedder::from_pretrained(model_name)?;
         
         let short_model_name = model_name
             .split('/')
             .last()
-            .unwrap_or(&model_name)
+            .unwrap_or(model_name)
             .to_string();
         
-        Ok(Self {
+        Ok(Self::from_embedder(&short_model_name, dimensions, Box::new(embedder)))
+    }
+
+    /// Wraps an already loaded embedder, reported in responses as `model_name`
+    /// and prefixing inputs as `InputPrefixes::for_model(model_name)` says.
+    pub fn from_embedder(model_name: &str, dimensions: usize, embedder: Box<dyn Embedder>) -> Self {
+        Self {
             embedder,
-            model_name: short_model_name,
+            model_name: model_name.to_string(),
             dimensions,
-        })
+            dimension_check: tokio::sync::OnceCell::new(),
+            retry: RetryPolicy::none(),
+            prefixes: InputPrefixes::for_model(model_name),
+        }
+    }
+
+    /// Retries failed calls to the embedder. Local models such as `HfEmbedder`
+    /// fail the same way every time, so only an embedder that calls a remote
+    /// service should need this.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_prefixes(mut self, prefixes: InputPrefixes) -> Self {
+        self.prefixes = prefixes;
+        self
     }
 
-    pub async fn embed(&self, texts: Vec<String>) -> anyhow::Result<EmbeddingResponse> {
+    pub fn prefixes(&self) -> &InputPrefixes {
+        &self.prefixes
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Embeds a probe string on first call to compare the model's real output
+    /// dimension with the configured one, logging a warning on a mismatch.
+    /// The outcome is cached for the life of the service.
+    pub async fn dimension_mismatch(&self) -> anyhow::Result<Option<DimensionMismatch>> {
+        self.dimension_check
+            .get_or_try_init(|| async {
+                let probe = self.embed(vec!["dimension probe".to_string()], None).await?;
+                let actual = probe.data.first().map_or(0, |data| data.embedding.len());
+
+                let mismatch = DimensionMismatch::check(self.dimensions, actual);
+                if let Some(mismatch) = mismatch {
+                    tracing::warn!(
+                        "EMBEDDING_DIMENSIONS is {} but model {} produces {} dimensions",
+                        mismatch.configured,
+                        self.model_name,
+                        mismatch.actual
+                    );
+                }
+                Ok(mismatch)
+            })
+            .await
+            .copied()
+    }
+
+    /// Embeds `texts`, each prefixed with the model's instruction for
+    /// `input_type`. Usage counts the prefixed texts, as the model sees them.
+    pub async fn embed(&self, texts: Vec<String>, input_type: Option<InputType>) -> anyhow::Result<EmbeddingResponse> {
+        Metrics::global().record_embedding_batch(texts.len());
+        let texts = self.with_prefix(texts, input_type);
         let text_refs: Vec<&str> = texts.iter().map(|s| s.as_str()).collect();
-        let embeddings = self.embedder.embed(&text_refs, None, None).await?;
-        
-        let data: Vec<crate::models::EmbeddingData> = embeddings
+        let embeddings = self.embed_batch(&text_refs).await?;
+
+        let data = embeddings
             .into_iter()
             .enumerate()
-            .map(|(index, embedding_result)| {
-                use embed_anything::embeddings::embed::EmbeddingResult;
-                let embedding = match embedding_result {
-                    EmbeddingResult::DenseVector(vec) => vec,
-                    _ => panic!("Unexpected embedding result type"),
-                };
-                crate::models::EmbeddingData {
-                    object: "embedding".to_string(),
-                    embedding,
-                    index,
-                }
-            })
-            .collect();
+            .map(|(index, result)| embedding_data(index, result))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut total_tokens = 0;
+        for text in &texts {
+            total_tokens += self.embedder.count_tokens(text)?;
+        }
 
-        let total_tokens: usize = texts.iter().map(|t| t.split_whitespace().count()).sum();
-        
         Ok(EmbeddingResponse {
             object: "list".to_string(),
+            dimensions: data.first().map_or(0, |data| data.embedding.len()),
             data,
             model: self.model_name.clone(),
             usage: crate::models::Usage {
@@ -72,13 +340,59 @@ impl EmbeddingService {
         })
     }
 
-    pub fn global() -> Arc<Mutex<EmbeddingService>> {
-        EMBEDDING_SERVICE
-            .get_or_init(|| {
-                Arc::new(Mutex::new(
-                    EmbeddingService::new().expect("Failed to initialize embedding service"),
-                ))
-            })
-            .clone()
+    async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<EmbeddingResult>> {
+        let what = format!("Embedding {} texts with {}", texts.len(), self.model_name);
+        self.retry.run(&what, || self.embedder.embed_batch(texts)).await
+    }
+
+    /// Like `embed`, but a bad input only fails its own entry. The batch is
+    /// embedded in one call; if that call fails, each input is retried on its
+    /// own so the error can be attributed to the texts that caused it.
+    pub async fn embed_lenient(&self, texts: &[String], input_type: Option<InputType>) -> Vec<Result<EmbeddingData, String>> {
+        Metrics::global().record_embedding_batch(texts.len());
+        let texts = self.with_prefix(texts.to_vec(), input_type);
+        let text_refs: Vec<&str> = texts.iter().map(|s| s.as_str()).collect();
+
+        match self.embed_batch(&text_refs).await {
+            Ok(embeddings) if embeddings.len() == texts.len() => embeddings
+                .into_iter()
+                .enumerate()
+                .map(|(index, result)| embedding_data(index, result).map_err(|e| e.to_string()))
+                .collect(),
+            _ => {
+                let mut data = Vec::with_capacity(texts.len());
+                for (index, text) in text_refs.iter().enumerate() {
+                    let result = match self.embedder.embed_batch(&[text]).await {
+                        Ok(mut embeddings) if embeddings.len() == 1 => {
+                            embedding_data(index, embeddings.remove(0)).map_err(|e| e.to_string())
+                        }
+                        Ok(embeddings) => Err(format!("Expected 1 embedding, got {}", embeddings.len())),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    data.push(result);
+                }
+                data
+            }
+        }
+    }
+
+    fn with_prefix(&self, texts: Vec<String>, input_type: Option<InputType>) -> Vec<String> {
+        let prefix = self.prefixes.for_input(input_type);
+        if prefix.is_empty() {
+            return texts;
+        }
+        texts.into_iter().map(|text| format!("{}{}", prefix, text)).collect()
+    }
+
+    /// The default model's service if something has already loaded it,
+    /// without loading it.
+    pub fn try_global() -> Option<Arc<EmbeddingService>> {
+        ModelRegistry::global().try_get(None)
+    }
+
+    /// The default model's service, loading it on first use. A failed load is
+    /// returned as an error rather than cached, so the next call retries.
+    pub async fn global() -> anyhow::Result<Arc<EmbeddingService>> {
+        Ok(ModelRegistry::global().get(None).await?)
     }
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+use crate::models::EmbeddingResponse;
+use arrow::array::{ArrayRef, FixedSizeListArray, Float32Array, StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Media type of the Arrow IPC streaming format.
+pub const ARROW_STREAM_MIME: &str = "application/vnd.apache.arrow.stream";
+
+/// Writes an embedding response as a single-batch Arrow IPC stream with
+/// columns `id` (the input index), `vector` (a fixed-size list of f32) and
+/// `model`. Token usage is recorded in the schema metadata.
+pub fn embeddings_to_arrow_ipc(response: &EmbeddingResponse) -> anyhow::Result<Vec<u8>> {
+    let dimensions = response.data.first().map_or(0, |data| data.embedding.len());
+    if let Some(data) = response.data.iter().find(|data| data.embedding.len() != dimensions) {
+        anyhow::bail!(
+            "Embedding {} has {} dimensions, expected {}",
+            data.index,
+            data.embedding.len(),
+            dimensions
+        );
+    }
+
+    let item = Arc::new(Field::new("item", DataType::Float32, false));
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("vector", DataType::FixedSizeList(item.clone(), dimensions as i32), false),
+        Field::new("model", DataType::Utf8, false),
+    ])
+    .with_metadata(HashMap::from([
+        ("prompt_tokens".to_string(), response.usage.prompt_tokens.to_string()),
+        ("total_tokens".to_string(), response.usage.total_tokens.to_string()),
+    ]));
+
+    let ids = UInt64Array::from_iter_values(response.data.iter().map(|data| data.index as u64));
+    let mut values = Vec::with_capacity(response.data.len() * dimensions);
+    for data in &response.data {
+        values.extend_from_slice(&data.embedding.to_floats()?);
+    }
+    let values = Float32Array::from(values);
+    let vectors = FixedSizeListArray::try_new(item, dimensions as i32, Arc::new(values), None)?;
+    let models = StringArray::from_iter_values(response.data.iter().map(|_| response.model.as_str()));
+
+    let columns: Vec<ArrayRef> = vec![Arc::new(ids), Arc::new(vectors), Arc::new(models)];
+    let batch = RecordBatch::try_new(Arc::new(schema), columns)?;
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = StreamWriter::try_new(&mut buffer, &batch.schema())?;
+        writer.write(&batch)?;
+        writer.finish()?;
+    }
+
+    Ok(buffer)
+}
@@ -0,0 +1,109 @@
+use crate::models::{ContentProcessingResult, JobProgress, JobState, JobStatus};
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// How long a finished job is kept when no other TTL is given.
+pub const DEFAULT_JOB_TTL: Duration = Duration::from_secs(3600);
+
+/// In-memory registry of background processing jobs. Each job's state is held
+/// in a watch channel, so callers can follow it as it changes. A finished job
+/// is kept for `ttl` and removed the next time a job is created after that.
+pub struct JobStore {
+    jobs: DashMap<Uuid, JobEntry>,
+    ttl: Duration,
+}
+
+struct JobEntry {
+    state: watch::Sender<JobState>,
+    /// When the job became `Done` or `Failed`.
+    finished_at: Option<Instant>,
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::with_ttl(DEFAULT_JOB_TTL)
+    }
+}
+
+impl JobStore {
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self { jobs: DashMap::new(), ttl }
+    }
+
+    pub fn create(&self) -> JobState {
+        self.evict_expired();
+
+        let job = JobState {
+            job_id: Uuid::new_v4(),
+            status: JobStatus::Pending,
+            progress: JobProgress::default(),
+            result: None,
+            error: None,
+        };
+        self.jobs.insert(job.job_id, JobEntry { state: watch::channel(job.clone()).0, finished_at: None });
+        job
+    }
+
+    pub fn get(&self, job_id: &Uuid) -> Option<JobState> {
+        self.jobs.get(job_id).map(|job| job.state.borrow().clone())
+    }
+
+    /// The job's state now and each time it changes. The receiver sees the
+    /// latest state only, so updates in quick succession are coalesced.
+    pub fn subscribe(&self, job_id: &Uuid) -> Option<watch::Receiver<JobState>> {
+        self.jobs.get(job_id).map(|job| job.state.subscribe())
+    }
+
+    pub fn set_status(&self, job_id: &Uuid, status: JobStatus) {
+        self.update(job_id, |job| job.status = status);
+    }
+
+    pub fn set_progress(&self, job_id: &Uuid, progress: JobProgress) {
+        self.update(job_id, |job| job.progress = progress);
+    }
+
+    pub fn finish(&self, job_id: &Uuid, outcome: anyhow::Result<ContentProcessingResult>) {
+        if let Some(mut job) = self.jobs.get_mut(job_id) {
+            job.finished_at = Some(Instant::now());
+        }
+        self.update(job_id, |job| match outcome {
+            Ok(result) if !result.success => {
+                job.status = JobStatus::Failed;
+                job.error = result.error.clone();
+                job.result = Some(result);
+            }
+            Ok(result) => {
+                job.status = JobStatus::Done;
+                job.result = Some(result);
+            }
+            Err(err) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(err.to_string());
+            }
+        });
+    }
+
+    /// The number of jobs held, finished or not.
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    fn update(&self, job_id: &Uuid, modify: impl FnOnce(&mut JobState)) {
+        if let Some(job) = self.jobs.get(job_id) {
+            job.state.send_modify(modify);
+        }
+    }
+
+    /// Drops the jobs that finished more than `ttl` ago. Anyone following one
+    /// sees its channel close.
+    fn evict_expired(&self) {
+        self.jobs
+            .retain(|_, job| job.finished_at.is_none_or(|finished_at| finished_at.elapsed() < self.ttl));
+    }
+}
@@ -0,0 +1,91 @@
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+use std::time::Duration;
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// Process-wide Prometheus metrics, served by `GET /metrics`.
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    processing_duration: HistogramVec,
+    embedding_batch_size: Histogram,
+    cache_lookups: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let requests = IntCounterVec::new(
+            Opts::new("p8fs_http_requests_total", "HTTP requests by endpoint, method and status"),
+            &["endpoint", "method", "status"],
+        )
+        .unwrap();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("p8fs_http_request_duration_seconds", "HTTP request latency by endpoint"),
+            &["endpoint"],
+        )
+        .unwrap();
+        let processing_duration = HistogramVec::new(
+            HistogramOpts::new("p8fs_processing_duration_seconds", "Time a provider spent on one upload")
+                .buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0]),
+            &["content_type"],
+        )
+        .unwrap();
+        let embedding_batch_size = Histogram::with_opts(
+            HistogramOpts::new("p8fs_embedding_batch_size", "Texts per embedding call")
+                .buckets(prometheus::exponential_buckets(1.0, 2.0, 12).unwrap()),
+        )
+        .unwrap();
+        let cache_lookups = IntCounterVec::new(
+            Opts::new("p8fs_cache_lookups_total", "Cache lookups by cache and result (hit or miss)"),
+            &["cache", "result"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(request_duration.clone())).unwrap();
+        registry.register(Box::new(processing_duration.clone())).unwrap();
+        registry.register(Box::new(embedding_batch_size.clone())).unwrap();
+        registry.register(Box::new(cache_lookups.clone())).unwrap();
+
+        Self {
+            registry,
+            requests,
+            request_duration,
+            processing_duration,
+            embedding_batch_size,
+            cache_lookups,
+        }
+    }
+
+    pub fn global() -> &'static Metrics {
+        &METRICS
+    }
+
+    pub fn record_request(&self, endpoint: &str, method: &str, status: u16, elapsed: Duration) {
+        self.requests.with_label_values(&[endpoint, method, &status.to_string()]).inc();
+        self.request_duration.with_label_values(&[endpoint]).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_processing(&self, content_type: &str, elapsed: Duration) {
+        self.processing_duration.with_label_values(&[content_type]).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_embedding_batch(&self, size: usize) {
+        self.embedding_batch_size.observe(size as f64);
+    }
+
+    pub fn record_cache_lookup(&self, cache: &str, hit: bool) {
+        self.cache_lookups.with_label_values(&[cache, if hit { "hit" } else { "miss" }]).inc();
+    }
+
+    /// Every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
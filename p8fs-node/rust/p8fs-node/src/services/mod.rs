@@ -1,6 +1,22 @@
 pub mod embeddings;
+pub mod export;
+pub mod jobs;
+pub mod metrics;
+pub mod model_registry;
+pub mod postprocess;
+pub mod rerank;
+pub mod semantic;
+pub mod retry;
+pub mod sentences;
+pub mod vectors;
 
 #[cfg(test)]
 mod tests;
 
-pub use embeddings::EmbeddingService;
\ No newline at end of file
+pub use embeddings::EmbeddingService;
+pub use jobs::{JobStore, DEFAULT_JOB_TTL};
+pub use metrics::Metrics;
+pub use model_registry::ModelRegistry;
+pub use rerank::rerank;
+pub use retry::RetryPolicy;
+pub use vectors::{cosine_similarity, normalize_l2, truncate_embeddings, LengthMismatch};
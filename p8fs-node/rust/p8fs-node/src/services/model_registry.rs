@@ -0,0 +1,162 @@
+use crate::services::{EmbeddingService, Metrics, RetryPolicy};
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+static REGISTRY: Lazy<ModelRegistry> = Lazy::new(ModelRegistry::from_env);
+
+type Loader = Arc<dyn Fn() -> anyhow::Result<EmbeddingService> + Send + Sync>;
+
+struct ModelEntry {
+    loader: Loader,
+    /// Set by the first successful load. Concurrent first uses wait on the one
+    /// load in progress rather than starting their own.
+    service: OnceCell<Arc<EmbeddingService>>,
+}
+
+/// The embedding models a node can serve, keyed by name and loaded on first use.
+pub struct ModelRegistry {
+    default: String,
+    models: BTreeMap<String, ModelEntry>,
+    /// Applied to each load, since downloads from the hub can fail transiently.
+    retry: RetryPolicy,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    #[error("Unknown model '{name}'. Available models: {}", available.join(", "))]
+    Unknown { name: String, available: Vec<String> },
+    #[error(transparent)]
+    Load(#[from] anyhow::Error),
+}
+
+impl ModelRegistry {
+    /// An empty registry whose default is `default`, which must be registered
+    /// before it can be used.
+    pub fn new(default: &str) -> Self {
+        Self {
+            default: default.to_string(),
+            models: BTreeMap::new(),
+            retry: RetryPolicy::none(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Registers `EMBEDDING_MODEL` (the default, expected to produce
+    /// `EMBEDDING_DIMENSIONS`) and each `model` or `model=dimensions` entry of
+    /// the comma-separated `EMBEDDING_MODELS`. With `EMBEDDING_MODEL_PATH` set,
+    /// the default is loaded from that directory rather than the hub.
+    pub fn from_env() -> Self {
+        let default = env::var("EMBEDDING_MODEL")
+            .unwrap_or_else(|_| "sentence-transformers/all-MiniLM-L6-v2".to_string());
+        let dimensions = env::var("EMBEDDING_DIMENSIONS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(384);
+
+        let mut registry = Self::new(&default).with_retry(RetryPolicy::from_env());
+        match env::var("EMBEDDING_MODEL_PATH") {
+            Ok(path) => {
+                let model_name = default.clone();
+                registry.register(&default, move || {
+                    EmbeddingService::with_local_model(&model_name, Path::new(&path), dimensions)
+                });
+            }
+            Err(_) => registry.register_hf(&default, dimensions),
+        }
+
+        for entry in env::var("EMBEDDING_MODELS").unwrap_or_default().split(',') {
+            let (name, model_dimensions) = match entry.trim().split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim().parse().unwrap_or(dimensions)),
+                None => (entry.trim(), dimensions),
+            };
+            if !name.is_empty() && !registry.models.contains_key(name) {
+                registry.register_hf(name, model_dimensions);
+            }
+        }
+
+        registry
+    }
+
+    pub fn global() -> &'static ModelRegistry {
+        &REGISTRY
+    }
+
+    /// Registers a model under `name`; `loader` runs the first time it is used,
+    /// on the blocking thread pool since loading downloads and reads weights.
+    pub fn register<F>(&mut self, name: &str, loader: F)
+    where
+        F: Fn() -> anyhow::Result<EmbeddingService> + Send + Sync + 'static,
+    {
+        self.models.insert(
+            name.to_string(),
+            ModelEntry {
+                loader: Arc::new(loader),
+                service: OnceCell::new(),
+            },
+        );
+    }
+
+    fn register_hf(&mut self, name: &str, dimensions: usize) {
+        let model_name = name.to_string();
+        self.register(name, move || EmbeddingService::with_model(&model_name, dimensions));
+    }
+
+    /// Registered model names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.models.keys().cloned().collect()
+    }
+
+    /// The service for `name`, or the default model for `None`, loading it if
+    /// this is its first use. A name may also be given without its `org/` prefix.
+    ///
+    /// The loader and its retries run off the async workers, so a cold
+    /// download only holds up the callers waiting for that model.
+    pub async fn get(&self, name: Option<&str>) -> Result<Arc<EmbeddingService>, ModelError> {
+        let entry = self.entry(name)?;
+        Metrics::global().record_cache_lookup("embedding_model", entry.service.initialized());
+        let service = entry
+            .service
+            .get_or_try_init(|| async {
+                let what = format!("Loading model {}", name.unwrap_or(&self.default));
+                self.retry
+                    .run(&what, || {
+                        let loader = entry.loader.clone();
+                        async move { tokio::task::spawn_blocking(move || loader()).await? }
+                    })
+                    .await
+                    .map(Arc::new)
+            })
+            .await?;
+        Ok(service.clone())
+    }
+
+    /// Like `get`, but only returns a model that is already loaded.
+    pub fn try_get(&self, name: Option<&str>) -> Option<Arc<EmbeddingService>> {
+        self.entry(name).ok()?.service.get().cloned()
+    }
+
+    fn entry(&self, name: Option<&str>) -> Result<&ModelEntry, ModelError> {
+        let name = name.unwrap_or(&self.default);
+
+        self.models
+            .get(name)
+            .or_else(|| {
+                self.models
+                    .iter()
+                    .find(|(key, _)| key.rsplit('/').next() == Some(name))
+                    .map(|(_, entry)| entry)
+            })
+            .ok_or_else(|| ModelError::Unknown {
+                name: name.to_string(),
+                available: self.names(),
+            })
+    }
+}
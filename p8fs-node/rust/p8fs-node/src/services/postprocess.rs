@@ -0,0 +1,397 @@
+use crate::models::{ContentChunk, ContentProcessingResult, ContentType, EmbedFilter, OutputFormat, PriorChunk, ProcessingOptions};
+use crate::providers::ids;
+use crate::services::sentences::sentence_spans;
+use pulldown_cmark::{html, Event, Parser, TagEnd};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Words per shingle when comparing chunks for near duplicates.
+const SHINGLE_WORDS: usize = 3;
+/// Share of shingles two chunks must have in common (Jaccard similarity) for
+/// the later one to count as a duplicate.
+const DUPLICATE_SIMILARITY: f64 = 0.9;
+
+/// Applies request-level options to a provider result before it is returned.
+pub fn apply_options(result: &mut ContentProcessingResult, options: &ProcessingOptions) {
+    render_chunks(&mut result.chunks, options.output_format);
+
+    // After rendering, so the offsets index the content that is returned
+    if options.sentence_offsets {
+        attach_sentence_offsets(&mut result.chunks);
+    }
+}
+
+/// Records each chunk's `metadata["content_hash"]`. Run on the provider's
+/// markdown, so the hash does not depend on `output_format`.
+pub fn attach_content_hashes(chunks: &mut [ContentChunk]) {
+    for chunk in chunks.iter_mut() {
+        let hash = ids::content_hash(&chunk.content);
+        chunk.metadata.insert("content_hash".to_string(), serde_json::json!(hash));
+    }
+}
+
+/// Drops the chunks whose content hash matches one of `prior`, for a
+/// diff-based upsert. Matched prior IDs are listed in
+/// `additional["unchanged_chunk_ids"]` and the rest, whose content no longer
+/// appears, in `additional["deleted_chunk_ids"]`. Each prior chunk matches at
+/// most one new chunk, so duplicated content is counted correctly.
+pub fn keep_changed_chunks(result: &mut ContentProcessingResult, prior: &[PriorChunk]) {
+    let mut by_hash: HashMap<&str, VecDeque<&str>> = HashMap::new();
+    for chunk in prior {
+        by_hash.entry(chunk.content_hash.as_str()).or_default().push_back(chunk.id.as_str());
+    }
+
+    let mut unchanged: Vec<&str> = Vec::new();
+    result.chunks.retain(|chunk| {
+        let matched = chunk
+            .metadata
+            .get("content_hash")
+            .and_then(|hash| hash.as_str())
+            .and_then(|hash| by_hash.get_mut(hash)?.pop_front());
+        match matched {
+            Some(id) => {
+                unchanged.push(id);
+                false
+            }
+            None => true,
+        }
+    });
+
+    let kept: HashSet<&str> = unchanged.iter().copied().collect();
+    let deleted: Vec<&str> = prior.iter().map(|chunk| chunk.id.as_str()).filter(|id| !kept.contains(id)).collect();
+
+    result.metadata.additional.insert("unchanged_chunk_ids".to_string(), serde_json::json!(unchanged));
+    result.metadata.additional.insert("deleted_chunk_ids".to_string(), serde_json::json!(deleted));
+}
+
+/// Drops chunks that repeat an earlier one, such as a header or footer that
+/// was extracted on every page. Chunks are compared on their words, ignoring
+/// case, punctuation and numbers (so "Page 2" repeats "Page 1"), and one is a
+/// duplicate when at least 90% of its three-word shingles are shared with an
+/// earlier chunk that was kept. Each dropped chunk is listed in
+/// `additional["duplicate_chunks"]` as `{"id", "duplicate_of"}`, the latter
+/// being the kept chunk it repeats.
+///
+/// Only kept chunks sharing one of a chunk's lowest shingle hashes are
+/// compared with it (prefix filtering), so a long document is not compared
+/// chunk against chunk.
+pub fn drop_duplicate_chunks(result: &mut ContentProcessingResult) {
+    let mut kept: Vec<(String, Vec<u64>)> = Vec::new();
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    result.chunks.retain(|chunk| {
+        let shingles = shingles(&chunk.content);
+        if shingles.is_empty() {
+            return true;
+        }
+        let prefix = &shingles[..prefix_len(shingles.len())];
+
+        let candidates: BTreeSet<usize> = prefix.iter().filter_map(|shingle| index.get(shingle)).flatten().copied().collect();
+        if let Some(original) = candidates
+            .into_iter()
+            .find(|&position| jaccard(&shingles, &kept[position].1) >= DUPLICATE_SIMILARITY)
+        {
+            duplicates.push(serde_json::json!({ "id": chunk.id, "duplicate_of": kept[original].0 }));
+            return false;
+        }
+
+        for shingle in prefix {
+            index.entry(*shingle).or_default().push(kept.len());
+        }
+        kept.push((chunk.id.clone(), shingles));
+        true
+    });
+
+    result.metadata.additional.insert("duplicate_chunks".to_string(), serde_json::json!(duplicates));
+}
+
+/// The hashes of `content`'s normalized word shingles, sorted and
+/// deduplicated. Content of fewer words than a shingle is one shingle.
+fn shingles(content: &str) -> Vec<u64> {
+    let words: Vec<String> = content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| if word.chars().all(char::is_numeric) { "0".to_string() } else { word.to_lowercase() })
+        .collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hashes: Vec<u64> = words
+        .windows(SHINGLE_WORDS.min(words.len()))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+/// How many of a sorted set's first elements another set must share at least
+/// one of to reach `DUPLICATE_SIMILARITY` with it.
+fn prefix_len(len: usize) -> usize {
+    let required = (len as f64 * DUPLICATE_SIMILARITY).ceil() as usize;
+    (len - required.min(len) + 1).min(len)
+}
+
+/// Jaccard similarity of two sorted, deduplicated sets.
+fn jaccard(a: &[u64], b: &[u64]) -> f64 {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Puts a chunk summarizing the whole document, marked `chunk_type:
+/// "summary"`, in front of the others: the section headings for markdown, the
+/// first and last paragraphs for PDF and DOCX, and the top-level keys for JSON
+/// and YAML. Built from the chunks, so it reflects what the provider
+/// extracted. Other content types, and documents with nothing to summarize,
+/// are left as they are.
+pub fn prepend_summary(result: &mut ContentProcessingResult, doc_hash: &str) {
+    let body = match result.metadata.content_type {
+        ContentType::Markdown => heading_outline(&result.chunks),
+        ContentType::Pdf | ContentType::Document => opening_and_closing(&result.chunks),
+        ContentType::StructuredData | ContentType::Yaml => top_level_keys(&result.chunks),
+        _ => None,
+    };
+    let Some(body) = body else {
+        return;
+    };
+
+    let heading = match &result.metadata.title {
+        Some(title) => format!("# Summary: {}", title),
+        None => "# Summary".to_string(),
+    };
+    let content = format!("{}\n\n{}", heading, body);
+
+    let mut metadata = HashMap::new();
+    metadata.insert("chunk_type".to_string(), serde_json::json!("summary"));
+    result.chunks.insert(
+        0,
+        ContentChunk {
+            id: ids::chunk_id("summary", doc_hash, &content),
+            content,
+            metadata,
+        },
+    );
+}
+
+/// The section headings as a nested list, in document order.
+fn heading_outline(chunks: &[ContentChunk]) -> Option<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for chunk in chunks {
+        let title = chunk.metadata.get("section_title").and_then(|title| title.as_str()).unwrap_or_default();
+        if title.is_empty() {
+            continue;
+        }
+        let level = chunk.metadata.get("heading_level").and_then(|level| level.as_u64()).unwrap_or(1);
+        let line = format!("{}- {}", "  ".repeat(level.saturating_sub(1) as usize), title);
+        // Sections split across several chunks repeat their heading
+        if lines.last() != Some(&line) {
+            lines.push(line);
+        }
+    }
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// The first paragraph of the first chunk and the last of the last, leaving
+/// out the headings providers put at the top of each chunk.
+fn opening_and_closing(chunks: &[ContentChunk]) -> Option<String> {
+    let paragraphs = |chunk: &ContentChunk| -> Vec<String> {
+        chunk
+            .content
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|paragraph| !paragraph.is_empty() && !paragraph.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    };
+
+    let first = paragraphs(chunks.first()?).into_iter().next()?;
+    let last = paragraphs(chunks.last()?).pop().filter(|last| *last != first);
+
+    Some(match last {
+        Some(last) => format!("{}\n\n…\n\n{}", first, last),
+        None => first,
+    })
+}
+
+/// The keys the chunk paths start with, in order of first appearance, or the
+/// item count for a document that is an array.
+fn top_level_keys(chunks: &[ContentChunk]) -> Option<String> {
+    let mut keys: Vec<&str> = Vec::new();
+    let mut items = HashSet::new();
+    for chunk in chunks {
+        let path = chunk.metadata.get("path").and_then(|path| path.as_str()).unwrap_or_default();
+        let key = &path[..path.find(['.', '[']).unwrap_or(path.len())];
+        if !key.is_empty() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        } else if let Some(index) = path.strip_prefix('[').and_then(|rest| rest.split(']').next()) {
+            items.insert(index);
+        }
+    }
+
+    if !keys.is_empty() {
+        Some(format!("Top-level keys: {}", keys.join(", ")))
+    } else if !items.is_empty() {
+        Some(format!("Top-level items: {}", items.len()))
+    } else {
+        None
+    }
+}
+
+pub fn attach_sentence_offsets(chunks: &mut [ContentChunk]) {
+    for chunk in chunks.iter_mut() {
+        let sentences: Vec<_> = sentence_spans(&chunk.content)
+            .into_iter()
+            .map(|span| serde_json::json!({ "start": span.start, "end": span.end }))
+            .collect();
+        chunk.metadata.insert("sentences".to_string(), serde_json::json!(sentences));
+    }
+}
+
+pub fn render_chunks(chunks: &mut [ContentChunk], format: OutputFormat) {
+    let render: fn(&str) -> String = match format {
+        OutputFormat::Markdown => return,
+        OutputFormat::Plain => render_plain,
+        OutputFormat::Html => render_html,
+    };
+
+    for chunk in chunks.iter_mut() {
+        chunk.content = render(&chunk.content);
+    }
+}
+
+/// Renders markdown to HTML, sanitized so that raw HTML embedded in source
+/// documents (scripts, event handlers) cannot be stored and replayed by clients.
+pub fn render_html(markdown: &str) -> String {
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, Parser::new(markdown));
+    ammonia::clean(&rendered)
+}
+
+pub fn render_plain(markdown: &str) -> String {
+    let mut text = String::new();
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::Item)
+            | Event::End(TagEnd::CodeBlock) => text.push_str("\n\n"),
+            _ => {}
+        }
+    }
+
+    text.trim().to_string()
+}
+
+/// Marks every chunk `filter` leaves out with `embedding: null` and the reason
+/// under `embedding_skipped`, and returns the indices of the chunks to embed.
+pub fn select_for_embedding(result: &mut ContentProcessingResult, filter: &EmbedFilter) -> Vec<usize> {
+    let mut selected = Vec::new();
+
+    for (i, chunk) in result.chunks.iter_mut().enumerate() {
+        match embed_skip_reason(filter, chunk, &result.metadata.content_type) {
+            Some(reason) => {
+                chunk.metadata.insert("embedding".to_string(), serde_json::Value::Null);
+                chunk.metadata.insert("embedding_skipped".to_string(), serde_json::json!(reason));
+            }
+            None => selected.push(i),
+        }
+    }
+
+    selected
+}
+
+/// The chunks at `selected` as they should be embedded: each one's content
+/// preceded by the values of its metadata `fields`, one per line, with
+/// `title` falling back to the document title. Fields a chunk lacks are left
+/// out, and list values (such as heading paths) are joined with ` > `.
+pub fn embedding_inputs(result: &ContentProcessingResult, selected: &[usize], fields: &[String]) -> Vec<ContentChunk> {
+    selected
+        .iter()
+        .map(|&i| {
+            let mut chunk = result.chunks[i].clone();
+            let mut lines: Vec<String> = fields
+                .iter()
+                .filter_map(|field| match chunk.metadata.get(field.as_str()) {
+                    Some(value) => metadata_text(value),
+                    None if field == "title" => result.metadata.title.clone(),
+                    None => None,
+                })
+                .filter(|line| !line.trim().is_empty())
+                .collect();
+
+            if !lines.is_empty() {
+                lines.push(String::new());
+                lines.push(chunk.content);
+                chunk.content = lines.join("\n");
+            }
+            chunk
+        })
+        .collect()
+}
+
+fn metadata_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Array(items) => {
+            Some(items.iter().filter_map(metadata_text).collect::<Vec<_>>().join(" > "))
+        }
+        other => Some(other.to_string()),
+    }
+}
+
+/// Why `filter` excludes `chunk`, or `None` if it should be embedded. A chunk's
+/// own `metadata["content_type"]` takes precedence over the document's type.
+pub fn embed_skip_reason(filter: &EmbedFilter, chunk: &ContentChunk, content_type: &ContentType) -> Option<String> {
+    let section = chunk.metadata.get("section_title").and_then(|title| title.as_str());
+    let in_sections = |prefixes: &[String]| {
+        section.is_some_and(|section| prefixes.iter().any(|prefix| section.starts_with(prefix.as_str())))
+    };
+
+    if !filter.sections.is_empty() && !in_sections(&filter.sections) {
+        return Some("section not selected".to_string());
+    }
+    if in_sections(&filter.exclude_sections) {
+        return Some(format!("section excluded: {}", section.unwrap_or_default()));
+    }
+    if chunk.content.chars().count() < filter.min_length {
+        return Some(format!("shorter than {} characters", filter.min_length));
+    }
+    if !filter.content_types.is_empty() {
+        let chunk_type = chunk
+            .metadata
+            .get("content_type")
+            .and_then(|value| serde_json::from_value::<ContentType>(value.clone()).ok());
+        let chunk_type = chunk_type.as_ref().unwrap_or(content_type);
+        if !filter.content_types.contains(chunk_type) {
+            return Some(format!("content type {:?} not selected", chunk_type));
+        }
+    }
+
+    None
+}
@@ -0,0 +1,43 @@
+use crate::models::{RerankResponse, RerankResult};
+use crate::services::{cosine_similarity, EmbeddingService};
+use std::iter;
+
+/// Scores each of `documents` against `query` and returns them most relevant
+/// first, ties in document order.
+///
+/// `embed_anything`'s cross-encoder rerankers run on ONNX Runtime, which this
+/// build does not enable, so the score is the cosine similarity between the
+/// query's embedding and the document's. Models that expect it get their
+/// query prefix on the query and their passage prefix on the documents.
+pub async fn rerank(service: &EmbeddingService, query: &str, documents: &[String]) -> anyhow::Result<RerankResponse> {
+    let prefixes = service.prefixes();
+    let texts = iter::once(format!("{}{}", prefixes.query, query))
+        .chain(documents.iter().map(|document| format!("{}{}", prefixes.passage, document)))
+        .collect();
+    let response = service.embed(texts, None).await?;
+
+    let mut embeddings = response.data.into_iter().map(|data| data.embedding.into_floats());
+    let query_embedding = embeddings
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Model returned no embedding for the query"))??;
+
+    let mut results = documents
+        .iter()
+        .zip(embeddings)
+        .enumerate()
+        .map(|(index, (document, embedding))| {
+            Ok(RerankResult {
+                index,
+                score: cosine_similarity(&query_embedding, &embedding?)?,
+                document: document.clone(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(RerankResponse {
+        model: response.model,
+        results,
+        usage: response.usage,
+    })
+}
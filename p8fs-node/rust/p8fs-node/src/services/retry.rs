@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::time::Duration;
+
+/// How many times to retry an operation that can fail transiently, such as
+/// downloading a model from the Hugging Face hub. The first retry waits
+/// `base_delay` and each later one waits twice as long as the one before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Runs an operation once.
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            base_delay: Duration::ZERO,
+        }
+    }
+
+    /// `EMBEDDING_LOAD_RETRIES` retries (default 3), starting at 500 ms.
+    pub fn from_env() -> Self {
+        Self {
+            retries: std::env::var("EMBEDDING_LOAD_RETRIES")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(3),
+            base_delay: Duration::from_millis(500),
+        }
+    }
+
+    /// The wait before retry number `retry`, counting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+
+    /// Runs `operation` until it succeeds or the retries run out, waiting
+    /// without blocking the runtime between attempts. `what` names the
+    /// operation in the logs.
+    pub async fn run<T, F, Fut>(&self, what: &str, mut operation: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    let delay = self.failed(what, attempt, err)?;
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Logs failed attempt number `attempt` and returns how long to wait
+    /// before the next one, or the error once there are no retries left.
+    fn failed(&self, what: &str, attempt: u32, err: anyhow::Error) -> anyhow::Result<Duration> {
+        let attempts = self.retries + 1;
+        if attempt >= attempts {
+            if attempts > 1 {
+                tracing::error!(attempt, attempts, error = %err, "{} failed, giving up", what);
+            }
+            return Err(err);
+        }
+
+        let delay = self.delay(attempt);
+        tracing::warn!(attempt, attempts, delay_ms = delay.as_millis() as u64, error = %err, "{} failed, retrying", what);
+        Ok(delay)
+    }
+}
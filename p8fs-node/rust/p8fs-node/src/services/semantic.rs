@@ -0,0 +1,57 @@
+use crate::services::sentences::sentence_spans;
+use crate::services::{cosine_similarity, EmbeddingService};
+use std::ops::Range;
+
+/// Cuts `text` into chunks of whole sentences, starting a new chunk where
+/// the topic changes: each sentence is embedded, and one whose cosine
+/// similarity to the sentence before it falls below `similarity_threshold`
+/// starts a new chunk. A chunk also ends before it would pass `max_chars`
+/// characters; a single longer sentence is a chunk of its own.
+///
+/// Returns each chunk with its character range in `text`, like
+/// `DocumentProvider::chunk_text`. Unlike fixed windows, chunks do not overlap.
+pub async fn semantic_chunks(
+    service: &EmbeddingService,
+    text: &str,
+    similarity_threshold: f32,
+    max_chars: usize,
+) -> anyhow::Result<Vec<(Range<usize>, String)>> {
+    let sentences: Vec<Range<usize>> = sentence_spans(text)
+        .into_iter()
+        .filter(|span| !text[span.clone()].trim().is_empty())
+        .collect();
+    if sentences.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let texts = sentences.iter().map(|span| text[span.clone()].trim().to_string()).collect();
+    let embeddings = service
+        .embed(texts, None)
+        .await?
+        .data
+        .into_iter()
+        .map(|data| data.embedding.into_floats())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut groups: Vec<Range<usize>> = vec![sentences[0].clone()];
+    for (i, sentence) in sentences.iter().enumerate().skip(1) {
+        let current = groups.last_mut().expect("groups starts with the first sentence");
+        let same_topic = cosine_similarity(&embeddings[i - 1], &embeddings[i])? >= similarity_threshold;
+        let fits = text[current.start..sentence.end].chars().count() <= max_chars;
+
+        if same_topic && fits {
+            current.end = sentence.end;
+        } else {
+            groups.push(sentence.clone());
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|group| {
+            let start = text[..group.start].chars().count();
+            let end = start + text[group.clone()].chars().count();
+            (start..end, text[group].to_string())
+        })
+        .collect())
+}
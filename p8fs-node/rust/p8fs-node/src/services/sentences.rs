@@ -0,0 +1,115 @@
+use crate::providers::limits::env_or;
+use once_cell::sync::Lazy;
+use std::ops::Range;
+
+/// Longest sentence, in characters, before it is split into fixed-size
+/// pieces. Read from `P8FS_MAX_SENTENCE_CHARS`.
+static MAX_SENTENCE_CHARS: Lazy<usize> = Lazy::new(|| env_or("P8FS_MAX_SENTENCE_CHARS", 2000).max(1));
+
+const TERMINATORS: &[char] = &['.', '!', '?'];
+const FULL_WIDTH_TERMINATORS: &[char] = &['。', '！', '？'];
+const CLOSERS: &[char] = &['"', '\'', ')', ']', '”', '’', '」'];
+
+/// Splits `text` into sentences, returned as byte ranges that partition it:
+/// each sentence keeps the whitespace after it, so the ranges are contiguous
+/// and cover the whole text.
+///
+/// A sentence ends at `.`, `!` or `?` (and any closing quotes or brackets)
+/// when followed by whitespace and a word that does not start in lowercase,
+/// which keeps abbreviations like "e.g. this" together. Full-width
+/// terminators and blank lines always end a sentence.
+///
+/// Segmentation is a single forward scan, so it stays linear on adversarial
+/// input. Text with no usable boundaries (a long run of `.`, a minified blob)
+/// would still come back as one huge "sentence", so sentences over
+/// `P8FS_MAX_SENTENCE_CHARS` are split into fixed-size pieces instead.
+pub fn sentence_spans(text: &str) -> Vec<Range<usize>> {
+    sentence_spans_with_limit(text, *MAX_SENTENCE_CHARS)
+}
+
+pub fn sentence_spans_with_limit(text: &str, max_chars: usize) -> Vec<Range<usize>> {
+    let spans = scan_sentences(text);
+
+    let oversized = spans.iter().filter(|span| text[(*span).clone()].chars().nth(max_chars).is_some()).count();
+    if oversized == 0 {
+        return spans;
+    }
+
+    tracing::warn!(
+        "{} sentence(s) exceed {} characters; splitting them into fixed-size pieces",
+        oversized,
+        max_chars
+    );
+    spans
+        .into_iter()
+        .flat_map(|span| fixed_size_spans(text, span, max_chars))
+        .collect()
+}
+
+/// Splits `span` into consecutive pieces of at most `max_chars` characters.
+fn fixed_size_spans(text: &str, span: Range<usize>, max_chars: usize) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = span.start;
+
+    for (count, (i, _)) in text[span.clone()].char_indices().enumerate() {
+        if count > 0 && count % max_chars == 0 {
+            pieces.push(start..span.start + i);
+            start = span.start + i;
+        }
+    }
+    pieces.push(start..span.end);
+
+    pieces
+}
+
+fn scan_sentences(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let mut end = i + c.len_utf8();
+
+        let boundary = if TERMINATORS.contains(&c) || FULL_WIDTH_TERMINATORS.contains(&c) {
+            while let Some(&(j, next)) = chars.peek() {
+                if !CLOSERS.contains(&next) {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            FULL_WIDTH_TERMINATORS.contains(&c) || starts_sentence(&text[end..])
+        } else {
+            c == '\n' && text[end..].trim_start_matches([' ', '\t']).starts_with('\n')
+        };
+
+        if !boundary {
+            continue;
+        }
+
+        while let Some(&(j, next)) = chars.peek() {
+            if !next.is_whitespace() {
+                break;
+            }
+            end = j + next.len_utf8();
+            chars.next();
+        }
+
+        spans.push(start..end);
+        start = end;
+    }
+
+    if start < text.len() {
+        spans.push(start..text.len());
+    }
+
+    spans
+}
+
+/// Whether the text after a terminator begins a new sentence.
+fn starts_sentence(rest: &str) -> bool {
+    if !rest.starts_with(char::is_whitespace) {
+        return false;
+    }
+    rest.trim_start().chars().next().is_none_or(|c| !c.is_lowercase())
+}
@@ -1,66 +1,984 @@
 #[cfg(test)]
 mod tests {
     use super::super::embeddings::*;
-    use crate::models::{EmbeddingRequest, EmbeddingResponse};
+    use super::super::export::embeddings_to_arrow_ipc;
+    use super::super::postprocess::{render_html, render_plain};
+    use super::super::vectors::normalize_l2;
     use std::sync::Arc;
 
     #[tokio::test]
     async fn test_embedding_service_creation() {
-        let service = EmbeddingService::new();
+        use super::super::model_registry::ModelRegistry;
+
+        let service = ModelRegistry::from_env().get(None).await;
         assert!(service.is_ok(), "Failed to create embedding service");
     }
 
     #[tokio::test]
     async fn test_embedding_service_global_instance() {
-        let service1 = EmbeddingService::global();
-        let service2 = EmbeddingService::global();
+        let service1 = EmbeddingService::global().await.unwrap();
+        let service2 = EmbeddingService::global().await.unwrap();
         
         assert!(Arc::ptr_eq(&service1, &service2), "Global instances should be the same");
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore] // This test requires the model to be downloaded
+    async fn test_concurrent_embed_calls() {
+        let service = EmbeddingService::global().await.unwrap();
+
+        let handles: Vec<_> = (0..50)
+            .map(|i| {
+                let service = service.clone();
+                tokio::spawn(async move { service.embed(vec![format!("Concurrent text {}", i)], None).await })
+            })
+            .collect();
+
+        for handle in handles {
+            let response = handle.await.unwrap().unwrap();
+            assert_eq!(response.data.len(), 1);
+            assert_eq!(response.data[0].embedding.len(), 384);
+        }
+    }
+
+    struct FakeEmbedder(usize);
+
+    #[async_trait::async_trait]
+    impl Embedder for FakeEmbedder {
+        async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<embed_anything::embeddings::embed::EmbeddingResult>> {
+            use embed_anything::embeddings::embed::EmbeddingResult;
+            Ok(texts.iter().map(|_| EmbeddingResult::DenseVector(vec![1.0; self.0])).collect())
+        }
+
+        fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+            Ok(text.split_whitespace().count())
+        }
+    }
+
+    /// A small BERT-style WordPiece tokenizer: lowercases, splits CJK
+    /// characters into their own words and falls back to `[UNK]`.
+    fn wordpiece_tokenizer() -> tokenizers::Tokenizer {
+        use tokenizers::models::wordpiece::WordPiece;
+        use tokenizers::normalizers::BertNormalizer;
+        use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
+
+        let tokens = ["[UNK]", "你", "好", "世", "界", "token", "##izer", "##s"];
+        let vocab: [(String, u32); 8] = std::array::from_fn(|id| (tokens[id].to_string(), id as u32));
+        let model = WordPiece::builder().vocab(vocab).unk_token("[UNK]".to_string()).build().unwrap();
+
+        let mut tokenizer = tokenizers::Tokenizer::new(model);
+        tokenizer.with_normalizer(Some(BertNormalizer::default()));
+        tokenizer.with_pre_tokenizer(Some(BertPreTokenizer));
+        tokenizer
+    }
+
+    struct TokenizedEmbedder(tokenizers::Tokenizer);
+
+    #[async_trait::async_trait]
+    impl Embedder for TokenizedEmbedder {
+        async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<embed_anything::embeddings::embed::EmbeddingResult>> {
+            FakeEmbedder(4).embed_batch(texts).await
+        }
+
+        fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+            count_tokens(&self.0, text)
+        }
+    }
+
+    /// Returns a multi-vector result for inputs containing "multi" and fails
+    /// any batch containing "broken".
+    struct UnevenEmbedder;
+
+    #[async_trait::async_trait]
+    impl Embedder for UnevenEmbedder {
+        async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<embed_anything::embeddings::embed::EmbeddingResult>> {
+            use embed_anything::embeddings::embed::EmbeddingResult;
+
+            if texts.iter().any(|text| text.contains("broken")) {
+                anyhow::bail!("cannot embed a broken input");
+            }
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    if text.contains("multi") {
+                        EmbeddingResult::MultiVector(vec![vec![1.0; 4]; 2])
+                    } else {
+                        EmbeddingResult::DenseVector(vec![1.0; 4])
+                    }
+                })
+                .collect())
+        }
+
+        fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+            Ok(text.split_whitespace().count())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_rejects_multi_vector_results() {
+        let service = EmbeddingService::from_embedder("uneven", 4, Box::new(UnevenEmbedder));
+
+        let err = service
+            .embed(vec!["dense text".to_string(), "multi text".to_string()], None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Input 1 produced a multi-vector embedding"));
+
+        let response = service.embed(vec!["dense text".to_string()], None).await.unwrap();
+        assert_eq!(response.data[0].embedding.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_embed_lenient_reports_errors_per_input() {
+        let service = EmbeddingService::from_embedder("uneven", 4, Box::new(UnevenEmbedder));
+
+        // One call for the whole batch
+        let results = service.embed_lenient(&["first".to_string(), "multi".to_string()], None).await;
+        assert_eq!(results[0].as_ref().unwrap().index, 0);
+        assert!(results[1].as_ref().unwrap_err().contains("multi-vector"));
+
+        // The batch call fails, so each input is retried alone
+        let texts = ["first", "broken", "multi", "last"].map(String::from);
+        let results = service.embed_lenient(&texts, None).await;
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().index, 0);
+        assert!(results[1].as_ref().unwrap_err().contains("broken input"));
+        assert!(results[2].as_ref().unwrap_err().contains("multi-vector"));
+        assert_eq!(results[3].as_ref().unwrap().index, 3);
+    }
+
+    /// Records every text it is asked to embed.
+    struct RecordingEmbedder(Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl Embedder for RecordingEmbedder {
+        async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<embed_anything::embeddings::embed::EmbeddingResult>> {
+            self.0.lock().unwrap().extend(texts.iter().map(|text| text.to_string()));
+            FakeEmbedder(4).embed_batch(texts).await
+        }
+
+        fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+            Ok(text.split_whitespace().count())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_prepends_model_prefix_for_input_type() {
+        use crate::models::InputType;
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = EmbeddingService::from_embedder("e5-base-v2", 4, Box::new(RecordingEmbedder(seen.clone())));
+
+        let response = service.embed(vec!["what is p8fs".to_string()], Some(InputType::Query)).await.unwrap();
+        service.embed(vec!["p8fs is a file system".to_string()], Some(InputType::Passage)).await.unwrap();
+        service.embed(vec!["raw".to_string()], None).await.unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec!["query: what is p8fs", "passage: p8fs is a file system", "raw"]);
+        // The prefix counts towards usage, as the model sees it
+        assert_eq!(response.usage.prompt_tokens, 4);
+    }
+
+    #[tokio::test]
+    async fn test_embed_uses_configured_prefixes_and_none_by_default() {
+        use crate::models::InputType;
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let plain = EmbeddingService::from_embedder("all-MiniLM-L6-v2", 4, Box::new(RecordingEmbedder(seen.clone())));
+        assert_eq!(*plain.prefixes(), InputPrefixes::default());
+        plain.embed(vec!["text".to_string()], Some(InputType::Query)).await.unwrap();
+
+        let custom = EmbeddingService::from_embedder("custom", 4, Box::new(RecordingEmbedder(seen.clone())))
+            .with_prefixes(InputPrefixes { query: "Q: ".to_string(), passage: "D: ".to_string() });
+        custom.embed(vec!["text".to_string()], Some(InputType::Passage)).await.unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec!["text", "D: text"]);
+        assert_eq!(InputPrefixes::for_model("BAAI/bge-small-en-v1.5").passage, "");
+        assert_eq!(InputPrefixes::for_model("nomic-ai/nomic-embed-text-v1.5").query, "search_query: ");
+        assert_eq!(InputPrefixes::for_model("intfloat/multilingual-e5-large").query, "query: ");
+    }
+
+    #[test]
+    fn test_count_tokens_matches_tokenizer() {
+        let tokenizer = wordpiece_tokenizer();
+
+        // No whitespace at all, but one token per character
+        assert_eq!(count_tokens(&tokenizer, "你好世界").unwrap(), 4);
+        // Subword pieces are counted separately
+        assert_eq!(count_tokens(&tokenizer, "Tokenizers").unwrap(), 3);
+
+        for text in ["你好世界", "Tokenizers", "tokenizer 你好"] {
+            let encoding = tokenizer.encode(text, true).unwrap();
+            assert_eq!(count_tokens(&tokenizer, text).unwrap(), encoding.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_usage_sums_tokens_per_input() {
+        let service = EmbeddingService::from_embedder("fake", 4, Box::new(TokenizedEmbedder(wordpiece_tokenizer())));
+
+        let response = service
+            .embed(vec!["你好世界".to_string(), "Tokenizers".to_string()], None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.usage.prompt_tokens, 7);
+        assert_eq!(response.usage.total_tokens, 7);
+    }
+
+    #[tokio::test]
+    async fn test_model_registry_dispatches_by_name() {
+        use super::super::model_registry::{ModelError, ModelRegistry};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let loads = Arc::new(AtomicUsize::new(0));
+        let mut registry = ModelRegistry::new("org/small");
+        for (name, dimensions) in [("org/small", 4), ("org/large", 8)] {
+            let loads = loads.clone();
+            registry.register(name, move || {
+                loads.fetch_add(1, Ordering::SeqCst);
+                let short_name = name.rsplit('/').next().unwrap();
+                Ok(EmbeddingService::from_embedder(short_name, dimensions, Box::new(FakeEmbedder(dimensions))))
+            });
+        }
+
+        assert!(registry.try_get(None).is_none());
+        assert_eq!(loads.load(Ordering::SeqCst), 0);
+
+        let large = registry.get(Some("org/large")).await.unwrap().embed(vec!["text".to_string()], None).await.unwrap();
+        assert_eq!(large.model, "large");
+        assert_eq!(large.data[0].embedding.len(), 8);
+
+        let default = registry.get(None).await.unwrap().embed(vec!["text".to_string()], None).await.unwrap();
+        assert_eq!(default.model, "small");
+        assert_eq!(default.data[0].embedding.len(), 4);
+
+        // Short names resolve, and loaded models are reused
+        assert!(Arc::ptr_eq(&registry.get(Some("large")).await.unwrap(), &registry.get(Some("org/large")).await.unwrap()));
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        match registry.get(Some("medium")).await {
+            Err(ModelError::Unknown { available, .. }) => assert_eq!(available, vec!["org/large", "org/small"]),
+            _ => panic!("expected an unknown model error"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_model_registry_loads_once_under_concurrent_first_use() {
+        use super::super::model_registry::ModelRegistry;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let loads = Arc::new(AtomicUsize::new(0));
+        let mut registry = ModelRegistry::new("org/slow");
+        let counter = loads.clone();
+        registry.register("org/slow", move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            // A slow load keeps every caller arriving while it is in progress
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(EmbeddingService::from_embedder("slow", 4, Box::new(FakeEmbedder(4))))
+        });
+        let registry = Arc::new(registry);
+
+        let handles: Vec<_> = (0..64)
+            .map(|i| {
+                let registry = registry.clone();
+                let name = if i % 2 == 0 { None } else { Some("slow") };
+                tokio::spawn(async move { registry.get(name).await.unwrap() })
+            })
+            .collect();
+        let services = futures::future::join_all(handles).await;
+
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        let first = services[0].as_ref().unwrap();
+        assert!(services.iter().all(|service| Arc::ptr_eq(first, service.as_ref().unwrap())));
+    }
+
+    #[tokio::test]
+    async fn test_model_load_retries_until_success() {
+        use super::super::model_registry::ModelRegistry;
+        use super::super::retry::RetryPolicy;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let registry_failing_twice = |retries| {
+            let loads = Arc::new(AtomicUsize::new(0));
+            let counter = loads.clone();
+            let mut registry =
+                ModelRegistry::new("org/flaky").with_retry(RetryPolicy { retries, base_delay: Duration::from_millis(1) });
+            registry.register("org/flaky", move || {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    anyhow::bail!("connection reset by hub");
+                }
+                Ok(EmbeddingService::from_embedder("flaky", 4, Box::new(FakeEmbedder(4))))
+            });
+            (registry, loads)
+        };
+
+        let (registry, loads) = registry_failing_twice(3);
+        assert_eq!(registry.get(None).await.unwrap().model_name(), "flaky");
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+
+        let (registry, loads) = registry_failing_twice(1);
+        assert!(registry.get(None).await.err().unwrap().to_string().contains("connection reset"));
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_local_model_path_must_exist() {
+        let missing = std::path::Path::new("/nonexistent/p8fs-models/all-MiniLM-L6-v2");
+        let err = EmbeddingService::with_local_model("all-MiniLM-L6-v2", missing, 384).err().unwrap().to_string();
+        assert!(err.contains("EMBEDDING_MODEL_PATH"), "{}", err);
+        assert!(err.contains("/nonexistent/p8fs-models/all-MiniLM-L6-v2 does not exist"), "{}", err);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.json"), "{}").unwrap();
+        let err = EmbeddingService::with_local_model("all-MiniLM-L6-v2", dir.path(), 384).err().unwrap().to_string();
+        assert!(err.contains("has no tokenizer.json"), "{}", err);
+    }
+
+    #[test]
+    fn test_local_models_with_the_same_name_get_distinct_repos() {
+        use std::path::Path;
+
+        let first = local_repo_name(Path::new("/models/team-a/minilm"), "minilm");
+        let second = local_repo_name(Path::new("/models/team-b/minilm"), "minilm");
+
+        assert!(first.starts_with("minilm-") && second.starts_with("minilm-"));
+        assert_ne!(first, second);
+        assert_eq!(first, local_repo_name(Path::new("/models/team-a/minilm"), "minilm"));
+    }
+
+    #[test]
+    fn test_retry_delay_doubles() {
+        use super::super::retry::RetryPolicy;
+        use std::time::Duration;
+
+        let policy = RetryPolicy { retries: 3, base_delay: Duration::from_millis(500) };
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3), Duration::from_millis(2000));
+    }
+
+    /// Fails as many calls as its count, then embeds like `FakeEmbedder(4)`.
+    struct FlakyEmbedder(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl Embedder for FlakyEmbedder {
+        async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<embed_anything::embeddings::embed::EmbeddingResult>> {
+            use std::sync::atomic::Ordering;
+
+            if self.0.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1)).is_ok() {
+                anyhow::bail!("upstream timed out");
+            }
+            FakeEmbedder(4).embed_batch(texts).await
+        }
+
+        fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+            Ok(text.split_whitespace().count())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_retries_transient_failures() {
+        use super::super::retry::RetryPolicy;
+        use std::time::Duration;
+
+        let flaky = FlakyEmbedder(std::sync::atomic::AtomicUsize::new(2));
+        let service = EmbeddingService::from_embedder("flaky", 4, Box::new(flaky))
+            .with_retry(RetryPolicy { retries: 2, base_delay: Duration::from_millis(1) });
+
+        let response = service.embed(vec!["text".to_string()], None).await.unwrap();
+        assert_eq!(response.data[0].embedding.len(), 4);
+    }
+
+    #[test]
+    fn test_dimension_mismatch_check() {
+        assert_eq!(DimensionMismatch::check(384, 384), None);
+        assert_eq!(
+            DimensionMismatch::check(384, 768),
+            Some(DimensionMismatch { configured: 384, actual: 768 })
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires the model to be downloaded
+    async fn test_dimension_mismatch_detected() {
+        let service = EmbeddingService::with_model("sentence-transformers/all-MiniLM-L6-v2", 768).unwrap();
+
+        let mismatch = service.dimension_mismatch().await.unwrap();
+        assert_eq!(mismatch, Some(DimensionMismatch { configured: 768, actual: 384 }));
+    }
+
     #[tokio::test]
     #[ignore] // This test requires the model to be downloaded
     async fn test_embed_single_text() {
-        let service = EmbeddingService::new().unwrap();
+        let service = EmbeddingService::global().await.unwrap();
         let texts = vec!["Hello world".to_string()];
         
-        let result = service.embed(texts).await;
+        let result = service.embed(texts, None).await;
         assert!(result.is_ok(), "Embedding generation failed");
         
         let response = result.unwrap();
-        assert_eq!(response.embeddings.len(), 1);
-        assert_eq!(response.dimensions, 384);
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0].embedding.len(), 384);
         assert_eq!(response.model, "all-MiniLM-L6-v2");
     }
 
     #[tokio::test]
     #[ignore] // This test requires the model to be downloaded
     async fn test_embed_multiple_texts() {
-        let service = EmbeddingService::new().unwrap();
+        let service = EmbeddingService::global().await.unwrap();
         let texts = vec![
             "First text".to_string(),
             "Second text".to_string(),
             "Third text".to_string(),
         ];
         
-        let result = service.embed(texts.clone()).await;
+        let result = service.embed(texts.clone(), None).await;
         assert!(result.is_ok(), "Embedding generation failed");
         
         let response = result.unwrap();
-        assert_eq!(response.embeddings.len(), texts.len());
+        assert_eq!(response.data.len(), texts.len());
         
-        for embedding in &response.embeddings {
-            assert_eq!(embedding.len(), 384);
+        for data in &response.data {
+            assert_eq!(data.embedding.len(), 384);
         }
     }
 
     #[tokio::test]
     #[ignore] // This test requires the model to be downloaded
     async fn test_embed_empty_text() {
-        let service = EmbeddingService::new().unwrap();
+        let service = EmbeddingService::global().await.unwrap();
         let texts = vec!["".to_string()];
         
-        let result = service.embed(texts).await;
+        let result = service.embed(texts, None).await;
         assert!(result.is_ok(), "Should handle empty text");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_job_store_evicts_finished_jobs_after_ttl() {
+        use super::super::jobs::JobStore;
+        use crate::models::JobStatus;
+        use std::time::Duration;
+
+        let jobs = JobStore::with_ttl(Duration::ZERO);
+        let running = jobs.create();
+        jobs.set_status(&running.job_id, JobStatus::Running);
+        let failed = jobs.create();
+        jobs.finish(&failed.job_id, Err(anyhow::anyhow!("unreadable")));
+        let updates = jobs.subscribe(&failed.job_id).unwrap();
+
+        // Still there until the next job is created
+        assert_eq!(jobs.get(&failed.job_id).unwrap().status, JobStatus::Failed);
+        let next = jobs.create();
+
+        assert!(jobs.get(&failed.job_id).is_none());
+        assert!(jobs.get(&running.job_id).is_some());
+        assert!(jobs.get(&next.job_id).is_some());
+        assert_eq!(jobs.len(), 2);
+        assert!(updates.has_changed().is_err(), "followers should see the job go away");
+
+        let kept = JobStore::default();
+        let done = kept.create();
+        kept.finish(&done.job_id, Err(anyhow::anyhow!("unreadable")));
+        kept.create();
+        assert!(kept.get(&done.job_id).is_some());
+    }
+
+    #[test]
+    fn test_normalize_l2_unit_norm() {
+        let mut vector = vec![3.0, 4.0, 12.0];
+        normalize_l2(&mut vector);
+
+        let norm: f32 = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6, "Normalized vector should have unit length, got {}", norm);
+        assert!((vector[0] - 3.0 / 13.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_truncate_embeddings_to_requested_dimensions() {
+        use super::super::vectors::truncate_embeddings;
+
+        let service = EmbeddingService::from_embedder("fake", 384, Box::new(FakeEmbedder(384)));
+        let full = service.embed(vec!["first".to_string(), "second".to_string()], None).await.unwrap();
+        assert_eq!(full.dimensions, 384);
+
+        let mut truncated = full.clone();
+        truncate_embeddings(&mut truncated, 128);
+
+        assert_eq!(truncated.dimensions, 128);
+        for (short, long) in truncated.data.iter().zip(&full.data) {
+            assert_eq!(short.embedding.len(), 128);
+            assert_eq!(short.embedding.to_floats().unwrap()[..], long.embedding.to_floats().unwrap()[..128]);
+        }
+    }
+
+    /// Counts occurrences of a few keywords, so texts sharing words with the
+    /// query end up close to it.
+    struct KeywordEmbedder;
+
+    #[async_trait::async_trait]
+    impl Embedder for KeywordEmbedder {
+        async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<embed_anything::embeddings::embed::EmbeddingResult>> {
+            use embed_anything::embeddings::embed::EmbeddingResult;
+
+            const KEYWORDS: [&str; 4] = ["rust", "borrow", "garden", "tomato"];
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    EmbeddingResult::DenseVector(KEYWORDS.iter().map(|word| text.matches(word).count() as f32).collect())
+                })
+                .collect())
+        }
+
+        fn count_tokens(&self, text: &str) -> anyhow::Result<usize> {
+            Ok(text.split_whitespace().count())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerank_ranks_most_relevant_document_first() {
+        use super::super::rerank::rerank;
+
+        let service = EmbeddingService::from_embedder("keywords", 4, Box::new(KeywordEmbedder));
+        let documents = vec![
+            "Planting tomato seedlings in the garden".to_string(),
+            "The Rust borrow checker and Rust lifetimes".to_string(),
+            "Rust stains on garden tools".to_string(),
+        ];
+
+        let response = rerank(&service, "How does the Rust borrow checker work?", &documents).await.unwrap();
+
+        let order: Vec<usize> = response.results.iter().map(|result| result.index).collect();
+        assert_eq!(order, vec![1, 2, 0]);
+        assert_eq!(response.results[0].document, documents[1]);
+        assert!(response.results[0].score > response.results[1].score);
+        assert_eq!(response.results[2].score, 0.0);
+        assert_eq!(response.model, "keywords");
+    }
+
+    #[tokio::test]
+    async fn test_semantic_chunks_split_distinct_topics() {
+        use super::super::semantic::semantic_chunks;
+
+        let service = EmbeddingService::from_embedder("keywords", 4, Box::new(KeywordEmbedder));
+        let text = "The garden needs tomato stakes. Tomato plants in the garden grow fast. \
+                    The Rust borrow checker rejects the code. Rust borrow rules prevent data races.";
+
+        let chunks = semantic_chunks(&service, text, 0.5, 1000).await.unwrap();
+
+        let contents: Vec<&str> = chunks.iter().map(|(_, content)| content.trim()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "The garden needs tomato stakes. Tomato plants in the garden grow fast.",
+                "The Rust borrow checker rejects the code. Rust borrow rules prevent data races.",
+            ]
+        );
+        let (range, _) = &chunks[1];
+        assert_eq!(text.chars().skip(range.start).take(range.len()).collect::<String>(), chunks[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_chunks_cap_chunk_size() {
+        use super::super::semantic::semantic_chunks;
+
+        let service = EmbeddingService::from_embedder("keywords", 4, Box::new(KeywordEmbedder));
+        let text = "Rust borrow one. Rust borrow two. Rust borrow three.";
+
+        let chunks = semantic_chunks(&service, text, 0.5, 40).await.unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|(range, _)| range.len() <= 40));
+        assert_eq!(chunks[0].1, "Rust borrow one. Rust borrow two. ");
+    }
+
+    #[test]
+    fn test_cosine_similarity_identical_vectors() {
+        use super::super::vectors::cosine_similarity;
+
+        let similarity = cosine_similarity(&[0.5, -1.0, 2.0], &[0.5, -1.0, 2.0]).unwrap();
+        assert!((similarity - 1.0).abs() < 1e-6, "got {}", similarity);
+    }
+
+    #[test]
+    fn test_cosine_similarity_orthogonal_vectors() {
+        use super::super::vectors::cosine_similarity;
+
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).unwrap(), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_cosine_similarity_length_mismatch() {
+        use super::super::vectors::{cosine_similarity, LengthMismatch};
+
+        assert_eq!(cosine_similarity(&[1.0, 2.0, 3.0], &[1.0, 2.0]), Err(LengthMismatch(3, 2)));
+    }
+
+    #[test]
+    fn test_normalize_l2_zero_vector_unchanged() {
+        let mut vector = vec![0.0; 4];
+        normalize_l2(&mut vector);
+
+        assert_eq!(vector, vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_render_html_heading_and_sanitization() {
+        let markdown = "## Results\n\nSafe text.\n\n<script>alert('xss')</script>\n";
+        let html = render_html(markdown);
+
+        assert!(html.contains("<h2>Results</h2>"), "Heading should render as <h2>: {}", html);
+        assert!(html.contains("Safe text."));
+        assert!(!html.contains("<script"), "Script tags must be stripped: {}", html);
+        assert!(!html.contains("alert"), "Script bodies must be stripped: {}", html);
+    }
+
+    #[test]
+    fn test_render_plain_strips_markup() {
+        let plain = render_plain("## Results\n\nSome **bold** text.");
+
+        assert_eq!(plain, "Results\n\nSome bold text.");
+    }
+
+    #[test]
+    fn test_sentence_spans_partition_content() {
+        use super::super::sentences::sentence_spans;
+
+        let text = "The sample was heated to 3.5 degrees, e.g. slowly. Did it melt? \
+                    It did (eventually.) \"Remarkable!\" they said.\n\n\
+                    实验成功了。结果很好！";
+        let spans = sentence_spans(text);
+
+        let sentences: Vec<&str> = spans.iter().map(|span| text[span.clone()].trim()).collect();
+        assert_eq!(
+            sentences,
+            vec![
+                "The sample was heated to 3.5 degrees, e.g. slowly.",
+                "Did it melt?",
+                "It did (eventually.)",
+                "\"Remarkable!\" they said.",
+                "实验成功了。",
+                "结果很好！",
+            ]
+        );
+
+        // Contiguous from the start to the end of the content
+        assert_eq!(spans.first().unwrap().start, 0);
+        assert_eq!(spans.last().unwrap().end, text.len());
+        for pair in spans.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+
+    #[test]
+    fn test_sentence_spans_split_pathological_input() {
+        use super::super::sentences::{sentence_spans, sentence_spans_with_limit};
+
+        let text = ".".repeat(1024 * 1024);
+        let started = std::time::Instant::now();
+        let spans = sentence_spans(&text);
+        assert!(started.elapsed() < std::time::Duration::from_secs(2), "took {:?}", started.elapsed());
+
+        assert_eq!(spans.len(), 1024 * 1024 / 2000 + 1);
+        assert!(spans.iter().all(|span| span.len() <= 2000));
+        assert_eq!(spans.last().unwrap().end, text.len());
+        for pair in spans.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+
+        // Pieces end on character boundaries, and short sentences are left alone
+        let text = "短い文。".repeat(3) + &"あ".repeat(10);
+        let spans = sentence_spans_with_limit(&text, 4);
+        let pieces: Vec<&str> = spans.iter().map(|span| &text[span.clone()]).collect();
+        assert_eq!(pieces, vec!["短い文。", "短い文。", "短い文。", "ああああ", "ああああ", "ああ"]);
+    }
+
+    #[test]
+    fn test_apply_options_attaches_sentence_offsets() {
+        use super::super::postprocess::apply_options;
+        use crate::models::{ContentMetadata, ContentProcessingResult, ContentType, ProcessingOptions};
+        use crate::providers::markdown::MarkdownProvider;
+
+        let mut result = ContentProcessingResult {
+            success: true,
+            status: Default::default(),
+            chunks: MarkdownProvider::new().chunk_str("# Notes\n\nFirst point. Second point!\n\nA new paragraph"),
+            metadata: ContentMetadata {
+                content_type: ContentType::Markdown,
+                file_name: None,
+                file_size: None,
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: None,
+                language: None,
+                additional: Default::default(),
+            },
+            error: None,
+        };
+        let options = ProcessingOptions {
+            sentence_offsets: true,
+            ..Default::default()
+        };
+
+        apply_options(&mut result, &options);
+
+        for chunk in &result.chunks {
+            let sentences = chunk.metadata["sentences"].as_array().unwrap();
+            let mut expected_start = 0;
+            for sentence in sentences {
+                let (start, end) = (sentence["start"].as_u64().unwrap() as usize, sentence["end"].as_u64().unwrap() as usize);
+                assert_eq!(start, expected_start);
+                assert!(!chunk.content[start..end].trim().is_empty());
+                expected_start = end;
+            }
+            assert_eq!(expected_start, chunk.content.len());
+        }
+        // The heading, two sentences and the final paragraph
+        assert_eq!(result.chunks[0].metadata["sentences"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_prepend_summary_adds_one_summary_chunk() {
+        use super::super::postprocess::prepend_summary;
+        use crate::models::{ContentMetadata, ContentProcessingResult, ContentType};
+        use crate::providers::json::JsonProvider;
+        use crate::providers::markdown::MarkdownProvider;
+
+        let metadata = |content_type| ContentMetadata {
+            content_type,
+            file_name: None,
+            file_size: None,
+            created_at: None,
+            modified_at: None,
+            author: None,
+            title: Some("Guide".to_string()),
+            language: None,
+            additional: Default::default(),
+        };
+
+        let mut result = ContentProcessingResult {
+            success: true,
+            status: Default::default(),
+            chunks: MarkdownProvider::new().chunk_str("# Guide\n\nIntro\n\n## Install\n\nSteps\n\n### Linux\n\napt\n\n## Usage\n\nRun it"),
+            metadata: metadata(ContentType::Markdown),
+            error: None,
+        };
+        let sections = result.chunks.len();
+
+        prepend_summary(&mut result, "doc");
+
+        assert_eq!(result.chunks.len(), sections + 1);
+        let summaries: Vec<_> = result.chunks.iter().filter(|chunk| chunk.metadata.get("chunk_type") == Some(&serde_json::json!("summary"))).collect();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(result.chunks[0].content, "# Summary: Guide\n\n- Guide\n  - Install\n    - Linux\n  - Usage");
+        assert!(!result.chunks[0].id.is_empty());
+
+        let mut result = ContentProcessingResult {
+            success: true,
+            status: Default::default(),
+            chunks: JsonProvider::new().chunk_str(r#"{"name": "app", "spec": {"replicas": 2}, "labels": ["a", "b"]}"#).unwrap(),
+            metadata: metadata(ContentType::StructuredData),
+            error: None,
+        };
+
+        prepend_summary(&mut result, "doc");
+
+        assert_eq!(result.chunks[0].content, "# Summary: Guide\n\nTop-level keys: name, spec, labels");
+    }
+
+    #[test]
+    fn test_drop_duplicate_chunks_removes_repeated_footer() {
+        use super::super::postprocess::drop_duplicate_chunks;
+        use crate::models::{ContentChunk, ContentMetadata, ContentProcessingResult, ContentType};
+
+        let paragraph = "Revenue grew in every region this quarter, led by strong demand for storage \
+                         appliances in Europe and a recovery in public sector spending across North America";
+        let contents = [
+            "Quarterly revenue grew across all regions.",
+            "ACME Corp — Confidential — Page 1",
+            "Operating costs fell for the third quarter in a row.",
+            "ACME Corp - Confidential - Page 2",
+            "acme corp  confidential  page 3",
+            paragraph,
+            &format!("{} too", paragraph),
+        ];
+        let chunks = contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| ContentChunk {
+                id: format!("c{}", i),
+                content: content.to_string(),
+                metadata: Default::default(),
+            })
+            .collect();
+        let mut result = ContentProcessingResult {
+            success: true,
+            status: Default::default(),
+            chunks,
+            metadata: ContentMetadata {
+                content_type: ContentType::Pdf,
+                file_name: None,
+                file_size: None,
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: None,
+                language: None,
+                additional: Default::default(),
+            },
+            error: None,
+        };
+
+        drop_duplicate_chunks(&mut result);
+
+        let ids: Vec<&str> = result.chunks.iter().map(|chunk| chunk.id.as_str()).collect();
+        assert_eq!(ids, vec!["c0", "c1", "c2", "c5"]);
+        assert_eq!(
+            result.metadata.additional["duplicate_chunks"],
+            serde_json::json!([
+                { "id": "c3", "duplicate_of": "c1" },
+                { "id": "c4", "duplicate_of": "c1" },
+                { "id": "c6", "duplicate_of": "c5" },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_select_for_embedding_skips_excluded_sections() {
+        use super::super::postprocess::select_for_embedding;
+        use crate::models::{ContentMetadata, ContentProcessingResult, ContentType, EmbedFilter};
+        use crate::providers::markdown::MarkdownProvider;
+
+        let chunks = MarkdownProvider::new()
+            .chunk_str("# Results\n\nThe main findings.\n\n# Appendix A\n\nRaw tables.\n\n# Appendix B\n\nReferences.");
+        let mut result = ContentProcessingResult {
+            success: true,
+            status: Default::default(),
+            chunks,
+            metadata: ContentMetadata {
+                content_type: ContentType::Markdown,
+                file_name: None,
+                file_size: None,
+                created_at: None,
+                modified_at: None,
+                author: None,
+                title: None,
+                language: None,
+                additional: Default::default(),
+            },
+            error: None,
+        };
+        let filter = EmbedFilter {
+            exclude_sections: vec!["Appendix".to_string()],
+            ..EmbedFilter::default()
+        };
+
+        let selected = select_for_embedding(&mut result, &filter);
+
+        assert_eq!(selected, vec![0]);
+        assert!(!result.chunks[0].metadata.contains_key("embedding"));
+        for chunk in &result.chunks[1..] {
+            assert_eq!(chunk.metadata["embedding"], serde_json::Value::Null);
+            assert!(chunk.metadata["embedding_skipped"].as_str().unwrap().contains("Appendix"));
+        }
+    }
+
+    #[test]
+    fn test_embed_filter_from_query() {
+        use crate::models::{ContentType, ProcessingOptions};
+
+        let options: ProcessingOptions = serde_json::from_value(serde_json::json!({
+            "embed": true,
+            "embed_filter": r#"{"min_length": 20, "content_types": ["MARKDOWN"]}"#,
+        }))
+        .unwrap();
+
+        assert!(options.embed);
+        assert_eq!(options.embed_filter.min_length, 20);
+        assert_eq!(options.embed_filter.content_types, vec![ContentType::Markdown]);
+    }
+
+    #[test]
+    fn test_chunk_strategy_from_query() {
+        use crate::models::{ChunkStrategy, ProcessingOptions};
+
+        let options: ProcessingOptions = serde_json::from_value(serde_json::json!({
+            "chunk_strategy": r#"{"type": "semantic", "similarity_threshold": 0.6}"#,
+        }))
+        .unwrap();
+        assert_eq!(options.chunk_strategy, ChunkStrategy::Semantic { similarity_threshold: 0.6 });
+
+        let options: ProcessingOptions = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(options.chunk_strategy, ChunkStrategy::Fixed);
+    }
+
+    #[test]
+    fn test_embeddings_to_arrow_ipc_round_trip() {
+        use crate::models::{EmbeddingData, EmbeddingResponse, Usage};
+        use arrow::array::{Array, FixedSizeListArray, Float32Array, StringArray, UInt64Array};
+        use arrow::ipc::reader::StreamReader;
+
+        let vectors = [vec![0.5f32, -1.0, 2.25], vec![0.0, 3.5, -0.125]];
+        let response = EmbeddingResponse {
+            object: "list".to_string(),
+            data: vectors
+                .iter()
+                .enumerate()
+                .map(|(index, embedding)| EmbeddingData {
+                    object: "embedding".to_string(),
+                    embedding: embedding.clone().into(),
+                    index,
+                })
+                .collect(),
+            model: "all-MiniLM-L6-v2".to_string(),
+            dimensions: 3,
+            usage: Usage { prompt_tokens: 7, total_tokens: 7 },
+        };
+
+        let bytes = embeddings_to_arrow_ipc(&response).unwrap();
+        let mut reader = StreamReader::try_new(std::io::Cursor::new(bytes), None).unwrap();
+        assert_eq!(reader.schema().metadata()["total_tokens"], "7");
+
+        let batch = reader.next().unwrap().unwrap();
+        assert!(reader.next().is_none());
+        assert_eq!(batch.num_rows(), 2);
+
+        let ids = batch.column_by_name("id").unwrap().as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(ids.values(), &[0, 1]);
+
+        let list = batch.column_by_name("vector").unwrap().as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+        assert_eq!(list.value_length(), 3);
+        for (row, expected) in vectors.iter().enumerate() {
+            let value = list.value(row);
+            let values = value.as_any().downcast_ref::<Float32Array>().unwrap();
+            assert_eq!(values.values(), expected.as_slice());
+        }
+
+        let models = batch.column_by_name("model").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(models.value(1), "all-MiniLM-L6-v2");
+    }
+
+    #[test]
+    fn test_embeddings_to_arrow_ipc_rejects_ragged_vectors() {
+        use crate::models::{EmbeddingData, EmbeddingResponse, Usage};
+
+        let data = [vec![1.0f32, 2.0], vec![1.0]]
+            .into_iter()
+            .enumerate()
+            .map(|(index, embedding)| EmbeddingData { object: "embedding".to_string(), embedding: embedding.into(), index })
+            .collect();
+        let response = EmbeddingResponse {
+            object: "list".to_string(),
+            data,
+            model: "test".to_string(),
+            dimensions: 2,
+            usage: Usage { prompt_tokens: 0, total_tokens: 0 },
+        };
+
+        assert!(embeddings_to_arrow_ipc(&response).is_err());
+    }
+}
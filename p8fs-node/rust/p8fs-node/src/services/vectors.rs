@@ -0,0 +1,46 @@
+use crate::models::{EmbeddingResponse, EmbeddingVector};
+
+/// Scales `vector` to unit L2 norm in place. A zero vector has no direction,
+/// so it is left unchanged rather than filled with NaNs.
+pub fn normalize_l2(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return;
+    }
+
+    for value in vector.iter_mut() {
+        *value /= norm;
+    }
+}
+
+/// Keeps the first `dimensions` values of each embedding, which for models
+/// trained with Matryoshka representation learning is itself a usable, smaller
+/// embedding. The vectors are no longer unit length afterwards.
+pub fn truncate_embeddings(response: &mut EmbeddingResponse, dimensions: usize) {
+    for data in &mut response.data {
+        if let EmbeddingVector::Float(values) = &mut data.embedding {
+            values.truncate(dimensions);
+        }
+    }
+    response.dimensions = response.dimensions.min(dimensions);
+}
+
+/// Two vectors that should be compared have different lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Cannot compare vectors of different lengths ({0} and {1})")]
+pub struct LengthMismatch(pub usize, pub usize);
+
+/// The cosine of the angle between `a` and `b`, from -1.0 to 1.0. A zero
+/// vector has no direction, so its similarity to anything is 0.0.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Result<f32, LengthMismatch> {
+    if a.len() != b.len() {
+        return Err(LengthMismatch(a.len(), b.len()));
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(dot / norms)
+}
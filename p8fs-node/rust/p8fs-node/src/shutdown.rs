@@ -0,0 +1,75 @@
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+#[cfg(test)]
+#[path = "shutdown_tests.rs"]
+mod tests;
+
+/// Resolves once the process receives SIGTERM or SIGINT.
+pub async fn signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("Could not listen for SIGINT: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                warn!("Could not listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+/// Serves `app` until `signal` resolves, then stops accepting connections and
+/// waits for in-flight requests to finish. Requests still running `timeout`
+/// after the signal are abandoned.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    signal: impl Future<Output = ()> + Send + 'static,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    let (signalled, on_signal) = oneshot::channel();
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        signal.await;
+        info!(timeout_secs = timeout.as_secs(), "Shutting down, waiting for in-flight requests");
+        let _ = signalled.send(());
+    });
+
+    let deadline = async move {
+        // The sender is only dropped unsent if the server stopped on its own
+        if on_signal.await.is_err() {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(timeout).await;
+    };
+
+    tokio::select! {
+        result = server.into_future() => {
+            result?;
+            info!("Shutdown complete");
+        }
+        _ = deadline => warn!("Requests still running after the shutdown timeout, exiting anyway"),
+    }
+
+    Ok(())
+}
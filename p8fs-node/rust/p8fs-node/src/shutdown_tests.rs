@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// A server whose `/slow` route takes `delay` to respond, with the sender
+    /// that shuts it down and the handle of the running `serve` task.
+    async fn start(
+        delay: Duration,
+        timeout: Duration,
+    ) -> (std::net::SocketAddr, oneshot::Sender<()>, tokio::task::JoinHandle<std::io::Result<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(delay).await;
+                "done"
+            }),
+        );
+
+        let (trigger, fired) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, app, async { fired.await.unwrap_or_default() }, timeout));
+        (addr, trigger, server)
+    }
+
+    async fn get_slow(addr: std::net::SocketAddr) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_in_flight_requests() {
+        let (addr, trigger, server) = start(Duration::from_millis(200), Duration::from_secs(10)).await;
+
+        let request = tokio::spawn(get_slow(addr));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        trigger.send(()).unwrap();
+
+        let response = request.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.ends_with("done"));
+        tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap().unwrap();
+
+        // No longer accepting connections
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_gives_up_after_timeout() {
+        let (addr, trigger, server) = start(Duration::from_secs(60), Duration::from_millis(100)).await;
+
+        let _request = tokio::spawn(get_slow(addr));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        trigger.send(()).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap().unwrap();
+    }
+}
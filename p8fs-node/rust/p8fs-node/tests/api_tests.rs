@@ -2,18 +2,18 @@ use axum::{
     body::Body,
     http::{self, Request, StatusCode},
 };
-use p8fs_node::{api, models::*};
+use p8fs_node::{api, models::*, Config};
 use serde_json::json;
 use tower::ServiceExt;
 
 #[tokio::test]
 async fn test_embeddings_endpoint() {
-    let app = api::create_router();
+    let app = api::create_router(Config::default());
 
-    let request_body = EmbeddingRequest {
-        text: vec!["Hello world".to_string(), "Test text".to_string()],
-        model: Some("test-model".to_string()),
-    };
+    let request_body = json!({
+        "input": ["Hello world", "Test text"],
+        "model": "all-MiniLM-L6-v2",
+    });
 
     let request = Request::builder()
         .method(http::Method::POST)
@@ -31,9 +31,35 @@ async fn test_embeddings_endpoint() {
     assert!(response.status() == StatusCode::OK || response.status() == StatusCode::INTERNAL_SERVER_ERROR);
 }
 
+#[tokio::test]
+async fn test_embeddings_endpoint_arrow_stream() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/embeddings")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .header(http::header::ACCEPT, "application/vnd.apache.arrow.stream")
+        .body(Body::from(serde_json::to_vec(&json!({ "input": ["Hello world"] })).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+
+    // Like test_embeddings_endpoint, this needs the model to succeed
+    if response.status() == StatusCode::OK {
+        assert_eq!(response.headers()[http::header::CONTENT_TYPE], "application/vnd.apache.arrow.stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let reader = arrow::ipc::reader::StreamReader::try_new(std::io::Cursor::new(body), None).unwrap();
+        let batches: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+    } else {
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
+
 #[tokio::test]
 async fn test_embeddings_endpoint_invalid_json() {
-    let app = api::create_router();
+    let app = api::create_router(Config::default());
 
     let request = Request::builder()
         .method(http::Method::POST)
@@ -48,9 +74,110 @@ async fn test_embeddings_endpoint_invalid_json() {
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn test_embeddings_endpoint_empty_input() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/embeddings")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(serde_json::to_vec(&json!({ "input": [] })).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["error"]["type"], "invalid_request_error");
+    assert_eq!(error["error"]["param"], "input");
+}
+
+#[tokio::test]
+async fn test_embeddings_endpoint_empty_string_input() {
+    let embed = |config: Config| {
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .uri("/embeddings")
+            .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from(serde_json::to_vec(&json!({ "input": ["Hello", ""] })).unwrap()))
+            .unwrap();
+        api::create_router(config).oneshot(request)
+    };
+
+    let response = embed(Config::default()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["error"]["param"], "input");
+    assert_eq!(error["error"]["message"], "'input[1]' is an empty string");
+
+    // Allowed, it reaches the model, which may or may not be available here
+    let response = embed(Config { allow_empty_input: true, ..Config::default() }).await.unwrap();
+    assert_ne!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_rerank_endpoint_requires_documents() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/rerank")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(serde_json::to_vec(&json!({ "query": "borrow checker", "documents": [] })).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["error"]["param"], "documents");
+}
+
+#[tokio::test]
+async fn test_embeddings_endpoint_unknown_model() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/embeddings")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(serde_json::to_vec(&json!({ "input": ["Hello"], "model": "no-such-model" })).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error["error"]["param"], "model");
+    assert!(error["error"]["message"].as_str().unwrap().contains("sentence-transformers/all-MiniLM-L6-v2"));
+}
+
+#[tokio::test]
+async fn test_openai_embeddings_alias() {
+    let app = axum::Router::new().nest("/v1", api::create_openai_router(Config::default()));
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/v1/embeddings")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(serde_json::to_vec(&json!({ "input": [] })).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+
+    // Reaching the handler's validation proves the alias is routed
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_content_process_endpoint_no_file() {
-    let app = api::create_router();
+    let app = api::create_router(Config::default());
 
     let request = Request::builder()
         .method(http::Method::POST)
@@ -61,13 +188,267 @@ async fn test_content_process_endpoint_no_file() {
 
     let response = app.oneshot(request).await.unwrap();
     
-    // Should return error for missing file
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    // Should return a client error for missing file
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(error["error"]["code"].is_string());
+    assert!(error["error"]["message"].is_string());
+}
+
+/// Builds a multipart body with a single `file` field.
+fn multipart_request(uri: &str, file_name: &str, content: &[u8]) -> Request<Body> {
+    let mut body = Vec::new();
+    body.extend_from_slice(b"--boundary\r\n");
+    body.extend_from_slice(
+        format!("Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\n", file_name).as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(b"\r\n--boundary--\r\n");
+
+    Request::builder()
+        .method(http::Method::POST)
+        .uri(uri)
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn error_body(response: axum::response::Response) -> serde_json::Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_content_process_missing_file_field() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/content/process")
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(
+            "--boundary\r\nContent-Disposition: form-data; name=\"other\"\r\n\r\nvalue\r\n--boundary--\r\n",
+        ))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_body(response).await["error"]["code"], "missing_file");
+}
+
+#[tokio::test]
+async fn test_content_process_unsupported_type() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "notes.xyz", b"data"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let error = error_body(response).await;
+    assert_eq!(error["error"]["code"], "unsupported_type");
+    assert!(error["error"]["message"].as_str().unwrap().contains("xyz"));
+    assert_eq!(error["error"]["rejection"]["reason"], "unsupported_extension");
+
+    let supported: Vec<&str> = error["error"]["rejection"]["supported_extensions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|extension| extension.as_str().unwrap())
+        .collect();
+    for extension in ["pdf", "json", "md"] {
+        assert!(supported.contains(&extension), "{:?}", supported);
+    }
+    assert!(!supported.contains(&"xyz"));
+}
+
+#[tokio::test]
+async fn test_content_process_disabled_type() {
+    let app = api::create_router(Config {
+        disabled_types: vec![ContentType::Markdown],
+        ..Config::default()
+    });
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "notes.md", b"# Notes"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let error = error_body(response).await;
+    assert_eq!(error["error"]["code"], "unsupported_type");
+    assert_eq!(error["error"]["rejection"]["reason"], "disabled_type");
+    assert!(error["error"]["rejection"]["detail"].as_str().unwrap().contains("Markdown"));
+}
+
+#[tokio::test]
+async fn test_content_process_too_large() {
+    let app = api::create_router(Config {
+        max_file_bytes: Some(8),
+        ..Config::default()
+    });
+
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/process", "notes.md", b"# Notes, too long"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let error = error_body(response).await;
+    assert_eq!(error["error"]["code"], "too_large");
+    assert_eq!(error["error"]["rejection"]["reason"], "too_large");
+    assert!(error["error"]["rejection"]["detail"].as_str().unwrap().contains("17 bytes"));
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "notes.md", b"# Notes"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_content_process_body_over_upload_limit() {
+    let app = api::create_router(Config {
+        max_upload_bytes: 1024,
+        ..Config::default()
+    });
+
+    let content = "# Notes\n\n".to_string() + &"Too long. ".repeat(400);
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/process", "notes.md", content.as_bytes()))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let error = error_body(response).await;
+    assert_eq!(error["error"]["code"], "too_large");
+    assert!(error["error"]["message"].as_str().unwrap().contains("P8FS_MAX_UPLOAD_BYTES"));
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "notes.md", b"# Notes"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_content_process_unknown_forced_type() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .oneshot(multipart_request("/content/process/bogus", "notes.md", b"# Notes"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_body(response).await["error"]["code"], "bad_request");
+}
+
+#[tokio::test]
+async fn test_content_process_embed_filter() {
+    let app = api::create_router(Config::default());
+    let filter = "%7B%22exclude_sections%22%3A%5B%22Appendix%22%5D%7D";
+
+    let response = app
+        .oneshot(multipart_request(
+            &format!("/content/process?embed=true&embed_filter={}", filter),
+            "report.md",
+            b"# Results\n\nThe main findings.\n\n# Appendix\n\nRaw tables.",
+        ))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert_eq!(result.chunks[1].metadata["embedding"], serde_json::Value::Null);
+    assert!(result.chunks[1].metadata.contains_key("embedding_skipped"));
+
+    // Embedding needs the model, so only check the vector when it is available
+    if !result.metadata.additional.contains_key("embedding_warning") {
+        assert_eq!(result.chunks[0].metadata["embedding"].as_array().unwrap().len(), 384);
+    }
+}
+
+#[tokio::test]
+async fn test_content_process_embed_field_toggles_embeddings() {
+    let request = |embed: &str| {
+        let body = format!(
+            concat!(
+                "--boundary\r\n",
+                "Content-Disposition: form-data; name=\"embed\"\r\n\r\n",
+                "{}",
+                "\r\n--boundary\r\n",
+                "Content-Disposition: form-data; name=\"file\"; filename=\"notes.md\"\r\n\r\n",
+                "# Notes\n\nA short note.",
+                "\r\n--boundary--\r\n",
+            ),
+            embed
+        );
+        Request::builder()
+            .method(http::Method::POST)
+            .uri("/content/process")
+            .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let app = api::create_router(Config::default());
+    let result = process_result(app.clone().oneshot(request("false")).await.unwrap()).await;
+    assert!(!result.chunks[0].metadata.contains_key("embedding"));
+    assert!(!result.metadata.additional.contains_key("embedding_warning"));
+
+    // Without the model the chunks still come back, with a warning instead of vectors
+    let result = process_result(app.oneshot(request("true")).await.unwrap()).await;
+    assert!(result.success);
+    assert_eq!(result.chunks.len(), 1);
+    match result.metadata.additional.get("embedding_warning") {
+        Some(warning) => {
+            assert!(warning.as_str().unwrap().starts_with("Chunks were not embedded"));
+            assert!(!result.chunks[0].metadata.contains_key("embedding"));
+        }
+        None => assert_eq!(result.chunks[0].metadata["embedding"].as_array().unwrap().len(), 384),
+    }
+}
+
+#[tokio::test]
+async fn test_content_embed_endpoint() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .oneshot(multipart_request(
+            "/content/embed",
+            "notes.md",
+            b"# Overview\n\nA short introduction.\n\n# Details\n\nThe specifics.",
+        ))
+        .await
+        .unwrap();
+
+    // Like test_content_process_embed_filter, this needs the model to succeed
+    if response.status() == StatusCode::OK {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: EmbeddedContentResult = serde_json::from_slice(&body).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.chunks.len(), 2);
+        assert_eq!(result.metadata.content_type, ContentType::Markdown);
+        for embedded in &result.chunks {
+            assert_eq!(embedded.embedding.len(), 384);
+            assert!(!embedded.chunk.content.is_empty());
+        }
+        assert!(result.chunks[1].chunk.content.contains("The specifics."));
+    } else {
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error_body(response).await["error"]["code"], "unavailable");
+    }
 }
 
 #[tokio::test]
 async fn test_health_check() {
-    let app = api::create_router();
+    let app = api::create_router(Config::default());
 
     let request = Request::builder()
         .method(http::Method::GET)
@@ -77,13 +458,15 @@ async fn test_health_check() {
 
     let response = app.oneshot(request).await.unwrap();
     
-    // Fallback route should return the server name
     assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(health["status"], "ok");
 }
 
 #[tokio::test]
 async fn test_not_found_route() {
-    let app = api::create_router();
+    let app = api::create_router(Config::default());
 
     let request = Request::builder()
         .method(http::Method::GET)
@@ -111,16 +494,26 @@ mod mock_tests {
     async fn mock_embeddings_handler(
         Json(request): Json<EmbeddingRequest>,
     ) -> Result<ResponseJson<EmbeddingResponse>, StatusCode> {
-        let embeddings: Vec<Vec<f32>> = request
-            .text
+        let data: Vec<EmbeddingData> = request
+            .input
             .iter()
-            .map(|_| vec![0.1, 0.2, 0.3, 0.4])
+            .enumerate()
+            .map(|(index, _)| EmbeddingData {
+                object: "embedding".to_string(),
+                embedding: vec![0.1, 0.2, 0.3, 0.4].into(),
+                index,
+            })
             .collect();
 
         Ok(ResponseJson(EmbeddingResponse {
-            embeddings,
+            object: "list".to_string(),
+            data,
             model: "mock-model".to_string(),
             dimensions: 4,
+            usage: Usage {
+                prompt_tokens: 2,
+                total_tokens: 2,
+            },
         }))
     }
 
@@ -132,10 +525,10 @@ mod mock_tests {
     async fn test_mock_embeddings_success() {
         let app = create_mock_app();
 
-        let request_body = EmbeddingRequest {
-            text: vec!["Hello".to_string(), "World".to_string()],
-            model: Some("test".to_string()),
-        };
+        let request_body = json!({
+            "input": ["Hello", "World"],
+            "model": "test",
+        });
 
         let request = Request::builder()
             .method(http::Method::POST)
@@ -148,11 +541,837 @@ mod mock_tests {
         
         assert_eq!(response.status(), StatusCode::OK);
 
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let response_data: EmbeddingResponse = serde_json::from_slice(&body).unwrap();
         
-        assert_eq!(response_data.embeddings.len(), 2);
-        assert_eq!(response_data.dimensions, 4);
+        assert_eq!(response_data.data.len(), 2);
+        assert_eq!(response_data.data[0].embedding.len(), 4);
         assert_eq!(response_data.model, "mock-model");
     }
-}
\ No newline at end of file
+}
+
+async fn process_result(response: axum::response::Response) -> ContentProcessingResult {
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_content_process_reads_fields_before_file() {
+    let app = api::create_router(Config::default());
+
+    let body = concat!(
+        "--boundary\r\n",
+        "Content-Disposition: form-data; name=\"chunk_options\"\r\n\r\n",
+        r#"{"output_format": "plain", "sentence_offsets": true}"#,
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"metadata\"\r\n\r\n",
+        r#"{"project": "p8fs"}"#,
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"content_type\"\r\n\r\n",
+        "markdown",
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"\r\n\r\n",
+        "# Notes\n\nSome **bold** text. Another sentence.",
+        "\r\n--boundary--\r\n",
+    );
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/content/process")
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(body))
+        .unwrap();
+
+    let result = process_result(app.oneshot(request).await.unwrap()).await;
+
+    assert_eq!(result.metadata.content_type, ContentType::Markdown);
+    assert_eq!(result.metadata.additional["user_metadata"], json!({ "project": "p8fs" }));
+    assert_eq!(result.chunks[0].content, "Notes\n\nSome bold text. Another sentence.");
+    assert_eq!(result.chunks[0].metadata["sentences"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_content_process_accepts_any_file_field_name() {
+    let app = api::create_router(Config::default());
+
+    let body = concat!(
+        "--boundary\r\n",
+        "Content-Disposition: form-data; name=\"summary_chunk\"\r\n\r\n",
+        "true",
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"output_format\"\r\n\r\n",
+        "plain",
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"note\"\r\n\r\n",
+        "not an option",
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"document\"; filename=\"notes.md\"\r\n\r\n",
+        "# Notes\n\nSome **bold** text.",
+        "\r\n--boundary--\r\n",
+    );
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/content/process")
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(body))
+        .unwrap();
+
+    let result = process_result(app.oneshot(request).await.unwrap()).await;
+
+    assert_eq!(result.metadata.content_type, ContentType::Markdown);
+    assert_eq!(result.metadata.file_name.as_deref(), Some("notes.md"));
+    assert_eq!(result.chunks[0].metadata["chunk_type"], "summary");
+    assert_eq!(result.chunks.last().unwrap().content, "Notes\n\nSome bold text.");
+}
+
+#[tokio::test]
+async fn test_content_process_rejects_invalid_chunk_options() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/content/process")
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(concat!(
+            "--boundary\r\n",
+            "Content-Disposition: form-data; name=\"chunk_options\"\r\n\r\n",
+            r#"{"output_format": "pdf"}"#,
+            "\r\n--boundary\r\n",
+            "Content-Disposition: form-data; name=\"file\"; filename=\"notes.md\"\r\n\r\n",
+            "# Notes",
+            "\r\n--boundary--\r\n",
+        )))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(error_body(response).await["error"]["message"].as_str().unwrap().contains("chunk_options"));
+}
+
+#[tokio::test]
+async fn test_content_process_fixed_width_with_columns() {
+    let app = api::create_router(Config::default());
+    // columns=[{"name":"id","width":4},{"name":"name","width":8}]
+    let columns = "%5B%7B%22name%22%3A%22id%22%2C%22width%22%3A4%7D%2C%7B%22name%22%3A%22name%22%2C%22width%22%3A8%7D%5D";
+
+    let response = app
+        .oneshot(multipart_request(
+            &format!("/content/process/fixedwidth?columns={}", columns),
+            "export.dat",
+            b"0001Alice   \n0002Bob     \n",
+        ))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert_eq!(result.metadata.content_type, ContentType::FixedWidth);
+    assert!(result.chunks[0].content.contains("| 0002 | Bob |"));
+}
+
+#[tokio::test]
+async fn test_content_process_returns_only_changed_chunks() {
+    let original = "# One\n\nFirst section.\n\n# Two\n\nSecond section.\n\n# Three\n\nThird section.";
+    let edited = "# One\n\nFirst section.\n\n# Two\n\nSecond section, edited.\n\n# Three\n\nThird section.";
+
+    let response = api::create_router(Config::default())
+        .oneshot(multipart_request("/content/process", "doc.md", original.as_bytes()))
+        .await
+        .unwrap();
+    let first = process_result(response).await;
+    assert_eq!(first.chunks.len(), 3);
+    let prior: Vec<_> = first
+        .chunks
+        .iter()
+        .map(|chunk| json!({ "id": chunk.id, "content_hash": chunk.metadata["content_hash"] }))
+        .collect();
+
+    let body = format!(
+        "--boundary\r\nContent-Disposition: form-data; name=\"prior_chunks\"\r\n\r\n{}\r\n\
+         --boundary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"doc.md\"\r\n\r\n{}\r\n--boundary--\r\n",
+        json!(prior),
+        edited
+    );
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/content/process")
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(body))
+        .unwrap();
+    let second = process_result(api::create_router(Config::default()).oneshot(request).await.unwrap()).await;
+
+    // Only the edited section comes back; its siblings are reported as unchanged
+    assert_eq!(second.chunks.len(), 1);
+    assert_eq!(second.chunks[0].metadata["section_title"], "Two");
+    assert!(second.chunks[0].content.contains("edited"));
+    assert_eq!(second.metadata.additional["deleted_chunk_ids"], json!([first.chunks[1].id]));
+    assert_eq!(
+        second.metadata.additional["unchanged_chunk_ids"],
+        json!([first.chunks[0].id, first.chunks[2].id])
+    );
+}
+
+#[tokio::test]
+async fn test_content_process_concurrent_same_filename() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let app = api::create_router(Config {
+        temp_dir: temp_dir.path().to_path_buf(),
+        in_memory_max_bytes: 0,
+        ..Config::default()
+    });
+
+    let first = app
+        .clone()
+        .oneshot(multipart_request("/content/process", "a.md", b"# First\n\nAlpha body"));
+    let second = app
+        .clone()
+        .oneshot(multipart_request("/content/process", "a.md", b"# Second\n\nBeta body"));
+    let (first, second) = tokio::join!(first, second);
+
+    let first = process_result(first.unwrap()).await;
+    let second = process_result(second.unwrap()).await;
+
+    assert!(first.chunks.iter().any(|c| c.content.contains("Alpha body")));
+    assert!(first.chunks.iter().all(|c| !c.content.contains("Beta body")));
+    assert!(second.chunks.iter().any(|c| c.content.contains("Beta body")));
+    assert!(second.chunks.iter().all(|c| !c.content.contains("Alpha body")));
+    assert_eq!(first.metadata.file_name.as_deref(), Some("a.md"));
+    assert_eq!(second.metadata.file_name.as_deref(), Some("a.md"));
+
+    // Staged uploads are removed once processing finishes
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_content_process_filename_traversal_is_sanitized() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let app = api::create_router(Config {
+        temp_dir: temp_dir.path().to_path_buf(),
+        in_memory_max_bytes: 0,
+        ..Config::default()
+    });
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "../../etc/notes.md", b"# Notes\n\nBody"))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert_eq!(result.metadata.file_name.as_deref(), Some("notes.md"));
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_content_process_cleans_up_on_provider_error() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let app = api::create_router(Config {
+        temp_dir: temp_dir.path().to_path_buf(),
+        in_memory_max_bytes: 0,
+        ..Config::default()
+    });
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "broken.json", b"{not json"))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert!(!result.success);
+    assert_eq!(result.status, ProcessingStatus::Failed);
+    assert!(result.error.is_some());
+    assert_eq!(result.metadata.file_name.as_deref(), Some("broken.json"));
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_content_process_with_type_cleans_up_on_corrupt_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let app = api::create_router(Config {
+        temp_dir: temp_dir.path().to_path_buf(),
+        in_memory_max_bytes: 0,
+        ..Config::default()
+    });
+
+    let response = app
+        .oneshot(multipart_request("/content/process/pdf", "corrupt.pdf", b"%PDF-1.7\nnot really a pdf"))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert!(!result.success);
+    assert_eq!(result.metadata.content_type, ContentType::Pdf);
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_content_process_small_text_upload_stays_in_memory() {
+    // Staging anything under a directory that doesn't exist would fail the request
+    let temp_dir = tempfile::tempdir().unwrap();
+    let app = api::create_router(Config {
+        temp_dir: temp_dir.path().join("missing"),
+        in_memory_max_bytes: 64,
+        ..Config::default()
+    });
+
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/process", "small.json", br#"{"name": "in memory"}"#))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert!(result.chunks.iter().any(|c| c.content.contains("in memory")));
+    assert_eq!(result.metadata.file_name.as_deref(), Some("small.json"));
+    assert_eq!(result.metadata.file_size, Some(21));
+
+    // Larger uploads still go through a temp file
+    let large = format!(r#"{{"name": "{}"}}"#, "x".repeat(64));
+    let response = app
+        .oneshot(multipart_request("/content/process", "large.json", large.as_bytes()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_content_process_summary_chunk() {
+    let app = api::create_router(Config::default());
+    let markdown = b"# Handbook\n\nWelcome.\n\n## Onboarding\n\nDay one.\n\n## Benefits\n\nHealth.";
+
+    let response = app
+        .oneshot(multipart_request("/content/process?summary_chunk=true", "handbook.md", markdown))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let result: ContentProcessingResult = serde_json::from_slice(&body).unwrap();
+
+    let summaries: Vec<_> = result.chunks.iter().filter(|chunk| chunk.metadata.get("chunk_type") == Some(&json!("summary"))).collect();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(result.chunks[0].metadata["chunk_type"], "summary");
+    assert!(result.chunks[0].content.contains("- Handbook\n  - Onboarding\n  - Benefits"));
+    assert_eq!(result.chunks.len(), 4);
+}
+
+#[tokio::test]
+async fn test_content_process_json_over_depth_limit_truncates() {
+    let app = api::create_router(Config::default());
+    let deep = format!("{}1{}", "[".repeat(100), "]".repeat(100));
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "deep.json", deep.as_bytes()))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let result: ContentProcessingResult = serde_json::from_slice(&body).unwrap();
+    assert_eq!(result.chunks.len(), 1);
+    assert_eq!(result.chunks[0].metadata["truncated_depth"], true);
+}
+
+#[tokio::test]
+async fn test_content_process_sniffs_unknown_extension() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "export.dat", br#"{"name": "sniffed"}"#))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert!(matches!(result.metadata.content_type, ContentType::StructuredData));
+    assert!(result.chunks.iter().any(|c| c.content.contains("sniffed")));
+}
+
+#[tokio::test]
+async fn test_content_process_group_by_kind_per_request() {
+    let app = api::create_router(Config::default());
+    let manifests = b"kind: Service\nname: web\n---\nkind: Deployment\nname: web\n---\nkind: Service\nname: db\n";
+
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/process?group_by_kind=true&kind_summaries=true", "stack.yaml", manifests))
+        .await
+        .unwrap();
+    let grouped = process_result(response).await;
+
+    let groups: Vec<_> = grouped.chunks.iter().map(|c| c.metadata["kind_group"].clone()).collect();
+    assert_eq!(groups, vec!["Service", "Service", "Service", "Deployment", "Deployment"]);
+    assert_eq!(grouped.chunks[0].metadata["summary"], true);
+    assert_eq!(grouped.chunks[3].metadata["summary"], true);
+
+    // The option applies to that request only
+    let response = app
+        .oneshot(multipart_request("/content/process", "stack.yaml", manifests))
+        .await
+        .unwrap();
+    let ungrouped = process_result(response).await;
+    assert_eq!(ungrouped.chunks.len(), 3);
+    assert!(ungrouped.chunks.iter().all(|c| !c.metadata.contains_key("kind_group")));
+}
+
+#[tokio::test]
+async fn test_content_process_forced_code_picks_language_from_extension() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/process/code", "app.rs", b"fn main() {\n    run();\n}\n"))
+        .await
+        .unwrap();
+    let result = process_result(response).await;
+    assert_eq!(result.metadata.content_type, ContentType::Code);
+    assert_eq!(result.metadata.language.as_deref(), Some("rust"));
+    assert_eq!(result.chunks[0].metadata["symbol_name"], "main");
+
+    // SQL keeps its own provider
+    let response = app
+        .oneshot(multipart_request("/content/process", "schema.sql", b"CREATE TABLE users (id INT);"))
+        .await
+        .unwrap();
+    let result = process_result(response).await;
+    assert_eq!(result.chunks[0].metadata["source"], "sql");
+}
+
+#[tokio::test]
+async fn test_content_process_gzipped_json() {
+    use std::io::Write;
+
+    let app = api::create_router(Config::default());
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(br#"{"name": "from the lake", "tags": ["a", "b"]}"#).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "records.json.gz", &gzipped))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert!(result.success);
+    assert!(matches!(result.metadata.content_type, ContentType::StructuredData));
+    assert_eq!(result.metadata.file_name.as_deref(), Some("records.json"));
+    assert!(result.chunks.iter().any(|c| c.content.contains("from the lake")));
+    assert_eq!(result.chunks[0].metadata["source"], "json");
+}
+
+#[tokio::test]
+async fn test_content_process_gzip_over_max_file_bytes_when_decompressed() {
+    use std::io::Write;
+
+    let app = api::create_router(Config {
+        max_file_bytes: Some(1024),
+        ..Config::default()
+    });
+    // Well under the limit compressed, four times over it decompressed
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&b"# Notes\n".repeat(512)).unwrap();
+    let gzipped = encoder.finish().unwrap();
+    assert!(gzipped.len() < 1024);
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "notes.md.gz", &gzipped))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let error = error_body(response).await;
+    assert_eq!(error["error"]["rejection"]["reason"], "too_large");
+    assert!(error["error"]["rejection"]["detail"].as_str().unwrap().contains("1024 bytes"));
+}
+
+#[tokio::test]
+async fn test_content_process_corrupt_gzip_is_bad_request() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "records.json.gz", b"not gzip at all"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_body(response).await["error"]["code"], "bad_request");
+}
+
+/// Polls a job until it leaves `Pending`/`Running`, giving up after ~2 seconds.
+async fn poll_job(app: &axum::Router, mut job: JobState) -> JobState {
+    for _ in 0..100 {
+        if matches!(job.status, JobStatus::Done | JobStatus::Failed) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        let request = Request::builder()
+            .uri(format!("/content/jobs/{}", job.job_id))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        job = serde_json::from_slice(&body).unwrap();
+    }
+    job
+}
+
+async fn submit_job(app: &axum::Router, file_name: &str, content: &[u8]) -> JobState {
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/jobs", file_name, content))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_content_job_submit_and_poll() {
+    let app = api::create_router(Config::default());
+
+    let job = submit_job(&app, "notes.md", b"# Notes\n\nQueued body").await;
+    assert!(job.result.is_none());
+
+    let job = poll_job(&app, job).await;
+
+    assert_eq!(job.status, JobStatus::Done);
+    let result = job.result.expect("finished job should carry its result");
+    assert!(result.chunks.iter().any(|c| c.content.contains("Queued body")));
+    assert_eq!(result.metadata.file_name.as_deref(), Some("notes.md"));
+}
+
+#[tokio::test]
+async fn test_content_job_failure_is_reported() {
+    let app = api::create_router(Config::default());
+
+    let job = submit_job(&app, "broken.json", b"{not json").await;
+    let job = poll_job(&app, job).await;
+
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.error.is_some());
+    assert!(job.result.is_some_and(|result| !result.success));
+}
+
+#[tokio::test]
+async fn test_content_job_unknown_id() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .uri("/content/jobs/00000000-0000-4000-8000-000000000000")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(error_body(response).await["error"]["code"], "not_found");
+}
+
+#[tokio::test]
+async fn test_content_job_events_report_progress_then_done() {
+    let app = api::create_router(Config::default());
+    let job = submit_job(&app, "notes.md", b"# One\n\nFirst.\n\n# Two\n\nSecond.").await;
+
+    let request = Request::builder()
+        .uri(format!("/content/jobs/{}/events", job.job_id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    // The stream ends after the `done` event
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let events: Vec<(String, serde_json::Value)> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .split("\n\n")
+        .filter_map(|event| {
+            let field = |name: &str| event.lines().find_map(|line| line.strip_prefix(name));
+            Some((field("event:")?.trim().to_string(), serde_json::from_str(field("data:")?).unwrap()))
+        })
+        .collect();
+
+    let (last, progress) = events.split_last().unwrap();
+    assert_eq!(last.0, "done");
+    assert_eq!(last.1["status"], "Done");
+    assert!(!progress.is_empty());
+    assert!(progress.iter().all(|(name, _)| name == "progress"));
+    let (_, final_progress) = progress.last().unwrap();
+    assert_eq!(final_progress["phase"], "complete");
+    assert_eq!(final_progress["processed_chunks"], 2);
+    assert_eq!(final_progress["total_estimate"], 2);
+}
+
+#[tokio::test]
+async fn test_content_job_events_unknown_id() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .uri("/content/jobs/00000000-0000-4000-8000-000000000000/events")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Serves `app` on a local port and reads every message of a
+/// `/content/stream` session, sending `upload` first when given.
+async fn stream_messages(app: axum::Router, query: &str, upload: Option<&[u8]>) -> Vec<serde_json::Value> {
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let url = format!("ws://{}/content/stream?{}", addr, query);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    if let Some(upload) = upload {
+        socket.send(Message::Binary(upload.to_vec())).await.unwrap();
+    }
+
+    let mut messages = Vec::new();
+    while let Some(message) = socket.next().await {
+        match message.unwrap() {
+            Message::Text(text) => messages.push(serde_json::from_str(&text).unwrap()),
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    messages
+}
+
+#[tokio::test]
+async fn test_content_stream_sends_chunks_then_metadata() {
+    let app = api::create_router(Config::default());
+    let markdown = b"# One\n\nFirst section.\n\n# Two\n\nSecond section.\n\n# Three\n\nThird section.";
+
+    let messages = stream_messages(app, "file_name=notes.md", Some(markdown)).await;
+
+    let chunks: Vec<_> = messages.iter().filter(|m| m["type"] == "chunk").collect();
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks[1]["chunk"]["content"].as_str().unwrap().contains("Second section"));
+    assert_eq!(messages.len(), chunks.len() + 1);
+    let last = messages.last().unwrap();
+    assert_eq!(last["type"], "metadata");
+    assert_eq!(last["metadata"]["file_name"], "notes.md");
+}
+
+#[tokio::test]
+async fn test_content_stream_applies_processing_options() {
+    let app = api::create_router(Config::default());
+    let markdown = b"# One\n\nThe same footer text on every page.\n\n# One\n\nThe same footer text on every page.\n\n# Two\n\nSomething else.";
+
+    let messages = stream_messages(app, "file_name=notes.md&dedupe=true&output_format=plain", Some(markdown)).await;
+
+    let chunks: Vec<_> = messages.iter().filter(|m| m["type"] == "chunk").collect();
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks.iter().all(|c| !c["chunk"]["content"].as_str().unwrap().contains('#')));
+    let metadata = &messages.last().unwrap()["metadata"];
+    assert_eq!(metadata["additional"]["duplicate_chunks"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_content_stream_below_minimum_size_sends_no_chunks() {
+    let app = api::create_router(Config {
+        min_file_bytes: 100,
+        ..Config::default()
+    });
+
+    // Not valid JSON, so this would fail if the provider ran
+    let messages = stream_messages(app, "file_name=stub.json", Some(b"{x}")).await;
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["type"], "metadata");
+    assert_eq!(messages[0]["metadata"]["file_size"], 3);
+}
+
+#[tokio::test]
+async fn test_content_stream_replays_job() {
+    let app = api::create_router(Config::default());
+    let job = submit_job(&app, "notes.md", b"# Notes\n\nQueued body").await;
+
+    let messages = stream_messages(app, &format!("job_id={}", job.job_id), None).await;
+
+    assert_eq!(messages.first().unwrap()["type"], "chunk");
+    assert_eq!(messages.last().unwrap()["type"], "metadata");
+}
+
+#[tokio::test]
+async fn test_content_stream_unsupported_file_is_an_error_message() {
+    let app = api::create_router(Config::default());
+
+    let messages = stream_messages(app, "file_name=data.unknownext", Some(b"\x00\x01\x02")).await;
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["type"], "error");
+    assert_eq!(messages[0]["error"]["code"], "unsupported_type");
+}
+
+#[tokio::test]
+async fn test_content_process_below_minimum_size_is_empty() {
+    let app = api::create_router(Config {
+        min_file_bytes: 100,
+        ..Config::default()
+    });
+
+    // Not valid JSON, so this would fail if the provider ran
+    let response = app
+        .clone()
+        .oneshot(multipart_request("/content/process", "stub.json", b"{x}"))
+        .await
+        .unwrap();
+
+    let result = process_result(response).await;
+    assert_eq!(result.status, ProcessingStatus::Empty);
+    assert!(result.chunks.is_empty());
+    assert_eq!(result.metadata.file_size, Some(3));
+    assert!(matches!(result.metadata.content_type, ContentType::StructuredData));
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "notes.md", "# Notes\n\n".repeat(20).as_bytes()))
+        .await
+        .unwrap();
+    assert_eq!(process_result(response).await.status, ProcessingStatus::Processed);
+}
+
+#[tokio::test]
+async fn test_content_process_zero_byte_upload_is_rejected() {
+    let app = api::create_router(Config {
+        min_file_bytes: 100,
+        ..Config::default()
+    });
+
+    let response = app
+        .oneshot(multipart_request("/content/process", "empty.md", b""))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error = error_body(response).await;
+    assert_eq!(error["error"]["code"], "empty_file");
+    assert_eq!(error["error"]["rejection"]["reason"], "empty");
+}
+
+#[tokio::test]
+async fn test_content_process_batch_of_files() {
+    let app = api::create_router(Config::default());
+
+    let body = concat!(
+        "--boundary\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"notes.md\"\r\n\r\n",
+        "# Notes\n\nSome text.",
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"config.json\"\r\n\r\n",
+        r#"{"kind": "Config", "name": "p8fs"}"#,
+        "\r\n--boundary\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"data.xyz\"\r\n\r\n",
+        "not a known type",
+        "\r\n--boundary--\r\n",
+    );
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/content/process")
+        .header(http::header::CONTENT_TYPE, "multipart/form-data; boundary=boundary")
+        .body(Body::from(body))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let results: Vec<ContentProcessingResult> = serde_json::from_slice(&body).unwrap();
+
+    let names: Vec<_> = results.iter().map(|r| r.metadata.file_name.as_deref().unwrap()).collect();
+    assert_eq!(names, vec!["notes.md", "config.json", "data.xyz"]);
+
+    assert!(results[0].success);
+    assert_eq!(results[0].metadata.content_type, ContentType::Markdown);
+    assert!(!results[0].chunks.is_empty());
+    assert!(results[1].success);
+    assert_eq!(results[1].metadata.content_type, ContentType::StructuredData);
+    assert!(!results[1].chunks.is_empty());
+
+    assert!(!results[2].success);
+    assert_eq!(results[2].status, ProcessingStatus::Failed);
+    assert_eq!(results[2].metadata.additional["error_code"], "unsupported_type");
+    assert!(results[2].error.as_deref().unwrap().contains("xyz"));
+}
+
+async fn scrape_metrics(app: &axum::Router) -> String {
+    let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// The value of the sample that starts with `prefix`, or 0 when there is none yet.
+fn sample(metrics: &str, prefix: &str) -> f64 {
+    metrics
+        .lines()
+        .find(|line| line.starts_with(prefix))
+        .and_then(|line| line.rsplit(' ').next())
+        .map_or(0.0, |value| value.parse().unwrap())
+}
+
+#[tokio::test]
+async fn test_metrics_counts_requests() {
+    let app = api::create_router(Config::default());
+    let counter = r#"p8fs_http_requests_total{endpoint="/embeddings",method="POST",status="400"}"#;
+
+    // Metrics are process-wide and other tests run alongside, so compare
+    // against a baseline rather than expecting exact values
+    let before = sample(&scrape_metrics(&app).await, counter);
+
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/embeddings")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(r#"{"input": []}"#))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let metrics = scrape_metrics(&app).await;
+    assert!(sample(&metrics, counter) >= before + 1.0, "{}", metrics);
+    assert!(metrics.contains("p8fs_http_request_duration_seconds_count{endpoint=\"/embeddings\"}"));
+}
+
+#[tokio::test]
+async fn test_content_metadata_without_chunks() {
+    let app = api::create_router(Config::default());
+
+    let response = app
+        .oneshot(multipart_request("/content/metadata", "notes.md", b"# Release Notes\n\nFirst line.\n\n## Fixes\n\nMore."))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(value.get("chunks").is_none());
+
+    let metadata: ContentMetadata = serde_json::from_value(value).unwrap();
+    assert_eq!(metadata.content_type, ContentType::Markdown);
+    assert_eq!(metadata.title.as_deref(), Some("Release Notes"));
+    assert_eq!(metadata.file_name.as_deref(), Some("notes.md"));
+}
+
+#[tokio::test]
+async fn test_content_types_lists_extensions() {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder().uri("/content/types").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let types: Vec<SupportedType> = serde_json::from_slice(&body).unwrap();
+
+    let markdown = types.iter().find(|t| t.content_type == ContentType::Markdown).unwrap();
+    assert_eq!(markdown.extensions, vec!["md", "markdown"]);
+    let code = types.iter().find(|t| t.content_type == ContentType::Code).unwrap();
+    assert!(code.extensions.contains(&"sql".to_string()) && code.extensions.contains(&"rs".to_string()));
+}
@@ -0,0 +1,31 @@
+//! Kept apart from the other tests because it points `EMBEDDING_MODEL` at a
+//! model that does not exist, which would break any test loading the real one.
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+};
+use p8fs_node::{api, services::EmbeddingService, Config};
+use serde_json::json;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn test_failed_model_load_returns_503() {
+    std::env::set_var("EMBEDDING_MODEL", "p8fs-test/bogus-model-that-does-not-exist");
+    // The load can never succeed, so don't wait on retries
+    std::env::set_var("EMBEDDING_LOAD_RETRIES", "0");
+
+    assert!(EmbeddingService::global().await.is_err());
+    assert!(EmbeddingService::try_global().is_none());
+
+    let app = api::create_router(Config::default());
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/embeddings")
+        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(serde_json::to_vec(&json!({ "input": ["Hello world"] })).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -108,6 +108,10 @@ async fn test_all_supported_extensions() {
         ("json", ContentType::StructuredData),
         ("md", ContentType::Markdown),
         ("markdown", ContentType::Markdown),
+        ("sql", ContentType::Code),
+        ("ddl", ContentType::Code),
+        ("yaml", ContentType::Yaml),
+        ("yml", ContentType::Yaml),
     ];
     
     for (ext, expected_type) in supported_extensions {
@@ -0,0 +1,39 @@
+//! Kept apart from `api_tests` because readiness depends on the process-wide
+//! embedding service, which the embedding tests there initialize.
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+};
+use p8fs_node::{api, services::EmbeddingService, Config};
+use tower::ServiceExt;
+
+async fn get_ready() -> (StatusCode, serde_json::Value) {
+    let app = api::create_router(Config::default());
+
+    let request = Request::builder()
+        .method(http::Method::GET)
+        .uri("/ready")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_ready_after_model_loads() {
+    let (status, body) = get_ready().await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "loading");
+
+    // Requires the model to be downloadable, like the embedding service tests
+    EmbeddingService::global().await.unwrap();
+
+    let (status, body) = get_ready().await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["model"], "all-MiniLM-L6-v2");
+    assert_eq!(body["dimensions"], 384);
+}